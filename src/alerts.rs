@@ -1,17 +1,37 @@
+use crate::config::{redact_secrets, MaintenanceWindow};
+use crate::email::EmailClient;
+use crate::message_bus::MessageBusClient;
+use crate::teams::TeamsClient;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
-use serde::Deserialize;
-use std::thread;
-use std::time::{Duration, SystemTime};
+use reqwest::Client;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::error::TryRecvError::{Disconnected, Empty};
 use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::Notify;
 
 static MIN_DURATION_FROM_START_TO_ERR: Duration = Duration::from_millis(60 * 60 * 1000);
 static THREAD_CONNECTIONS_ERR: &str = "Connections to the alerts thread have all closed.";
 static POLL_DURATION: Duration = Duration::from_millis(1000);
+static MAX_MAINTENANCE_SUMMARY_ALERTS: usize = 20;
+static LIFECYCLE_DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+static SNOOZE_FILE_READ_INTERVAL: Duration = Duration::from_secs(30);
+#[cfg(not(test))]
+static SINK_TIMEOUT: Duration = Duration::from_secs(60);
+// short enough for tests to wait out a sink that never answers
+#[cfg(test)]
+static SINK_TIMEOUT: Duration = Duration::from_millis(500);
 
-#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum AlertLevel {
     None,
     Info,
@@ -19,49 +39,330 @@ pub enum AlertLevel {
     Error,
 }
 
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AlertType {
+    Heartbeat,
+    Lifecycle,
+    StartupSummary,
+    MaintenanceSummary,
+    CycleEnd,
+    CycleSummary,
+    ClockSkew,
+    FuelWatcherStatus,
+    FuelWatcherThreadFailure,
+    FuelConnection,
+    FuelBlockProduction,
+    FuelBlockProductionStopped,
+    FuelBlockHeightStalled,
+    FuelPortalWithdraw,
+    FuelGatewayWithdraw,
+    LargeSingleWithdrawal,
+    EthereumWatcherStatus,
+    EthereumWatcherThreadFailure,
+    EthereumConnection,
+    EthereumBlockProduction,
+    EthereumBlockProductionStopped,
+    EthereumAccountFunds,
+    EthereumAccountFundsRunway,
+    EthereumAccountFundsHigh,
+    EthereumAccountFundsChange,
+    UnexpectedWalletActivity,
+    EthereumInvalidStateCommit,
+    EthereumCommitStalled,
+    EthereumCommitCadence,
+    EthereumFinalityLag,
+    EthereumGasSpike,
+    StaleRpcNode,
+    RpcDisagreement,
+    SuspiciousEmptyLogs,
+    OwnershipChanged,
+    EthereumPortalDeposit,
+    EthereumGatewayDeposit,
+    EthereumPortalImbalance,
+    EthereumGatewayImbalance,
+    SupplyMismatch,
+    UnexpectedToken,
+    TokenNameMismatch,
+    BridgeInactive,
+    EthereumActionsThreadFailure,
+    EthereumActionsQueueOverflow,
+    EthereumActionsDisabledNoWallet,
+    EthereumPausePermissionMissing,
+    EthereumPauseState,
+    EthereumPauseGateway,
+    EthereumPausePortal,
+    EthereumPauseAll,
+}
+
+// the chain an alert relates to (alerts of the same type on different chains are deduplicated separately)
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Chain {
+    None,
+    Fuel,
+    Ethereum,
+}
+
+impl AlertType {
+    // gets the chain the alert relates to by default (alert types shared by both watchers have no chain)
+    pub fn chain(&self) -> Chain {
+        match self {
+            AlertType::Heartbeat
+            | AlertType::Lifecycle
+            | AlertType::StartupSummary
+            | AlertType::MaintenanceSummary
+            | AlertType::CycleEnd
+            | AlertType::CycleSummary
+            | AlertType::ClockSkew => Chain::None,
+            AlertType::FuelWatcherStatus
+            | AlertType::FuelWatcherThreadFailure
+            | AlertType::FuelConnection
+            | AlertType::FuelBlockProduction
+            | AlertType::FuelBlockProductionStopped
+            | AlertType::FuelBlockHeightStalled
+            | AlertType::FuelPortalWithdraw
+            | AlertType::FuelGatewayWithdraw
+            | AlertType::LargeSingleWithdrawal => Chain::Fuel,
+            _ => Chain::Ethereum,
+        }
+    }
+}
+
+// the result of running a single check (any alerts and actions are still sent through their channels)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CheckOutcome {
+    Ok,
+    Alerted(AlertType),
+    Failed(String),
+}
+
+// an alert as it was logged (after redaction)
+#[derive(Clone, Debug)]
+pub struct AlertRecord {
+    pub text: String,
+    pub level: AlertLevel,
+    pub alert_type: AlertType,
+    pub time: SystemTime,
+}
+
 #[derive(Clone, Debug)]
 pub struct WatchtowerAlerts {
     alert_sender: UnboundedSender<AlertParams>,
+    history: Arc<Mutex<VecDeque<AlertRecord>>>,
+    failure_counts: Arc<Mutex<HashMap<(AlertType, String), u32>>>,
+    cycle_id: Option<u64>,
 }
 
-// TODO: buffer message alerts to avoid duplicates
-
 impl WatchtowerAlerts {
     pub fn new(config: &WatchtowerConfig) -> Result<Self> {
         let start = SystemTime::now();
 
-        // TODO: setup connection with alert messaging
+        // setup connections with alert messaging
+        let teams_client = match &config.teams_webhook_url {
+            Some(url) => Some(TeamsClient::new(
+                config.http_client(Some(Duration::from_secs(config.alert_timeout_secs)))?,
+                url,
+                &config.system_name,
+                &config.get_source_label(),
+                config.alert_component.as_deref(),
+                config.alert_retries,
+            )),
+            None => None,
+        };
+        let email_client = EmailClient::from_config(config)?;
+        let message_bus = MessageBusClient::from_config(config)?;
+        let min_notify_level = config.min_notify_level.clone();
 
         // start handler thread for alert function
         let (tx, mut rx) = mpsc::unbounded_channel::<AlertParams>();
+        let heartbeat_url = config.heartbeat_url.clone();
+        let duplicate_alert_delay = Duration::from_secs(config.duplicate_alert_delay as u64);
+        let info_log_interval = Duration::from_secs(config.info_log_interval_secs as u64);
+        let aggregate_per_cycle = config.aggregate_per_cycle;
+        let secrets = config.secrets();
+        let deployment_name = config.deployment_name.clone();
+        let history: Arc<Mutex<VecDeque<AlertRecord>>> = Arc::new(Mutex::new(VecDeque::new()));
+        let history_size = config.alert_history_size;
+        let thread_history = history.clone();
+        let maintenance_windows = config.maintenance_windows.clone();
+        let snooze_file = config.snooze_file.clone();
+        let client = config.http_client(None)?;
         tokio::spawn(async move {
+            let mut alert_cache: HashMap<AlertKey, SystemTime> = HashMap::new();
+            let mut info_log_cache: HashMap<String, SystemTime> = HashMap::new();
+            let mut in_maintenance = false;
+            let mut suppressed_alerts: Vec<AlertParams> = vec![];
+            let mut cycle_alerts: HashMap<Chain, Vec<AlertParams>> = HashMap::new();
+            let mut snoozes: HashMap<AlertType, u64> = HashMap::new();
+            let mut last_snooze_read: Option<SystemTime> = None;
             loop {
+                // pick up changes to the snoozed alert types every so often
+                match &snooze_file {
+                    Some(path) => {
+                        let read_due = match last_snooze_read {
+                            Some(last_read) => match SystemTime::now().duration_since(last_read) {
+                                Ok(elapsed) => elapsed >= SNOOZE_FILE_READ_INTERVAL,
+                                Err(_) => true,
+                            },
+                            None => true,
+                        };
+                        if read_due {
+                            match read_snooze_file(path) {
+                                Ok(read_snoozes) => snoozes = read_snoozes,
+                                Err(e) => log::warn!("Failed to read snooze file {path}: {e}"),
+                            }
+                            last_snooze_read = Some(SystemTime::now());
+                        }
+                    }
+                    None => {}
+                }
+
+                // summarize what was held back once a maintenance window ends
+                let now_in_maintenance = is_in_maintenance_window(&maintenance_windows, SystemTime::now());
+                if in_maintenance && !now_in_maintenance {
+                    let params = get_maintenance_summary(&suppressed_alerts);
+                    log::info!("{}", params.text);
+                    send_alert(&teams_client, &email_client, &message_bus, &params, &secrets).await;
+                    suppressed_alerts.clear();
+                }
+                in_maintenance = now_in_maintenance;
+
                 let received_result = rx.try_recv();
                 match received_result {
-                    Ok(params) => {
+                    Ok(mut params) => {
+                        // a watcher finished its cycle, so push what was held back from it as one notification
+                        if params.alert_type == AlertType::CycleEnd {
+                            match cycle_alerts.remove(&params.chain) {
+                                Some(held_alerts) => {
+                                    let summary = get_cycle_summary(held_alerts, params.chain, params.cycle_id);
+                                    send_alert(&teams_client, &email_client, &message_bus, &summary, &secrets).await;
+                                }
+                                None => {}
+                            }
+                            continue;
+                        }
+
+                        // make sure no secrets leak out through logs or alerts
+                        params.text = redact_secrets(&params.text, &secrets);
+                        params.context = params.context.take().map(|context| redact_context(context, &secrets));
+
+                        // tag alerts with the deployment they came from (when monitoring more than one)
+                        match &deployment_name {
+                            Some(name) => params.text = format!("[{name}] {}", params.text),
+                            None => {}
+                        }
+
+                        // carry the poll cycle the alert was raised in so it can be matched up with that cycle's logs
+                        let log_text = match params.cycle_id {
+                            Some(cycle_id) => {
+                                params.context = Some(add_cycle_id(params.context.take(), cycle_id));
+                                format!("[{:?} cycle {cycle_id}] {}", params.chain, params.text)
+                            }
+                            None => params.text.clone(),
+                        };
+
+                        // keep the most recent warnings and errors around for debugging (info alerts are left out
+                        // so routine messages can't push them out, and the oldest is dropped once it is full)
+                        if params.level >= AlertLevel::Warn && history_size > 0 {
+                            let mut history = thread_history.lock().unwrap();
+                            history.push_back(AlertRecord {
+                                text: params.text.clone(),
+                                level: params.level.clone(),
+                                alert_type: params.alert_type,
+                                time: SystemTime::now(),
+                            });
+                            while history.len() > history_size {
+                                history.pop_front();
+                            }
+                        }
+
+                        // lifecycle events are an audit trail and heartbeats show the watchtower is still alive, so
+                        // both skip every filter (level, log throttle, dedup and maintenance) and always go out
+                        if params.alert_type == AlertType::Lifecycle || params.alert_type == AlertType::Heartbeat {
+                            log::info!("{}", log_text);
+                            send_alert(&teams_client, &email_client, &message_bus, &params, &secrets).await;
+
+                            // let an external watchdog know we are still alive
+                            if params.alert_type == AlertType::Heartbeat {
+                                match &heartbeat_url {
+                                    Some(url) => send_heartbeat_ping(&client, url, &secrets).await,
+                                    None => {}
+                                }
+                            }
+                            match &params.delivered {
+                                Some(delivered) => delivered.notify_one(),
+                                None => {}
+                            }
+                            continue;
+                        }
+
                         match params.level {
                             AlertLevel::None => {}
                             AlertLevel::Info => {
-                                log::info!("{}", params.text);
+                                if !is_log_throttled(
+                                    &mut info_log_cache,
+                                    &params.text,
+                                    info_log_interval,
+                                    SystemTime::now(),
+                                ) {
+                                    log::info!("{}", log_text);
+                                }
                             }
                             AlertLevel::Warn => {
-                                log::warn!("{}", params.text);
+                                log::warn!("{}", log_text);
                                 let min_time_elapsed = match SystemTime::now().duration_since(start) {
                                     Ok(d) => d > MIN_DURATION_FROM_START_TO_ERR,
                                     _ => true,
                                 };
-                                if min_time_elapsed {
-                                    // TODO: send warning through communication channels (with time buffer for duplicates)
+                                if min_time_elapsed
+                                    && params.level >= min_notify_level
+                                    && !is_duplicate(
+                                        &mut alert_cache,
+                                        &params,
+                                        duplicate_alert_delay,
+                                        SystemTime::now(),
+                                    )
+                                {
+                                    if in_maintenance {
+                                        suppressed_alerts.push(params.clone());
+                                    } else if is_snoozed(&snoozes, params.alert_type, SystemTime::now()) {
+                                        log::info!("Not pushing {:?} alert (snoozed).", params.alert_type);
+                                    } else if aggregate_per_cycle
+                                        && params.cycle_id.is_some()
+                                        && params.chain != Chain::None
+                                    {
+                                        cycle_alerts.entry(params.chain).or_default().push(params.clone());
+                                    } else {
+                                        send_alert(&teams_client, &email_client, &message_bus, &params, &secrets).await;
+                                    }
                                 }
                             }
                             AlertLevel::Error => {
-                                log::error!("{}", params.text);
+                                log::error!("{}", log_text);
                                 let min_time_elapsed = match SystemTime::now().duration_since(start) {
                                     Ok(d) => d > MIN_DURATION_FROM_START_TO_ERR,
                                     _ => true,
                                 };
-                                if min_time_elapsed {
-                                    // TODO: send error through communication channels (with time buffer for duplicates)
+                                if min_time_elapsed
+                                    && params.level >= min_notify_level
+                                    && !is_duplicate(
+                                        &mut alert_cache,
+                                        &params,
+                                        duplicate_alert_delay,
+                                        SystemTime::now(),
+                                    )
+                                {
+                                    if in_maintenance {
+                                        suppressed_alerts.push(params.clone());
+                                    } else if is_snoozed(&snoozes, params.alert_type, SystemTime::now()) {
+                                        log::info!("Not pushing {:?} alert (snoozed).", params.alert_type);
+                                    } else if aggregate_per_cycle
+                                        && params.cycle_id.is_some()
+                                        && params.chain != Chain::None
+                                    {
+                                        cycle_alerts.entry(params.chain).or_default().push(params.clone());
+                                    } else {
+                                        send_alert(&teams_client, &email_client, &message_bus, &params, &secrets).await;
+                                    }
                                 }
                             }
                         }
@@ -76,7 +377,7 @@ impl WatchtowerAlerts {
                             }
                             Empty => {
                                 // wait a bit until next try
-                                thread::sleep(POLL_DURATION);
+                                tokio::time::sleep(POLL_DURATION).await;
                             }
                         }
                     }
@@ -84,17 +385,966 @@ impl WatchtowerAlerts {
             }
         });
 
-        Ok(WatchtowerAlerts { alert_sender: tx })
+        // start heartbeat thread (sent regardless of any check outcome)
+        match config.heartbeat_interval_secs {
+            Some(interval) if interval > 0 => {
+                let heartbeat_sender = tx.clone();
+                let heartbeat_interval = Duration::from_secs(interval as u64);
+                tokio::spawn(async move {
+                    loop {
+                        tokio::time::sleep(heartbeat_interval).await;
+                        let params = AlertParams {
+                            text: String::from("Watchtower heartbeat."),
+                            level: AlertLevel::Info,
+                            alert_type: AlertType::Heartbeat,
+                            chain: Chain::None,
+                            dedup_suffix: None,
+                            context: None,
+                            delivered: None,
+                            cycle_id: None,
+                        };
+                        if heartbeat_sender.send(params).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            _ => {}
+        }
+
+        Ok(WatchtowerAlerts {
+            alert_sender: tx,
+            history,
+            failure_counts: Arc::new(Mutex::new(HashMap::new())),
+            cycle_id: None,
+        })
+    }
+
+    // gets a copy that stamps every alert with the given poll cycle so a cycle's alerts can be grouped together
+    pub fn for_cycle(&self, cycle_id: u64) -> WatchtowerAlerts {
+        WatchtowerAlerts {
+            cycle_id: Some(cycle_id),
+            ..self.clone()
+        }
+    }
+
+    // marks the end of a watcher's poll cycle (pushing its held back alerts when aggregating per cycle)
+    pub fn end_cycle(&self, chain: Chain) {
+        let params = AlertParams {
+            text: String::new(),
+            level: AlertLevel::None,
+            alert_type: AlertType::CycleEnd,
+            chain,
+            dedup_suffix: None,
+            context: None,
+            delivered: None,
+            cycle_id: self.cycle_id,
+        };
+        self.alert_sender.send(params).unwrap();
+    }
+
+    pub fn alert(&self, text: String, level: AlertLevel, alert_type: AlertType) {
+        self.alert_for_chain(text, level, alert_type, alert_type.chain());
     }
 
-    pub fn alert(&self, text: String, level: AlertLevel) {
-        let params = AlertParams { text, level };
+    // same as alert, but for alert types raised by either watcher (so one chain doesn't suppress the other)
+    pub fn alert_for_chain(&self, text: String, level: AlertLevel, alert_type: AlertType, chain: Chain) {
+        let params = AlertParams {
+            text,
+            level,
+            alert_type,
+            chain,
+            dedup_suffix: None,
+            context: None,
+            delivered: None,
+            cycle_id: self.cycle_id,
+        };
         self.alert_sender.send(params).unwrap();
     }
+
+    // records a failed check, returning whether it has now failed enough cycles in a row to alert
+    // (the check id separates checks sharing an alert type, like deposit alerts for different tokens)
+    pub fn record_failure(&self, alert_type: AlertType, check_id: &str, consecutive_failures_to_alert: u32) -> bool {
+        let mut failure_counts = self.failure_counts.lock().unwrap();
+        let count = failure_counts.entry((alert_type, String::from(check_id))).or_insert(0);
+        *count += 1;
+        *count >= consecutive_failures_to_alert
+    }
+
+    // records a successful check, resetting its consecutive failure count
+    pub fn record_success(&self, alert_type: AlertType, check_id: &str) {
+        self.failure_counts
+            .lock()
+            .unwrap()
+            .remove(&(alert_type, String::from(check_id)));
+    }
+
+    // pushes a watchtower lifecycle event (like starting or stopping) to every sink regardless of level, dedup
+    // or maintenance windows, waiting for it to be delivered so it isn't lost when the process exits
+    pub async fn lifecycle(&self, text: String) {
+        let delivered = Arc::new(Notify::new());
+        let params = AlertParams {
+            text,
+            level: AlertLevel::Info,
+            alert_type: AlertType::Lifecycle,
+            chain: Chain::None,
+            dedup_suffix: None,
+            context: None,
+            delivered: Some(delivered.clone()),
+            cycle_id: None,
+        };
+        self.alert_sender.send(params).unwrap();
+        if tokio::time::timeout(LIFECYCLE_DELIVERY_TIMEOUT, delivered.notified())
+            .await
+            .is_err()
+        {
+            log::warn!("Timed out waiting for a lifecycle alert to be delivered.");
+        }
+    }
+
+    // gets up to the given number of the most recent warnings and errors (oldest first)
+    pub fn recent_alerts(&self, limit: usize) -> Vec<AlertRecord> {
+        let history = self.history.lock().unwrap();
+        let skip = history.len().saturating_sub(limit);
+        history.iter().skip(skip).cloned().collect()
+    }
+
+    // same as alert, but distinct dedup suffixes (like a token address) are not treated as duplicates of each other
+    pub fn alert_with_dedup_suffix(&self, text: String, level: AlertLevel, alert_type: AlertType, dedup_suffix: &str) {
+        let params = AlertParams {
+            text,
+            level,
+            alert_type,
+            chain: alert_type.chain(),
+            dedup_suffix: Some(dedup_suffix.to_lowercase()),
+            context: None,
+            delivered: None,
+            cycle_id: self.cycle_id,
+        };
+        self.alert_sender.send(params).unwrap();
+    }
+
+    // same as alert (or alert_with_dedup_suffix when given a suffix), but also carries structured details like
+    // amounts, thresholds and block ranges so downstream automation doesn't have to parse them out of the text
+    pub fn alert_with_context(
+        &self,
+        text: String,
+        level: AlertLevel,
+        alert_type: AlertType,
+        dedup_suffix: Option<&str>,
+        context: Value,
+    ) {
+        let params = AlertParams {
+            text,
+            level,
+            alert_type,
+            chain: alert_type.chain(),
+            dedup_suffix: dedup_suffix.map(|suffix| suffix.to_lowercase()),
+            context: Some(context),
+            delivered: None,
+            cycle_id: self.cycle_id,
+        };
+        self.alert_sender.send(params).unwrap();
+    }
+}
+
+// checks if the same alert was already pushed within the duplicate delay (and records it if not)
+// the duplicate delay is both the minimum re-alert interval and how long entries are kept in the cache
+// (the current time is passed in so expiry doesn't depend on the wall clock)
+fn is_duplicate(
+    alert_cache: &mut HashMap<AlertKey, SystemTime>,
+    params: &AlertParams,
+    delay: Duration,
+    now: SystemTime,
+) -> bool {
+    if params.alert_type == AlertType::Heartbeat {
+        return false;
+    }
+
+    alert_cache.retain(|_, last_pushed| match now.duration_since(*last_pushed) {
+        Ok(elapsed) => elapsed < delay,
+        Err(_) => true,
+    });
+    let key = (params.chain, params.alert_type, params.dedup_suffix.clone());
+    if alert_cache.contains_key(&key) {
+        return true;
+    }
+    alert_cache.insert(key, now);
+    false
+}
+
+// checks if the same info message was already logged within the interval (and records it if not)
+// this only quiets the log, info alerts are never pushed so the duplicate filter doesn't cover them
+fn is_log_throttled(
+    log_cache: &mut HashMap<String, SystemTime>,
+    text: &str,
+    interval: Duration,
+    now: SystemTime,
+) -> bool {
+    log_cache.retain(|_, last_logged| match now.duration_since(*last_logged) {
+        Ok(elapsed) => elapsed < interval,
+        Err(_) => true,
+    });
+    if log_cache.contains_key(text) {
+        return true;
+    }
+    log_cache.insert(String::from(text), now);
+    false
+}
+
+// checks if the given time falls within any of the maintenance windows
+fn is_in_maintenance_window(maintenance_windows: &[MaintenanceWindow], now: SystemTime) -> bool {
+    let now = match now.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => return false,
+    };
+    maintenance_windows.iter().any(|window| window.contains(now))
+}
+
+// reads the snooze file, a json map of alert type to the unix time (in seconds) it is snoozed until
+// (a missing file means nothing is snoozed)
+fn read_snooze_file(path: &str) -> Result<HashMap<AlertType, u64>> {
+    let json_string = match fs::read_to_string(path) {
+        Ok(json_string) => json_string,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(serde_json::from_str(&json_string)?)
+}
+
+// checks if alerts of the given type are snoozed at the given time
+fn is_snoozed(snoozes: &HashMap<AlertType, u64>, alert_type: AlertType, now: SystemTime) -> bool {
+    let now = match now.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => return false,
+    };
+    match snoozes.get(&alert_type) {
+        Some(snoozed_until) => now < *snoozed_until,
+        None => false,
+    }
+}
+
+// replaces any of the given secrets in the string values of an alert's structured context
+fn redact_context(context: Value, secrets: &[String]) -> Value {
+    match context {
+        Value::String(text) => Value::String(redact_secrets(&text, secrets)),
+        Value::Array(values) => Value::Array(values.into_iter().map(|value| redact_context(value, secrets)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name, redact_context(value, secrets)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+// adds the poll cycle an alert was raised in to its structured context
+fn add_cycle_id(context: Option<Value>, cycle_id: u64) -> Value {
+    match context {
+        Some(Value::Object(mut details)) => {
+            details.insert(String::from("cycle_id"), Value::from(cycle_id));
+            Value::Object(details)
+        }
+        Some(details) => json!({ "cycle_id": cycle_id, "details": details }),
+        None => json!({ "cycle_id": cycle_id }),
+    }
+}
+
+// builds the alert sent at the end of a maintenance window listing the alerts that were not pushed
+fn get_maintenance_summary(suppressed_alerts: &[AlertParams]) -> AlertParams {
+    let mut text = format!(
+        "Maintenance window ended. {} alert(s) were suppressed.",
+        suppressed_alerts.len()
+    );
+    for params in suppressed_alerts.iter().take(MAX_MAINTENANCE_SUMMARY_ALERTS) {
+        text = format!("{text}\n[{:?}] {}", params.level, params.text);
+    }
+    if suppressed_alerts.len() > MAX_MAINTENANCE_SUMMARY_ALERTS {
+        text = format!(
+            "{text}\n...and {} more.",
+            suppressed_alerts.len() - MAX_MAINTENANCE_SUMMARY_ALERTS
+        );
+    }
+    let level = match suppressed_alerts.iter().map(|params| params.level.clone()).max() {
+        Some(level) => level,
+        None => AlertLevel::Info,
+    };
+    AlertParams {
+        text,
+        level,
+        alert_type: AlertType::MaintenanceSummary,
+        chain: Chain::None,
+        dedup_suffix: None,
+        context: None,
+        delivered: None,
+        cycle_id: None,
+    }
+}
+
+// combines the alerts held back over a watcher cycle into one notification (a lone alert is pushed as is)
+fn get_cycle_summary(mut cycle_alerts: Vec<AlertParams>, chain: Chain, cycle_id: Option<u64>) -> AlertParams {
+    if cycle_alerts.len() == 1 {
+        return cycle_alerts.remove(0);
+    }
+
+    let mut text = format!(
+        "{} alert(s) were raised in one {:?} watcher cycle.",
+        cycle_alerts.len(),
+        chain
+    );
+    for params in &cycle_alerts {
+        text = format!("{text}\n[{:?}] {}", params.level, params.text);
+    }
+    let level = match cycle_alerts.iter().map(|params| params.level.clone()).max() {
+        Some(level) => level,
+        None => AlertLevel::Info,
+    };
+    let sub_alerts: Vec<Value> = cycle_alerts
+        .iter()
+        .map(|params| {
+            json!({
+                "level": format!("{:?}", params.level),
+                "alert_type": format!("{:?}", params.alert_type),
+                "text": params.text,
+                "context": params.context,
+            })
+        })
+        .collect();
+    AlertParams {
+        text,
+        level,
+        alert_type: AlertType::CycleSummary,
+        chain,
+        dedup_suffix: None,
+        context: Some(json!({ "cycle_id": cycle_id, "alerts": sub_alerts })),
+        delivered: None,
+        cycle_id,
+    }
+}
+
+// pushes an alert through all configured communication channels
+async fn send_alert(
+    teams_client: &Option<TeamsClient>,
+    email_client: &Option<EmailClient>,
+    message_bus: &Option<MessageBusClient>,
+    params: &AlertParams,
+    secrets: &[String],
+) {
+    // deliver to every sink at once so a slow or failing sink doesn't hold up the others
+    let teams = async {
+        match teams_client {
+            Some(teams_client) => Some(
+                deliver(teams_client.send_alert(
+                    &params.text,
+                    &params.level,
+                    params.alert_type,
+                    params.chain,
+                    params.context.as_ref(),
+                ))
+                .await,
+            ),
+            None => None,
+        }
+    };
+    let email = async {
+        match email_client {
+            Some(email_client) => {
+                Some(deliver(email_client.send_alert(&params.text, &params.level, params.context.as_ref())).await)
+            }
+            None => None,
+        }
+    };
+    let bus = async {
+        match message_bus {
+            Some(message_bus) => Some(
+                deliver(message_bus.publish_alert(
+                    &params.text,
+                    &params.level,
+                    params.alert_type,
+                    params.chain,
+                    params.context.as_ref(),
+                ))
+                .await,
+            ),
+            None => None,
+        }
+    };
+    let (teams_result, email_result, bus_result) = tokio::join!(teams, email, bus);
+
+    // sink errors can include the sink url (like the teams webhook) which is a secret
+    match teams_result {
+        Some(Err(e)) => log::error!(
+            "Failed to send {:?} alert to Teams: {}",
+            params.alert_type,
+            redact_secrets(&e.to_string(), secrets)
+        ),
+        _ => {}
+    }
+    match email_result {
+        Some(Err(e)) => log::warn!(
+            "Failed to send {:?} alert email: {}",
+            params.alert_type,
+            redact_secrets(&e.to_string(), secrets)
+        ),
+        _ => {}
+    }
+    match bus_result {
+        Some(Err(e)) => log::warn!(
+            "Failed to publish {:?} alert to the message bus: {}",
+            params.alert_type,
+            redact_secrets(&e.to_string(), secrets)
+        ),
+        _ => {}
+    }
+}
+
+// gives up on a sink that takes too long (its own retries included) so it can't stall the alert handler
+async fn deliver(delivery: impl Future<Output = Result<()>>) -> Result<()> {
+    match tokio::time::timeout(SINK_TIMEOUT, delivery).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("timed out after {}s", SINK_TIMEOUT.as_secs())),
+    }
+}
+
+// pings the configured heartbeat url
+async fn send_heartbeat_ping(client: &Client, url: &str, secrets: &[String]) {
+    match client.get(url).send().await {
+        Ok(response) => {
+            if !response.status().is_success() {
+                log::warn!("Heartbeat ping returned status: {}", response.status());
+            }
+        }
+        Err(e) => log::warn!(
+            "Failed to send heartbeat ping: {}",
+            redact_secrets(&e.to_string(), secrets)
+        ),
+    }
 }
 
 #[derive(Clone, Debug)]
 struct AlertParams {
     text: String,
     level: AlertLevel,
+    alert_type: AlertType,
+    chain: Chain,
+    dedup_suffix: Option<String>,
+    context: Option<Value>,
+    delivered: Option<Arc<Notify>>,
+    cycle_id: Option<u64>,
+}
+
+type AlertKey = (Chain, AlertType, Option<String>);
+
+// an alert as it was raised (before it reaches the alert handler)
+#[cfg(test)]
+#[derive(Clone, Debug)]
+pub struct RaisedAlert {
+    pub text: String,
+    pub level: AlertLevel,
+    pub alert_type: AlertType,
+    pub dedup_suffix: Option<String>,
+    pub context: Option<Value>,
+}
+
+// collects the alerts raised through a WatchtowerAlerts::collector instance
+#[cfg(test)]
+pub struct AlertCollector {
+    receiver: mpsc::UnboundedReceiver<AlertParams>,
+}
+
+#[cfg(test)]
+impl AlertCollector {
+    // takes the alerts raised since the last call (cycle ends are left out)
+    pub fn take(&mut self) -> Vec<RaisedAlert> {
+        let mut raised_alerts = vec![];
+        while let Ok(params) = self.receiver.try_recv() {
+            if params.alert_type == AlertType::CycleEnd {
+                continue;
+            }
+            raised_alerts.push(RaisedAlert {
+                text: params.text,
+                level: params.level,
+                alert_type: params.alert_type,
+                dedup_suffix: params.dedup_suffix,
+                context: params.context,
+            });
+        }
+        raised_alerts
+    }
+}
+
+#[cfg(test)]
+impl WatchtowerAlerts {
+    // an instance whose alerts are collected instead of handled, so tests can check exactly what a check raised
+    pub fn collector() -> (WatchtowerAlerts, AlertCollector) {
+        let (tx, rx) = mpsc::unbounded_channel::<AlertParams>();
+        let alerts = WatchtowerAlerts {
+            alert_sender: tx,
+            history: Arc::new(Mutex::new(VecDeque::new())),
+            failure_counts: Arc::new(Mutex::new(HashMap::new())),
+            cycle_id: None,
+        };
+        (alerts, AlertCollector { receiver: rx })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AlertDetails;
+    use crate::test_utils::{test_config_builder, FakeEmailTransport, FakeMessageBus, MockHttp, WALLET_KEY};
+
+    fn alert_params(text: &str, level: AlertLevel, alert_type: AlertType, dedup_suffix: Option<&str>) -> AlertParams {
+        AlertParams {
+            text: String::from(text),
+            level,
+            alert_type,
+            chain: alert_type.chain(),
+            dedup_suffix: dedup_suffix.map(String::from),
+            context: None,
+            delivered: None,
+            cycle_id: None,
+        }
+    }
+
+    #[test]
+    fn duplicates_are_keyed_by_type_and_suffix() {
+        let mut alert_cache = HashMap::new();
+        let delay = Duration::from_secs(300);
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let deposit = |suffix| alert_params("deposit", AlertLevel::Warn, AlertType::EthereumGatewayDeposit, suffix);
+
+        assert!(!is_duplicate(&mut alert_cache, &deposit(Some("0xaa")), delay, now));
+        assert!(is_duplicate(&mut alert_cache, &deposit(Some("0xaa")), delay, now));
+        assert!(!is_duplicate(&mut alert_cache, &deposit(Some("0xbb")), delay, now));
+        assert!(!is_duplicate(&mut alert_cache, &deposit(None), delay, now));
+
+        let imbalance = alert_params(
+            "imbalance",
+            AlertLevel::Warn,
+            AlertType::EthereumGatewayImbalance,
+            Some("0xaa"),
+        );
+        assert!(!is_duplicate(&mut alert_cache, &imbalance, delay, now));
+    }
+
+    #[test]
+    fn heartbeats_are_never_duplicates() {
+        let mut alert_cache = HashMap::new();
+        let heartbeat = alert_params("heartbeat", AlertLevel::Info, AlertType::Heartbeat, None);
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        assert!(!is_duplicate(
+            &mut alert_cache,
+            &heartbeat,
+            Duration::from_secs(300),
+            now
+        ));
+        assert!(!is_duplicate(
+            &mut alert_cache,
+            &heartbeat,
+            Duration::from_secs(300),
+            now
+        ));
+    }
+
+    #[test]
+    fn duplicate_delay_is_also_the_cache_expiry() {
+        let mut alert_cache = HashMap::new();
+        let delay = Duration::from_secs(300);
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let connection = alert_params("down", AlertLevel::Error, AlertType::EthereumConnection, None);
+        let other = alert_params("stalled", AlertLevel::Warn, AlertType::EthereumCommitStalled, None);
+
+        assert!(!is_duplicate(&mut alert_cache, &connection, delay, start));
+        assert!(is_duplicate(
+            &mut alert_cache,
+            &connection,
+            delay,
+            start + Duration::from_secs(299)
+        ));
+
+        // once the delay has passed the alert is pushed again and the stale entry is gone
+        assert!(!is_duplicate(
+            &mut alert_cache,
+            &other,
+            delay,
+            start + Duration::from_secs(300)
+        ));
+        assert_eq!(alert_cache.len(), 1);
+        assert!(!is_duplicate(
+            &mut alert_cache,
+            &connection,
+            delay,
+            start + Duration::from_secs(300)
+        ));
+    }
+
+    #[test]
+    fn dedup_follows_the_given_clock() {
+        let mut alert_cache = HashMap::new();
+        let delay = Duration::from_secs(3600);
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let funds = alert_params("low funds", AlertLevel::Warn, AlertType::EthereumAccountFunds, None);
+
+        assert!(!is_duplicate(&mut alert_cache, &funds, delay, start));
+
+        // a clock that goes backwards keeps the entry rather than expiring it
+        assert!(is_duplicate(
+            &mut alert_cache,
+            &funds,
+            delay,
+            start - Duration::from_secs(60)
+        ));
+
+        // fast forward a day without waiting
+        assert!(!is_duplicate(
+            &mut alert_cache,
+            &funds,
+            delay,
+            start + Duration::from_secs(86_400)
+        ));
+    }
+
+    #[test]
+    fn identical_info_logs_are_throttled_for_the_interval() {
+        let mut log_cache = HashMap::new();
+        let interval = Duration::from_secs(60);
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+        assert!(!is_log_throttled(&mut log_cache, "synced", interval, start));
+        assert!(is_log_throttled(
+            &mut log_cache,
+            "synced",
+            interval,
+            start + Duration::from_secs(59)
+        ));
+        assert!(!is_log_throttled(
+            &mut log_cache,
+            "other",
+            interval,
+            start + Duration::from_secs(59)
+        ));
+        assert!(!is_log_throttled(
+            &mut log_cache,
+            "synced",
+            interval,
+            start + Duration::from_secs(60)
+        ));
+    }
+
+    #[test]
+    fn maintenance_windows_include_start_and_exclude_end() {
+        let windows = vec![
+            MaintenanceWindow {
+                start_time: 1000,
+                end_time: 2000,
+            },
+            MaintenanceWindow {
+                start_time: 5000,
+                end_time: 6000,
+            },
+        ];
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert!(!is_in_maintenance_window(&windows, at(999)));
+        assert!(is_in_maintenance_window(&windows, at(1000)));
+        assert!(is_in_maintenance_window(&windows, at(1999)));
+        assert!(!is_in_maintenance_window(&windows, at(2000)));
+        assert!(is_in_maintenance_window(&windows, at(5500)));
+        assert!(!is_in_maintenance_window(&[], at(1500)));
+    }
+
+    #[test]
+    fn maintenance_summary_lists_suppressed_alerts() {
+        let suppressed = vec![
+            alert_params("deposit", AlertLevel::Warn, AlertType::EthereumGatewayDeposit, None),
+            alert_params("down", AlertLevel::Error, AlertType::EthereumConnection, None),
+        ];
+        let summary = get_maintenance_summary(&suppressed);
+        assert_eq!(summary.alert_type, AlertType::MaintenanceSummary);
+        assert_eq!(summary.level, AlertLevel::Error);
+        assert_eq!(
+            summary.text,
+            "Maintenance window ended. 2 alert(s) were suppressed.\n[Warn] deposit\n[Error] down"
+        );
+
+        let many: Vec<AlertParams> = (0..MAX_MAINTENANCE_SUMMARY_ALERTS + 3)
+            .map(|i| {
+                alert_params(
+                    &format!("alert {i}"),
+                    AlertLevel::Warn,
+                    AlertType::EthereumCommitStalled,
+                    None,
+                )
+            })
+            .collect();
+        assert!(get_maintenance_summary(&many).text.ends_with("\n...and 3 more."));
+    }
+
+    #[test]
+    fn snoozes_last_until_the_given_time() {
+        let snoozes = HashMap::from([(AlertType::EthereumConnection, 2000)]);
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert!(is_snoozed(&snoozes, AlertType::EthereumConnection, at(1999)));
+        assert!(!is_snoozed(&snoozes, AlertType::EthereumConnection, at(2000)));
+        assert!(!is_snoozed(&snoozes, AlertType::FuelConnection, at(1000)));
+    }
+
+    #[test]
+    fn snooze_file_is_read_when_present() {
+        let path = std::env::temp_dir().join(format!("watchtower-snooze-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert!(read_snooze_file(path).unwrap().is_empty());
+
+        fs::write(path, r#"{ "EthereumConnection": 2000, "FuelBlockProduction": 3000 }"#).unwrap();
+        let snoozes = read_snooze_file(path);
+        fs::remove_file(path).unwrap();
+        let snoozes = snoozes.unwrap();
+        assert_eq!(snoozes.get(&AlertType::EthereumConnection), Some(&2000));
+        assert_eq!(snoozes.get(&AlertType::FuelBlockProduction), Some(&3000));
+    }
+
+    #[test]
+    fn cycle_summary_combines_a_cycles_alerts() {
+        let lone = alert_params("down", AlertLevel::Error, AlertType::EthereumConnection, None);
+        let summary = get_cycle_summary(vec![lone], Chain::Ethereum, Some(7));
+        assert_eq!(summary.alert_type, AlertType::EthereumConnection);
+        assert_eq!(summary.text, "down");
+
+        let cycle_alerts = vec![
+            alert_params("deposit", AlertLevel::Warn, AlertType::EthereumGatewayDeposit, None),
+            alert_params("down", AlertLevel::Error, AlertType::EthereumConnection, None),
+        ];
+        let summary = get_cycle_summary(cycle_alerts, Chain::Ethereum, Some(7));
+        assert_eq!(summary.alert_type, AlertType::CycleSummary);
+        assert_eq!(summary.chain, Chain::Ethereum);
+        assert_eq!(summary.level, AlertLevel::Error);
+        assert_eq!(summary.cycle_id, Some(7));
+        assert_eq!(
+            summary.text,
+            "2 alert(s) were raised in one Ethereum watcher cycle.\n[Warn] deposit\n[Error] down"
+        );
+        let context = summary.context.unwrap();
+        assert_eq!(context["cycle_id"], 7);
+        assert_eq!(context["alerts"][1]["alert_type"], "EthereumConnection");
+    }
+
+    #[test]
+    fn secrets_in_the_context_are_redacted() {
+        let secrets = vec![String::from(WALLET_KEY)];
+        let context = json!({
+            "error": format!("signing with {WALLET_KEY} failed"),
+            "attempts": [{ "key": WALLET_KEY }, 3],
+            "amount": 10,
+        });
+        assert_eq!(
+            redact_context(context, &secrets),
+            json!({
+                "error": "signing with *** failed",
+                "attempts": [{ "key": "***" }, 3],
+                "amount": 10,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn configured_key_is_redacted_before_reaching_a_sink() {
+        let webhook = MockHttp::start(vec![]);
+        let config = test_config_builder("http://localhost:8545")
+            .ethereum_wallet_key(WALLET_KEY)
+            .teams_webhook_url(&webhook.url)
+            .build()
+            .unwrap();
+        let alerts = WatchtowerAlerts::new(&config).unwrap();
+        alerts.lifecycle(format!("Started with key {WALLET_KEY}.")).await;
+
+        let requests = webhook.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!((requests[0].method.as_str(), requests[0].path.as_str()), ("POST", "/"));
+        assert!(requests[0].body.contains("Started with key ***."));
+        assert!(!requests[0].body.contains(WALLET_KEY));
+    }
+
+    #[tokio::test]
+    async fn heartbeats_reach_the_sinks_on_their_cadence() {
+        let webhook = MockHttp::start(vec![]);
+        let heartbeat = MockHttp::start(vec![]);
+        let mut config = test_config_builder("http://localhost:8545")
+            .teams_webhook_url(&webhook.url)
+            .min_notify_level(AlertLevel::Error)
+            .info_log_interval_secs(60)
+            .build()
+            .unwrap();
+        config.heartbeat_interval_secs = Some(1);
+        config.heartbeat_url = Some(heartbeat.url.clone());
+        let _alerts = WatchtowerAlerts::new(&config).unwrap();
+
+        // sent at 1s, 2s and 3s (each picked up within the handler's 1s poll), so at least two have gone out
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+        let pushed = webhook.requests();
+        assert!((2..=3).contains(&pushed.len()), "{} heartbeats pushed", pushed.len());
+        assert!(pushed
+            .iter()
+            .all(|request| request.body.contains("Watchtower heartbeat.")));
+        assert_eq!(heartbeat.requests().len(), pushed.len());
+    }
+
+    fn email_client(transport: FakeEmailTransport) -> (Option<EmailClient>, Arc<FakeEmailTransport>) {
+        let transport = Arc::new(transport);
+        let to = vec![String::from("oncall@example.com")];
+        let email_client = EmailClient::new(transport.clone(), "watchtower@example.com", &to, "Watchtower").unwrap();
+        (Some(email_client), transport)
+    }
+
+    fn message_bus(bus: FakeMessageBus) -> (Option<MessageBusClient>, Arc<FakeMessageBus>) {
+        let bus = Arc::new(bus);
+        (Some(MessageBusClient::new(bus.clone(), "alerts", "Watchtower")), bus)
+    }
+
+    #[tokio::test]
+    async fn failing_sinks_do_not_stop_the_others() {
+        let params = alert_params("down", AlertLevel::Error, AlertType::EthereumConnection, None);
+        let teams_webhook = MockHttp::start(vec![500; 10]);
+        let teams_client = Some(TeamsClient::new(
+            reqwest::Client::new(),
+            &teams_webhook.url,
+            "Watchtower",
+            "test",
+            None,
+            0,
+        ));
+
+        // teams and email fail
+        let (failing_email_client, _) = email_client(FakeEmailTransport::failing());
+        let (bus_client, bus) = message_bus(FakeMessageBus::default());
+        send_alert(&teams_client, &failing_email_client, &bus_client, &params, &[]).await;
+        assert_eq!(bus.published().len(), 1);
+        assert_eq!(bus.published()[0].1["text"], "down");
+        assert!(!teams_webhook.requests().is_empty());
+
+        // teams fails and the bus never answers
+        let (email_client, email) = email_client(FakeEmailTransport::default());
+        let (hanging_bus_client, _) = message_bus(FakeMessageBus::hanging());
+        send_alert(&teams_client, &email_client, &hanging_bus_client, &params, &[]).await;
+        assert_eq!(email.sent().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn alert_history_keeps_the_newest_warnings_and_errors_in_order() {
+        let mut config = test_config_builder("http://localhost:8545").build().unwrap();
+        config.alert_history_size = 3;
+        let alerts = WatchtowerAlerts::new(&config).unwrap();
+        let raised = [
+            ("commit stalled", AlertLevel::Warn, AlertType::EthereumCommitStalled),
+            ("fuel down", AlertLevel::Error, AlertType::FuelConnection),
+            ("routine", AlertLevel::Info, AlertType::EthereumBlockProduction),
+            ("ethereum down", AlertLevel::Error, AlertType::EthereumConnection),
+            ("low funds", AlertLevel::Warn, AlertType::EthereumAccountFunds),
+        ];
+        for (text, level, alert_type) in raised {
+            alerts.alert(String::from(text), level, alert_type);
+        }
+        for _ in 0..50 {
+            match alerts.recent_alerts(10).last() {
+                Some(record) if record.text == "low funds" => break,
+                _ => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+
+        let texts = |records: Vec<AlertRecord>| records.into_iter().map(|record| record.text).collect::<Vec<_>>();
+        assert_eq!(
+            texts(alerts.recent_alerts(10)),
+            vec!["fuel down", "ethereum down", "low funds"]
+        );
+        assert_eq!(texts(alerts.recent_alerts(2)), vec!["ethereum down", "low funds"]);
+    }
+
+    #[test]
+    fn consecutive_failures_alert_once_they_reach_the_threshold() {
+        let (alerts, _collector) = WatchtowerAlerts::collector();
+        assert!(!alerts.record_failure(AlertType::EthereumConnection, "", 2));
+        assert!(alerts.record_failure(AlertType::EthereumConnection, "", 2));
+        assert!(alerts.record_failure(AlertType::EthereumConnection, "", 2));
+
+        // failures of other checks are counted separately
+        assert!(!alerts.record_failure(AlertType::EthereumConnection, "secondary", 2));
+        assert!(!alerts.record_failure(AlertType::FuelConnection, "", 2));
+    }
+
+    #[test]
+    fn success_between_failures_resets_the_count() {
+        let (alerts, _collector) = WatchtowerAlerts::collector();
+        for _ in 0..5 {
+            assert!(!alerts.record_failure(AlertType::EthereumConnection, "", 2));
+            alerts.record_success(AlertType::EthereumConnection, "");
+        }
+        assert!(!alerts.record_failure(AlertType::EthereumConnection, "", 2));
+        assert!(alerts.record_failure(AlertType::EthereumConnection, "", 2));
+    }
+
+    #[tokio::test]
+    async fn runbook_link_and_summary_reach_the_sinks() {
+        let details = AlertDetails {
+            runbook_url: Some(String::from("https://runbooks.example.com/block-production")),
+            custom_summary: Some(String::from("Check the sequencer.")),
+            ..AlertDetails::default()
+        };
+        let text = details.annotate(String::from("Ethereum block production stopped."));
+        let params = alert_params(&text, AlertLevel::Error, AlertType::EthereumBlockProduction, None);
+        let teams_webhook = MockHttp::start(vec![]);
+        let teams_client = Some(TeamsClient::new(
+            reqwest::Client::new(),
+            &teams_webhook.url,
+            "Watchtower",
+            "test",
+            None,
+            0,
+        ));
+        let (bus_client, bus) = message_bus(FakeMessageBus::default());
+        send_alert(&teams_client, &None, &bus_client, &params, &[]).await;
+
+        let expected = "Ethereum block production stopped. Check the sequencer. \
+            Runbook: https://runbooks.example.com/block-production";
+        assert_eq!(bus.published()[0].1["text"], expected);
+        let requests = teams_webhook.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].body.contains(expected));
+
+        // nothing is added when neither is configured
+        let text = AlertDetails::default().annotate(String::from("Ethereum block production stopped."));
+        assert_eq!(text, "Ethereum block production stopped.");
+    }
+
+    #[test]
+    fn alerts_from_one_cycle_share_its_id() {
+        let (alerts, mut collector) = WatchtowerAlerts::collector();
+        let first_cycle = alerts.for_cycle(1);
+        first_cycle.alert(String::from("down"), AlertLevel::Error, AlertType::EthereumConnection);
+        first_cycle.alert(
+            String::from("stalled"),
+            AlertLevel::Warn,
+            AlertType::EthereumCommitStalled,
+        );
+        alerts
+            .for_cycle(2)
+            .alert(String::from("down"), AlertLevel::Error, AlertType::EthereumConnection);
+        alerts.alert(String::from("started"), AlertLevel::Info, AlertType::Lifecycle);
+
+        let mut cycle_ids = vec![];
+        while let Ok(params) = collector.receiver.try_recv() {
+            cycle_ids.push(params.cycle_id);
+        }
+        assert_eq!(cycle_ids, vec![Some(1), Some(1), Some(2), None]);
+
+        // the id is merged into the context, or wraps it when it isn't an object
+        assert_eq!(add_cycle_id(None, 1), json!({ "cycle_id": 1 }));
+        assert_eq!(
+            add_cycle_id(Some(json!({ "block": 5 })), 1),
+            json!({ "block": 5, "cycle_id": 1 })
+        );
+        assert_eq!(
+            add_cycle_id(Some(json!([5])), 2),
+            json!({ "cycle_id": 2, "details": [5] })
+        );
+    }
 }
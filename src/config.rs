@@ -1,79 +1,1525 @@
-use crate::alerts::AlertLevel;
+use crate::alerts::{AlertLevel, AlertType};
+use crate::error::{Result, WatchtowerError};
 use crate::ethereum_actions::EthereumAction;
+use crate::ethereum_watcher::{ETHEREUM_BLOCK_TIME, TX_POLL_INTERVAL};
+use crate::fuel_watcher::FUEL_BLOCK_TIME;
+use crate::rpc_limit::LimitedHttp;
 
-use anyhow::Result;
-use serde::Deserialize;
+use ethers::providers::{Http, Provider};
+use ethers::types::Address;
+use ethers::utils::{keccak256, to_checksum};
+use reqwest::{Client, Proxy, Url};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::str::FromStr;
+use std::time::Duration;
 use std::{env, fs};
 
 pub static PRIVATE_KEY_ENV_VAR: &str = "WATCHTOWER_ETH_PRIVATE_KEY";
+pub static PRIVATE_KEY_FILE_ENV_VAR: &str = "WATCHTOWER_ETH_PRIVATE_KEY_FILE";
+pub static MIN_SECRET_LENGTH: usize = 8;
+pub static MAX_TIME_FRAME_SECS: u32 = 365 * 24 * 60 * 60;
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct WatchtowerConfig {
+    pub deployment_name: Option<String>,
     pub fuel_graphql: String,
     pub ethereum_rpc: String,
+    pub ethereum_ws_rpc: Option<String>,
+    pub ethereum_rpc_secondary: Option<String>,
     pub state_contract_address: String,
     pub portal_contract_address: String,
     pub gateway_contract_address: String,
     pub ethereum_wallet_key: Option<String>,
+    pub ethereum_wallet_key_path: Option<String>,
+    #[serde(alias = "expected_chain_id")]
+    pub expected_ethereum_chain_id: Option<u64>,
+    pub expected_fuel_chain_name: Option<String>,
+    #[serde(default)]
+    pub require_wallet: bool,
+    #[serde(default)]
+    pub preflight: bool,
     pub duplicate_alert_delay: u32,
+    #[serde(default)]
+    pub info_log_interval_secs: u32,
+    #[serde(default)]
+    pub aggregate_per_cycle: bool,
+    #[serde(default = "default_max_scan_timeframe")]
+    pub max_scan_timeframe_secs: u32,
+    #[serde(default = "default_alert_history_size")]
+    pub alert_history_size: usize,
+    #[serde(default = "default_pause_cooldown")]
+    pub pause_cooldown_secs: u32,
+    #[serde(default = "default_pause_confirmations")]
+    pub pause_confirmations: usize,
+    #[serde(default = "default_action_queue_capacity")]
+    pub action_queue_capacity: usize,
+    #[serde(default)]
+    pub action_policy: HashMap<AlertType, EthereumAction>,
+    pub heartbeat_interval_secs: Option<u32>,
+    pub heartbeat_url: Option<String>,
+    pub deadmanswitch_url: Option<String>,
+    #[serde(default = "default_system_name")]
+    pub system_name: String,
+    #[serde(default = "default_min_notify_level")]
+    pub min_notify_level: AlertLevel,
+    pub teams_webhook_url: Option<String>,
+    #[serde(default = "default_alert_retries")]
+    pub alert_retries: u32,
+    #[serde(default = "default_alert_timeout")]
+    pub alert_timeout_secs: u64,
+    pub smtp_host: Option<String>,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub email_from: Option<String>,
+    #[serde(default)]
+    pub email_to: Vec<String>,
+    pub message_bus: Option<MessageBus>,
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+    pub source_label: Option<String>,
+    #[serde(default)]
+    pub source_include_hostname: bool,
+    pub alert_component: Option<String>,
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+    pub snooze_file: Option<String>,
+    pub max_cycles: Option<u64>,
+    #[serde(default = "default_startup_stagger")]
+    pub startup_stagger_ms: u64,
+    #[serde(default = "default_check_stagger")]
+    pub check_stagger_ms: u64,
+    pub worker_threads: Option<usize>,
+    pub max_blocking_threads: Option<usize>,
+    pub max_concurrent_rpc: Option<usize>,
     pub fuel_client_watcher: FuelClientWatcher,
     pub ethereum_client_watcher: EthereumClientWatcher,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl WatchtowerConfig {
+    // gets the configured values that should never show up in logs or alerts (rpc urls often embed api keys)
+    pub fn secrets(&self) -> Vec<String> {
+        let mut secrets: Vec<String> = vec![self.ethereum_rpc.clone()];
+        match &self.ethereum_wallet_key {
+            Some(key) => {
+                secrets.push(key.clone());
+                secrets.push(key.trim_start_matches("0x").to_string());
+            }
+            None => {}
+        }
+        match &self.ethereum_ws_rpc {
+            Some(url) => secrets.push(url.clone()),
+            None => {}
+        }
+        match &self.ethereum_rpc_secondary {
+            Some(url) => secrets.push(url.clone()),
+            None => {}
+        }
+        match &self.heartbeat_url {
+            Some(url) => secrets.push(url.clone()),
+            None => {}
+        }
+        match &self.deadmanswitch_url {
+            Some(url) => secrets.push(url.clone()),
+            None => {}
+        }
+        match &self.teams_webhook_url {
+            Some(url) => secrets.push(url.clone()),
+            None => {}
+        }
+        match &self.http_proxy {
+            Some(url) => secrets.push(url.clone()),
+            None => {}
+        }
+        match &self.https_proxy {
+            Some(url) => secrets.push(url.clone()),
+            None => {}
+        }
+        match &self.smtp_password {
+            Some(password) => secrets.push(password.clone()),
+            None => {}
+        }
+        match &self.message_bus {
+            Some(message_bus) => secrets.push(message_bus.servers.clone()),
+            None => {}
+        }
+
+        // replace longer secrets first so a secret containing another is fully redacted
+        secrets.retain(|secret| secret.len() >= MIN_SECRET_LENGTH);
+        secrets.sort_by_key(|secret| Reverse(secret.len()));
+        secrets
+    }
+
+    // builds an http client that goes through the configured proxies (or the standard proxy env vars if none are set)
+    pub fn http_client(&self, timeout: Option<Duration>) -> Result<Client> {
+        let mut builder = Client::builder();
+        let (http_proxy, https_proxy) = get_proxy_urls(self.http_proxy.clone(), self.https_proxy.clone(), |name| {
+            env::var(name).ok()
+        });
+        match &http_proxy {
+            Some(url) => {
+                let proxy =
+                    Proxy::http(url).map_err(|e| WatchtowerError::Config(format!("Invalid http_proxy: {e}")))?;
+                builder = builder.proxy(proxy);
+            }
+            None => {}
+        }
+        match &https_proxy {
+            Some(url) => {
+                let proxy =
+                    Proxy::https(url).map_err(|e| WatchtowerError::Config(format!("Invalid https_proxy: {e}")))?;
+                builder = builder.proxy(proxy);
+            }
+            None => {}
+        }
+        match timeout {
+            Some(timeout) => builder = builder.timeout(timeout),
+            None => {}
+        }
+        builder
+            .build()
+            .map_err(|e| WatchtowerError::Config(format!("Failed to build http client: {e}")))
+    }
+
+    // builds an ethereum rpc provider that goes through the configured proxies
+    pub fn ethereum_http_provider(&self) -> Result<Provider<LimitedHttp>> {
+        let url = Url::parse(&self.ethereum_rpc)
+            .map_err(|e| WatchtowerError::Config(format!("Invalid ethereum RPC: {e}")))?;
+        let http = Http::new_with_client(url, self.http_client(None)?);
+        Ok(Provider::new(LimitedHttp::new(http, self.max_concurrent_rpc)).interval(TX_POLL_INTERVAL))
+    }
+
+    // gets the provider used to cross-check critical reads before acting (none if no secondary rpc is configured)
+    pub fn ethereum_secondary_http_provider(&self) -> Result<Option<Provider<LimitedHttp>>> {
+        let rpc = match &self.ethereum_rpc_secondary {
+            Some(rpc) => rpc,
+            None => return Ok(None),
+        };
+        let url =
+            Url::parse(rpc).map_err(|e| WatchtowerError::Config(format!("Invalid secondary ethereum RPC: {e}")))?;
+        let http = Http::new_with_client(url, self.http_client(None)?);
+        Ok(Some(Provider::new(LimitedHttp::new(http, self.max_concurrent_rpc))))
+    }
+
+    // gets the label identifying this instance as the source of pushed alerts
+    pub fn get_source_label(&self) -> String {
+        let label = match &self.source_label {
+            Some(label) => label.clone(),
+            None => self.system_name.clone(),
+        };
+        if !self.source_include_hostname {
+            return label;
+        }
+        let hostname = match env::var("HOSTNAME") {
+            Ok(hostname) => hostname,
+            Err(_) => fs::read_to_string("/etc/hostname")
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        };
+        if hostname.is_empty() {
+            return label;
+        }
+        format!("{label}@{hostname}")
+    }
+
+    // gets a short fingerprint of the config (secrets are left out so the hash is safe to share)
+    pub fn config_hash(&self) -> String {
+        let config = redact_secrets(&format!("{:?}", self), &self.secrets());
+        let hash = keccak256(config.as_bytes());
+        hash[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    // describes the enabled checks and actions (used to echo the active config back on startup)
+    pub fn get_summary(&self) -> String {
+        let fuel = &self.fuel_client_watcher;
+        let ethereum = &self.ethereum_client_watcher;
+
+        let mut fuel_checks: Vec<String> = vec![];
+        describe_check(
+            &mut fuel_checks,
+            "connection",
+            &fuel.connection_alert.alert_level,
+            &fuel.connection_alert.alert_action,
+            String::new(),
+        );
+        describe_check(
+            &mut fuel_checks,
+            "block production",
+            &fuel.block_production_alert.alert_level,
+            &fuel.block_production_alert.alert_action,
+            format!("max {}s", fuel.block_production_alert.max_block_time),
+        );
+        match fuel.block_production_alert.critical_block_time {
+            Some(critical_block_time) if fuel.block_production_alert.alert_level != AlertLevel::None => describe_check(
+                &mut fuel_checks,
+                "block production stopped",
+                &AlertLevel::Error,
+                &fuel.block_production_alert.critical_alert_action,
+                format!("max {}s", critical_block_time),
+            ),
+            _ => {}
+        }
+        describe_check(
+            &mut fuel_checks,
+            "block height stall",
+            &fuel.block_height_stall_alert.alert_level,
+            &fuel.block_height_stall_alert.alert_action,
+            format!("max {} cycles", fuel.block_height_stall_alert.max_stalled_cycles),
+        );
+        for alert in &fuel.portal_withdraw_alerts {
+            describe_check(
+                &mut fuel_checks,
+                "base asset withdrawals",
+                &alert.alert_level,
+                &alert.alert_action,
+                format!("{} over {}s", alert.amount, alert.time_frame),
+            );
+        }
+        for alert in &fuel.gateway_withdraw_alerts {
+            describe_check(
+                &mut fuel_checks,
+                "token withdrawals",
+                &alert.alert_level,
+                &alert.alert_action,
+                format!(
+                    "{}{} over {}s, {}",
+                    alert.amount, alert.token_name, alert.time_frame, alert.token_address
+                ),
+            );
+        }
+
+        let mut ethereum_checks: Vec<String> = vec![];
+        describe_check(
+            &mut ethereum_checks,
+            "connection",
+            &ethereum.connection_alert.alert_level,
+            &ethereum.connection_alert.alert_action,
+            String::new(),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "block production",
+            &ethereum.block_production_alert.alert_level,
+            &ethereum.block_production_alert.alert_action,
+            format!("max {}s", ethereum.block_production_alert.max_block_time),
+        );
+        match ethereum.block_production_alert.critical_block_time {
+            Some(critical_block_time) if ethereum.block_production_alert.alert_level != AlertLevel::None => {
+                describe_check(
+                    &mut ethereum_checks,
+                    "block production stopped",
+                    &AlertLevel::Error,
+                    &ethereum.block_production_alert.critical_alert_action,
+                    format!("max {}s", critical_block_time),
+                )
+            }
+            _ => {}
+        }
+        describe_check(
+            &mut ethereum_checks,
+            "account funds",
+            &ethereum.account_funds_alert.alert_level,
+            &ethereum.account_funds_alert.alert_action,
+            format!("min {}", ethereum.account_funds_alert.min_balance),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "balance runway",
+            &ethereum.balance_runway_alert.alert_level,
+            &ethereum.balance_runway_alert.alert_action,
+            format!("min {}h", ethereum.balance_runway_alert.balance_runway_hours),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "unexpected wallet activity",
+            &ethereum.unexpected_wallet_activity_alert.alert_level,
+            &ethereum.unexpected_wallet_activity_alert.alert_action,
+            String::new(),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "invalid state commits",
+            &ethereum.invalid_state_commit_alert.alert_level,
+            &ethereum.invalid_state_commit_alert.alert_action,
+            String::new(),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "commit stall",
+            &ethereum.commit_stall_alert.alert_level,
+            &ethereum.commit_stall_alert.alert_action,
+            format!("max {}s", ethereum.commit_stall_alert.max_commit_interval_secs),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "finality lag",
+            &ethereum.finality_lag_alert.alert_level,
+            &ethereum.finality_lag_alert.alert_action,
+            format!("max {} blocks", ethereum.finality_lag_alert.max_finality_lag_blocks),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "gas price",
+            &ethereum.gas_price_alert.alert_level,
+            &ethereum.gas_price_alert.alert_action,
+            format!("max {} gwei", ethereum.gas_price_alert.max_gas_price_gwei),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "stale rpc node",
+            &ethereum.stale_rpc_alert.alert_level,
+            &ethereum.stale_rpc_alert.alert_action,
+            format!("max {} blocks back", ethereum.stale_rpc_alert.max_block_regression),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "ownership",
+            &ethereum.ownership_alert.alert_level,
+            &ethereum.ownership_alert.alert_action,
+            String::new(),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "empty logs",
+            &ethereum.empty_logs_alert.alert_level,
+            &ethereum.empty_logs_alert.alert_action,
+            String::new(),
+        );
+        describe_check(
+            &mut ethereum_checks,
+            "commit cadence",
+            &ethereum.commit_cadence_alert.alert_level,
+            &None,
+            format!(
+                "last {} commits every {}s",
+                ethereum.commit_cadence_alert.sample_count, ethereum.commit_cadence_alert.report_interval_secs
+            ),
+        );
+        for alert in &ethereum.portal_deposit_alerts {
+            describe_check(
+                &mut ethereum_checks,
+                "base asset deposits",
+                &alert.alert_level,
+                &alert.alert_action,
+                format!("{} over {}s", alert.amount, alert.time_frame),
+            );
+        }
+        for alert in &ethereum.gateway_deposit_alerts {
+            describe_check(
+                &mut ethereum_checks,
+                "token deposits",
+                &alert.alert_level,
+                &alert.alert_action,
+                format!(
+                    "{}{} over {}s, {}",
+                    alert.amount,
+                    alert.token_name.clone().unwrap_or_default(),
+                    alert.time_frame,
+                    alert.token_address
+                ),
+            );
+        }
+        if ethereum.auto_discover_tokens {
+            let alert = &ethereum.discovered_token_deposit_alert;
+            describe_check(
+                &mut ethereum_checks,
+                "discovered token deposits",
+                &alert.alert_level,
+                &alert.alert_action,
+                format!("{} over {}s", alert.amount, alert.time_frame),
+            );
+        }
+        for alert in &ethereum.portal_imbalance_alerts {
+            describe_check(
+                &mut ethereum_checks,
+                "base asset imbalance",
+                &alert.alert_level,
+                &alert.alert_action,
+                format!("{} over {}s", alert.imbalance_threshold, alert.time_frame),
+            );
+        }
+        for alert in &ethereum.gateway_imbalance_alerts {
+            describe_check(
+                &mut ethereum_checks,
+                "token imbalance",
+                &alert.alert_level,
+                &alert.alert_action,
+                format!(
+                    "{}{} over {}s, {}",
+                    alert.imbalance_threshold,
+                    alert.token_name.clone().unwrap_or_default(),
+                    alert.time_frame,
+                    alert.token_address
+                ),
+            );
+        }
+
+        describe_check(
+            &mut ethereum_checks,
+            "supply mismatch",
+            &ethereum.supply_mismatch_alert.alert_level,
+            &ethereum.supply_mismatch_alert.alert_action,
+            format!(
+                "tolerance {} over {}s",
+                ethereum.supply_mismatch_alert.tolerance, ethereum.supply_mismatch_alert.time_frame
+            ),
+        );
+
+        describe_check(
+            &mut ethereum_checks,
+            "bridge inactivity",
+            &ethereum.inactivity_alert.alert_level,
+            &ethereum.inactivity_alert.alert_action,
+            format!("over {}s", ethereum.inactivity_alert.inactivity_window_secs),
+        );
+
+        let actions = match self.ethereum_wallet_key {
+            Some(_) => "enabled",
+            None => "disabled (no ethereum wallet configured)",
+        };
+        format!(
+            "Watchtower started. Actions: {}. Fuel checks: [{}]. Ethereum checks: [{}].",
+            actions,
+            fuel_checks.join("; "),
+            ethereum_checks.join("; ")
+        )
+    }
+
+    // gets the distinct actions that the configured alerts can take
+    pub fn get_configured_actions(&self) -> Vec<EthereumAction> {
+        let fuel = &self.fuel_client_watcher;
+        let ethereum = &self.ethereum_client_watcher;
+
+        let mut actions = vec![
+            &fuel.connection_alert.alert_action,
+            &fuel.block_production_alert.alert_action,
+            &fuel.block_production_alert.critical_alert_action,
+            &fuel.block_height_stall_alert.alert_action,
+            &ethereum.connection_alert.alert_action,
+            &ethereum.block_production_alert.alert_action,
+            &ethereum.block_production_alert.critical_alert_action,
+            &ethereum.account_funds_alert.alert_action,
+            &ethereum.balance_runway_alert.alert_action,
+            &ethereum.unexpected_wallet_activity_alert.alert_action,
+            &ethereum.invalid_state_commit_alert.alert_action,
+            &ethereum.commit_stall_alert.alert_action,
+            &ethereum.finality_lag_alert.alert_action,
+            &ethereum.gas_price_alert.alert_action,
+            &ethereum.stale_rpc_alert.alert_action,
+            &ethereum.empty_logs_alert.alert_action,
+            &ethereum.ownership_alert.alert_action,
+            &ethereum.supply_mismatch_alert.alert_action,
+            &ethereum.inactivity_alert.alert_action,
+        ];
+        actions.extend(fuel.portal_withdraw_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(fuel.gateway_withdraw_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(ethereum.portal_deposit_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(ethereum.gateway_deposit_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(ethereum.portal_imbalance_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(
+            ethereum
+                .gateway_imbalance_alerts
+                .iter()
+                .map(|alert| &alert.alert_action),
+        );
+        if ethereum.auto_discover_tokens {
+            actions.push(&ethereum.discovered_token_deposit_alert.alert_action);
+        }
+
+        let mut configured: Vec<EthereumAction> = vec![];
+        for action in actions.into_iter().flatten() {
+            if *action != EthereumAction::None && !configured.contains(action) {
+                configured.push(action.clone());
+            }
+        }
+        configured
+    }
+}
+
+impl Default for WatchtowerConfig {
+    fn default() -> Self {
+        WatchtowerConfig {
+            deployment_name: None,
+            fuel_graphql: String::new(),
+            ethereum_rpc: String::new(),
+            ethereum_ws_rpc: None,
+            ethereum_rpc_secondary: None,
+            state_contract_address: String::new(),
+            portal_contract_address: String::new(),
+            gateway_contract_address: String::new(),
+            ethereum_wallet_key: None,
+            ethereum_wallet_key_path: None,
+            expected_ethereum_chain_id: None,
+            expected_fuel_chain_name: None,
+            require_wallet: false,
+            preflight: false,
+            duplicate_alert_delay: 0,
+            info_log_interval_secs: 0,
+            aggregate_per_cycle: false,
+            max_scan_timeframe_secs: default_max_scan_timeframe(),
+            alert_history_size: default_alert_history_size(),
+            pause_cooldown_secs: default_pause_cooldown(),
+            pause_confirmations: default_pause_confirmations(),
+            action_queue_capacity: default_action_queue_capacity(),
+            action_policy: HashMap::new(),
+            heartbeat_interval_secs: None,
+            heartbeat_url: None,
+            deadmanswitch_url: None,
+            system_name: default_system_name(),
+            min_notify_level: default_min_notify_level(),
+            teams_webhook_url: None,
+            alert_retries: default_alert_retries(),
+            alert_timeout_secs: default_alert_timeout(),
+            smtp_host: None,
+            smtp_port: default_smtp_port(),
+            smtp_username: None,
+            smtp_password: None,
+            email_from: None,
+            email_to: vec![],
+            message_bus: None,
+            http_proxy: None,
+            https_proxy: None,
+            source_label: None,
+            source_include_hostname: false,
+            alert_component: None,
+            maintenance_windows: vec![],
+            snooze_file: None,
+            max_cycles: None,
+            startup_stagger_ms: default_startup_stagger(),
+            check_stagger_ms: default_check_stagger(),
+            worker_threads: None,
+            max_concurrent_rpc: None,
+            max_blocking_threads: None,
+            fuel_client_watcher: FuelClientWatcher::default(),
+            ethereum_client_watcher: EthereumClientWatcher::default(),
+        }
+    }
+}
+
+// builds a config in code (for embedding the watchtower) with the same validation as loading one from a file
+#[derive(Clone, Debug, Default)]
+pub struct WatchtowerConfigBuilder {
+    config: WatchtowerConfig,
+}
+
+impl WatchtowerConfigBuilder {
+    pub fn new() -> Self {
+        WatchtowerConfigBuilder::default()
+    }
+
+    pub fn deployment_name(mut self, deployment_name: &str) -> Self {
+        self.config.deployment_name = Some(String::from(deployment_name));
+        self
+    }
+
+    pub fn fuel_graphql(mut self, fuel_graphql: &str) -> Self {
+        self.config.fuel_graphql = String::from(fuel_graphql);
+        self
+    }
+
+    pub fn ethereum_rpc(mut self, ethereum_rpc: &str) -> Self {
+        self.config.ethereum_rpc = String::from(ethereum_rpc);
+        self
+    }
+
+    pub fn ethereum_ws_rpc(mut self, ethereum_ws_rpc: &str) -> Self {
+        self.config.ethereum_ws_rpc = Some(String::from(ethereum_ws_rpc));
+        self
+    }
+
+    pub fn ethereum_rpc_secondary(mut self, ethereum_rpc_secondary: &str) -> Self {
+        self.config.ethereum_rpc_secondary = Some(String::from(ethereum_rpc_secondary));
+        self
+    }
+
+    pub fn contract_addresses(mut self, state: &str, portal: &str, gateway: &str) -> Self {
+        self.config.state_contract_address = String::from(state);
+        self.config.portal_contract_address = String::from(portal);
+        self.config.gateway_contract_address = String::from(gateway);
+        self
+    }
+
+    pub fn ethereum_wallet_key(mut self, ethereum_wallet_key: &str) -> Self {
+        self.config.ethereum_wallet_key = Some(String::from(ethereum_wallet_key));
+        self
+    }
+
+    pub fn ethereum_wallet_key_path(mut self, ethereum_wallet_key_path: &str) -> Self {
+        self.config.ethereum_wallet_key_path = Some(String::from(ethereum_wallet_key_path));
+        self
+    }
+
+    pub fn require_wallet(mut self, require_wallet: bool) -> Self {
+        self.config.require_wallet = require_wallet;
+        self
+    }
+
+    pub fn duplicate_alert_delay(mut self, duplicate_alert_delay: u32) -> Self {
+        self.config.duplicate_alert_delay = duplicate_alert_delay;
+        self
+    }
+
+    pub fn info_log_interval_secs(mut self, info_log_interval_secs: u32) -> Self {
+        self.config.info_log_interval_secs = info_log_interval_secs;
+        self
+    }
+
+    pub fn aggregate_per_cycle(mut self, aggregate_per_cycle: bool) -> Self {
+        self.config.aggregate_per_cycle = aggregate_per_cycle;
+        self
+    }
+
+    pub fn system_name(mut self, system_name: &str) -> Self {
+        self.config.system_name = String::from(system_name);
+        self
+    }
+
+    pub fn min_notify_level(mut self, min_notify_level: AlertLevel) -> Self {
+        self.config.min_notify_level = min_notify_level;
+        self
+    }
+
+    pub fn teams_webhook_url(mut self, teams_webhook_url: &str) -> Self {
+        self.config.teams_webhook_url = Some(String::from(teams_webhook_url));
+        self
+    }
+
+    pub fn email_alerts(mut self, smtp_host: &str, smtp_port: u16, email_from: &str, email_to: &[&str]) -> Self {
+        self.config.smtp_host = Some(String::from(smtp_host));
+        self.config.smtp_port = smtp_port;
+        self.config.email_from = Some(String::from(email_from));
+        self.config.email_to = email_to.iter().map(|to| String::from(*to)).collect();
+        self
+    }
+
+    pub fn smtp_credentials(mut self, smtp_username: &str, smtp_password: &str) -> Self {
+        self.config.smtp_username = Some(String::from(smtp_username));
+        self.config.smtp_password = Some(String::from(smtp_password));
+        self
+    }
+
+    pub fn message_bus(mut self, bus: MessageBusKind, servers: &str, subject: &str) -> Self {
+        self.config.message_bus = Some(MessageBus {
+            bus,
+            servers: String::from(servers),
+            subject: String::from(subject),
+        });
+        self
+    }
+
+    pub fn action_policy(mut self, alert_type: AlertType, action: EthereumAction) -> Self {
+        self.config.action_policy.insert(alert_type, action);
+        self
+    }
+
+    pub fn max_cycles(mut self, max_cycles: u64) -> Self {
+        self.config.max_cycles = Some(max_cycles);
+        self
+    }
+
+    pub fn fuel_client_watcher(mut self, fuel_client_watcher: FuelClientWatcher) -> Self {
+        self.config.fuel_client_watcher = fuel_client_watcher;
+        self
+    }
+
+    pub fn ethereum_client_watcher(mut self, ethereum_client_watcher: EthereumClientWatcher) -> Self {
+        self.config.ethereum_client_watcher = ethereum_client_watcher;
+        self
+    }
+
+    pub fn build(self) -> Result<WatchtowerConfig> {
+        if self.config.fuel_graphql.is_empty() || self.config.ethereum_rpc.is_empty() {
+            return Err(WatchtowerError::Config(String::from(
+                "Both fuel_graphql and ethereum_rpc must be set.",
+            )));
+        }
+        prepare_config(self.config)
+    }
+}
+
+// turns off the checks relying on rpc methods the ethereum provider doesn't support
+// (otherwise they would fail and alert every cycle)
+fn apply_provider_capabilities(config: &mut WatchtowerConfig) {
+    let watch_config = &mut config.ethereum_client_watcher;
+    let capabilities = watch_config.provider_capabilities.clone();
+    if !capabilities.get_logs {
+        watch_config.invalid_state_commit_alert.enabled = Some(false);
+        watch_config.commit_stall_alert.enabled = Some(false);
+        watch_config.commit_cadence_alert.enabled = Some(false);
+        watch_config.supply_mismatch_alert.enabled = Some(false);
+        watch_config.unexpected_token_alert.enabled = Some(false);
+        watch_config.inactivity_alert.enabled = Some(false);
+        watch_config.empty_logs_alert.enabled = Some(false);
+        watch_config.discovered_token_deposit_alert.enabled = Some(false);
+        watch_config.auto_discover_tokens = false;
+        for alert in &mut watch_config.portal_deposit_alerts {
+            alert.enabled = Some(false);
+        }
+        for alert in &mut watch_config.gateway_deposit_alerts {
+            alert.enabled = Some(false);
+        }
+        for alert in &mut watch_config.portal_imbalance_alerts {
+            alert.enabled = Some(false);
+        }
+        for alert in &mut watch_config.gateway_imbalance_alerts {
+            alert.enabled = Some(false);
+        }
+        log::warn!(
+            "The ethereum provider does not support eth_getLogs. State commit, deposit, imbalance, supply mismatch, unexpected token and inactivity checks are disabled."
+        );
+    }
+    if !capabilities.finalized_block_tag {
+        watch_config.finality_lag_alert.enabled = Some(false);
+        log::warn!(
+            "The ethereum provider does not support the finalized block tag. The finality lag check is disabled."
+        );
+    }
+}
+
+// gives alert configs without their own action the action the policy maps their alert type to
+// (an explicit "None" is an action of its own, so a block can opt out of its policy action)
+fn apply_action_policy(config: &mut WatchtowerConfig) {
+    let policy = &config.action_policy;
+    let resolve = |action: &mut Option<EthereumAction>, alert_type: AlertType| {
+        if action.is_none() {
+            *action = policy.get(&alert_type).cloned();
+        }
+    };
+
+    let fuel = &mut config.fuel_client_watcher;
+    resolve(&mut fuel.connection_alert.alert_action, AlertType::FuelConnection);
+    resolve(
+        &mut fuel.block_production_alert.alert_action,
+        AlertType::FuelBlockProduction,
+    );
+    resolve(
+        &mut fuel.block_production_alert.critical_alert_action,
+        AlertType::FuelBlockProductionStopped,
+    );
+    resolve(
+        &mut fuel.block_height_stall_alert.alert_action,
+        AlertType::FuelBlockHeightStalled,
+    );
+    for alert in &mut fuel.portal_withdraw_alerts {
+        resolve(&mut alert.alert_action, AlertType::FuelPortalWithdraw);
+    }
+    for alert in &mut fuel.gateway_withdraw_alerts {
+        resolve(&mut alert.alert_action, AlertType::FuelGatewayWithdraw);
+    }
+
+    let ethereum = &mut config.ethereum_client_watcher;
+    resolve(
+        &mut ethereum.connection_alert.alert_action,
+        AlertType::EthereumConnection,
+    );
+    resolve(
+        &mut ethereum.block_production_alert.alert_action,
+        AlertType::EthereumBlockProduction,
+    );
+    resolve(
+        &mut ethereum.block_production_alert.critical_alert_action,
+        AlertType::EthereumBlockProductionStopped,
+    );
+    resolve(
+        &mut ethereum.account_funds_alert.alert_action,
+        AlertType::EthereumAccountFunds,
+    );
+    resolve(
+        &mut ethereum.balance_runway_alert.alert_action,
+        AlertType::EthereumAccountFundsRunway,
+    );
+    resolve(
+        &mut ethereum.unexpected_wallet_activity_alert.alert_action,
+        AlertType::UnexpectedWalletActivity,
+    );
+    resolve(
+        &mut ethereum.invalid_state_commit_alert.alert_action,
+        AlertType::EthereumInvalidStateCommit,
+    );
+    resolve(
+        &mut ethereum.commit_stall_alert.alert_action,
+        AlertType::EthereumCommitStalled,
+    );
+    resolve(
+        &mut ethereum.finality_lag_alert.alert_action,
+        AlertType::EthereumFinalityLag,
+    );
+    resolve(&mut ethereum.gas_price_alert.alert_action, AlertType::EthereumGasSpike);
+    resolve(&mut ethereum.stale_rpc_alert.alert_action, AlertType::StaleRpcNode);
+    resolve(
+        &mut ethereum.empty_logs_alert.alert_action,
+        AlertType::SuspiciousEmptyLogs,
+    );
+    resolve(&mut ethereum.ownership_alert.alert_action, AlertType::OwnershipChanged);
+    resolve(
+        &mut ethereum.supply_mismatch_alert.alert_action,
+        AlertType::SupplyMismatch,
+    );
+    resolve(
+        &mut ethereum.unexpected_token_alert.alert_action,
+        AlertType::UnexpectedToken,
+    );
+    resolve(&mut ethereum.inactivity_alert.alert_action, AlertType::BridgeInactive);
+    resolve(
+        &mut ethereum.discovered_token_deposit_alert.alert_action,
+        AlertType::EthereumGatewayDeposit,
+    );
+    for alert in &mut ethereum.portal_deposit_alerts {
+        resolve(&mut alert.alert_action, AlertType::EthereumPortalDeposit);
+    }
+    for alert in &mut ethereum.gateway_deposit_alerts {
+        resolve(&mut alert.alert_action, AlertType::EthereumGatewayDeposit);
+    }
+    for alert in &mut ethereum.portal_imbalance_alerts {
+        resolve(&mut alert.alert_action, AlertType::EthereumPortalImbalance);
+    }
+    for alert in &mut ethereum.gateway_imbalance_alerts {
+        resolve(&mut alert.alert_action, AlertType::EthereumGatewayImbalance);
+    }
+}
+
+// adds a description of a check to the list if it is enabled
+fn describe_check(
+    checks: &mut Vec<String>,
+    name: &str,
+    level: &AlertLevel,
+    action: &Option<EthereumAction>,
+    threshold: String,
+) {
+    if *level == AlertLevel::None {
+        return;
+    }
+    let mut description = format!("{name} ({:?}", level);
+    if !threshold.is_empty() {
+        description = format!("{description}, {threshold}");
+    }
+    match action {
+        Some(action) if *action != EthereumAction::None => description = format!("{description}, action {:?}", action),
+        _ => {}
+    }
+    checks.push(format!("{description})"));
+}
+
+// checks if a check should run (checks default to running only when they alert, but can be
+// explicitly enabled to query without alerting or disabled to skip querying entirely)
+pub fn is_check_enabled(enabled: Option<bool>, level: &AlertLevel) -> bool {
+    match enabled {
+        Some(enabled) => enabled,
+        None => *level != AlertLevel::None,
+    }
+}
+
+// puts an address in lowercase with a 0x prefix so differently formatted addresses can be compared
+pub fn normalize_address(address: &str) -> String {
+    let address = address.trim().to_lowercase();
+    format!("0x{}", address.trim_start_matches("0x"))
+}
+
+// checks if an address is in a list of addresses (ignoring formatting differences)
+pub fn is_address_in_list(address: &str, list: &[String]) -> bool {
+    let address = normalize_address(address);
+    list.iter().any(|a| normalize_address(a) == address)
+}
+
+// replaces any of the given secrets in the text
+pub fn redact_secrets(text: &str, secrets: &[String]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets {
+        text = text.replace(secret.as_str(), "***");
+    }
+    text
+}
+
+// where alerts are published for downstream consumers (servers is the nats url or the kafka bootstrap servers)
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct MessageBus {
+    pub bus: MessageBusKind,
+    pub servers: String,
+    pub subject: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MessageBusKind {
+    Nats,
+    Kafka,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct MaintenanceWindow {
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+impl MaintenanceWindow {
+    // checks if the given unix time (in seconds) is within the window
+    pub fn contains(&self, time: u64) -> bool {
+        time >= self.start_time && time < self.end_time
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct FuelClientWatcher {
+    #[serde(default = "default_fuel_poll_logging_skip")]
+    pub poll_logging_skip: u64,
     pub connection_alert: GenericAlert,
     pub block_production_alert: BlockProductionAlert,
+    #[serde(default)]
+    pub block_height_stall_alert: BlockHeightStallAlert,
     pub portal_withdraw_alerts: Vec<WithdrawAlert>,
     pub gateway_withdraw_alerts: Vec<WithdrawAlert>,
+    #[serde(default)]
+    pub ignored_token_addresses: Vec<String>,
+    #[serde(default = "default_withdrawal_scan_mode")]
+    pub withdrawal_scan_mode: WithdrawalScanMode,
+    #[serde(default = "default_withdrawal_sample_rate")]
+    pub withdrawal_sample_rate: f64,
+    #[serde(default = "default_withdrawal_receipt_filter")]
+    pub withdrawal_receipt_filter: WithdrawalReceiptFilter,
+}
+
+// how thoroughly fuel blocks are scanned for withdrawals (sampling trades accuracy for fewer queries)
+#[derive(Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+pub enum WithdrawalScanMode {
+    Full,
+    Sampled,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+// which transactions have their message receipts counted as withdrawals
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WithdrawalReceiptFilter {
+    All,
+    SuccessfulScripts,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct EthereumClientWatcher {
+    #[serde(default = "default_ethereum_poll_logging_skip")]
+    pub poll_logging_skip: u64,
     pub connection_alert: GenericAlert,
+    #[serde(default)]
+    pub pause_on_rpc_outage: bool,
+    #[serde(default = "default_rpc_outage_pause")]
+    pub rpc_outage_pause_secs: u32,
     pub block_production_alert: BlockProductionAlert,
     pub account_funds_alert: AccountFundsAlert,
+    #[serde(default)]
+    pub balance_runway_alert: BalanceRunwayAlert,
+    #[serde(default)]
+    pub unexpected_wallet_activity_alert: GenericAlert,
     pub invalid_state_commit_alert: GenericAlert,
+    #[serde(default)]
+    pub commit_stall_alert: CommitStallAlert,
+    #[serde(default)]
+    pub finality_lag_alert: FinalityLagAlert,
+    #[serde(default)]
+    pub commit_cadence_alert: CommitCadenceAlert,
+    #[serde(default)]
+    pub gas_price_alert: GasPriceAlert,
+    #[serde(default)]
+    pub stale_rpc_alert: StaleRpcAlert,
+    #[serde(default)]
+    pub empty_logs_alert: EmptyLogsAlert,
+    #[serde(default)]
+    pub ownership_alert: OwnershipAlert,
     pub portal_deposit_alerts: Vec<DepositAlert>,
     pub gateway_deposit_alerts: Vec<DepositAlert>,
+    #[serde(default)]
+    pub portal_imbalance_alerts: Vec<ImbalanceAlert>,
+    #[serde(default)]
+    pub gateway_imbalance_alerts: Vec<ImbalanceAlert>,
+    #[serde(default)]
+    pub supply_mismatch_alert: SupplyMismatchAlert,
+    #[serde(default)]
+    pub unexpected_token_alert: UnexpectedTokenAlert,
+    #[serde(default)]
+    pub inactivity_alert: InactivityAlert,
+    #[serde(default)]
+    pub ignored_token_addresses: Vec<String>,
+    #[serde(default)]
+    pub verify_token_names: bool,
+    #[serde(default)]
+    pub auto_discover_tokens: bool,
+    #[serde(default)]
+    pub discovered_token_deposit_alert: DiscoveredTokenAlert,
+    #[serde(default)]
+    pub provider_capabilities: ProviderCapabilities,
+}
+
+impl Default for FuelClientWatcher {
+    fn default() -> Self {
+        FuelClientWatcher {
+            poll_logging_skip: default_fuel_poll_logging_skip(),
+            connection_alert: GenericAlert::default(),
+            block_production_alert: BlockProductionAlert::default(),
+            block_height_stall_alert: BlockHeightStallAlert::default(),
+            portal_withdraw_alerts: vec![],
+            gateway_withdraw_alerts: vec![],
+            ignored_token_addresses: vec![],
+            withdrawal_scan_mode: default_withdrawal_scan_mode(),
+            withdrawal_sample_rate: default_withdrawal_sample_rate(),
+            withdrawal_receipt_filter: default_withdrawal_receipt_filter(),
+        }
+    }
+}
+
+impl Default for EthereumClientWatcher {
+    fn default() -> Self {
+        EthereumClientWatcher {
+            poll_logging_skip: default_ethereum_poll_logging_skip(),
+            connection_alert: GenericAlert::default(),
+            pause_on_rpc_outage: false,
+            rpc_outage_pause_secs: default_rpc_outage_pause(),
+            block_production_alert: BlockProductionAlert::default(),
+            account_funds_alert: AccountFundsAlert::default(),
+            balance_runway_alert: BalanceRunwayAlert::default(),
+            unexpected_wallet_activity_alert: GenericAlert::default(),
+            invalid_state_commit_alert: GenericAlert::default(),
+            commit_stall_alert: CommitStallAlert::default(),
+            finality_lag_alert: FinalityLagAlert::default(),
+            commit_cadence_alert: CommitCadenceAlert::default(),
+            gas_price_alert: GasPriceAlert::default(),
+            stale_rpc_alert: StaleRpcAlert::default(),
+            empty_logs_alert: EmptyLogsAlert::default(),
+            ownership_alert: OwnershipAlert::default(),
+            portal_deposit_alerts: vec![],
+            gateway_deposit_alerts: vec![],
+            portal_imbalance_alerts: vec![],
+            gateway_imbalance_alerts: vec![],
+            supply_mismatch_alert: SupplyMismatchAlert::default(),
+            unexpected_token_alert: UnexpectedTokenAlert::default(),
+            inactivity_alert: InactivityAlert::default(),
+            ignored_token_addresses: vec![],
+            verify_token_names: false,
+            auto_discover_tokens: false,
+            discovered_token_deposit_alert: DiscoveredTokenAlert::default(),
+            provider_capabilities: ProviderCapabilities::default(),
+        }
+    }
+}
+
+impl FuelClientWatcher {
+    pub fn is_token_ignored(&self, token_address: &str) -> bool {
+        is_address_in_list(token_address, &self.ignored_token_addresses)
+    }
+}
+
+impl EthereumClientWatcher {
+    pub fn is_token_ignored(&self, token_address: &str) -> bool {
+        is_address_in_list(token_address, &self.ignored_token_addresses)
+    }
+
+    // checks if a token already has its own deposit alert (and so isn't covered by token discovery)
+    pub fn is_token_configured(&self, token_address: &str) -> bool {
+        let configured: Vec<String> = self
+            .gateway_deposit_alerts
+            .iter()
+            .map(|alert| alert.token_address.clone())
+            .collect();
+        is_address_in_list(token_address, &configured)
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct AlertDetails {
+    pub runbook_url: Option<String>,
+    pub custom_summary: Option<String>,
+    #[serde(default = "default_consecutive_failures_to_alert")]
+    pub consecutive_failures_to_alert: u32,
+}
+
+impl Default for AlertDetails {
+    fn default() -> Self {
+        AlertDetails {
+            runbook_url: None,
+            custom_summary: None,
+            consecutive_failures_to_alert: default_consecutive_failures_to_alert(),
+        }
+    }
+}
+
+impl AlertDetails {
+    // appends the configured summary and runbook link to an alert message
+    pub fn annotate(&self, text: String) -> String {
+        let mut text = text;
+        match &self.custom_summary {
+            Some(summary) => text = format!("{text} {summary}"),
+            None => {}
+        }
+        match &self.runbook_url {
+            Some(url) => text = format!("{text} Runbook: {url}"),
+            None => {}
+        }
+        text
+    }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct GenericAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
-    #[serde(default = "default_alert_action")]
-    pub alert_action: EthereumAction,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+}
+
+impl Default for GenericAlert {
+    fn default() -> Self {
+        GenericAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+        }
+    }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct BlockProductionAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
-    #[serde(default = "default_alert_action")]
-    pub alert_action: EthereumAction,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
     #[serde(default = "default_max_block_time")]
     pub max_block_time: u32,
+    pub critical_block_time: Option<u32>,
+    #[serde(default)]
+    pub critical_alert_action: Option<EthereumAction>,
+}
+
+// how block production looks from the time since the last block
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockProduction {
+    OnTime,
+    Slow,
+    Stopped { critical_block_time: u32 },
+}
+
+impl BlockProductionAlert {
+    // production has stopped rather than slowed once there has been no block for over the critical block time
+    pub fn get_block_production(&self, seconds_since_last_block: u32) -> BlockProduction {
+        match self.critical_block_time {
+            Some(critical_block_time) if seconds_since_last_block > critical_block_time => {
+                return BlockProduction::Stopped { critical_block_time }
+            }
+            _ => {}
+        }
+        match seconds_since_last_block > self.max_block_time {
+            true => BlockProduction::Slow,
+            false => BlockProduction::OnTime,
+        }
+    }
+}
+
+impl Default for BlockProductionAlert {
+    fn default() -> Self {
+        BlockProductionAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            max_block_time: default_max_block_time(),
+            critical_block_time: None,
+            critical_alert_action: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct BlockHeightStallAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_max_stalled_cycles")]
+    pub max_stalled_cycles: u32,
+}
+
+impl Default for BlockHeightStallAlert {
+    fn default() -> Self {
+        BlockHeightStallAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            max_stalled_cycles: default_max_stalled_cycles(),
+        }
+    }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct AccountFundsAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
-    #[serde(default = "default_alert_action")]
-    pub alert_action: EthereumAction,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
     #[serde(default = "default_minimum_balance")]
     pub min_balance: f64,
+    pub max_balance: Option<f64>,
+    pub max_balance_change: Option<f64>,
+    #[serde(default = "default_check_every_n_cycles")]
+    pub check_every_n_cycles: u64,
+}
+
+impl Default for AccountFundsAlert {
+    fn default() -> Self {
+        AccountFundsAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            min_balance: default_minimum_balance(),
+            max_balance: None,
+            max_balance_change: None,
+            check_every_n_cycles: default_check_every_n_cycles(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct BalanceRunwayAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_balance_runway_hours")]
+    pub balance_runway_hours: u32,
+}
+
+impl Default for BalanceRunwayAlert {
+    fn default() -> Self {
+        BalanceRunwayAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            balance_runway_hours: default_balance_runway_hours(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct CommitStallAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_max_commit_interval")]
+    pub max_commit_interval_secs: u32,
+}
+
+impl Default for CommitStallAlert {
+    fn default() -> Self {
+        CommitStallAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            max_commit_interval_secs: default_max_commit_interval(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct FinalityLagAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_max_finality_lag_blocks")]
+    pub max_finality_lag_blocks: u64,
+}
+
+impl Default for FinalityLagAlert {
+    fn default() -> Self {
+        FinalityLagAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            max_finality_lag_blocks: default_max_finality_lag_blocks(),
+        }
+    }
+}
+
+// the rpc features the ethereum provider supports (checks needing a missing one are turned off rather than failing)
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct ProviderCapabilities {
+    #[serde(default = "default_provider_capability")]
+    pub get_logs: bool,
+    #[serde(default = "default_provider_capability")]
+    pub finalized_block_tag: bool,
+}
+
+impl Default for ProviderCapabilities {
+    fn default() -> Self {
+        ProviderCapabilities {
+            get_logs: default_provider_capability(),
+            finalized_block_tag: default_provider_capability(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct GasPriceAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_max_gas_price_gwei")]
+    pub max_gas_price_gwei: f64,
+}
+
+impl Default for GasPriceAlert {
+    fn default() -> Self {
+        GasPriceAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            max_gas_price_gwei: default_max_gas_price_gwei(),
+        }
+    }
+}
+
+// alerts when the ethereum provider reports a latest block behind one it already reported (a lagging node)
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct StaleRpcAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default)]
+    pub max_block_regression: u64,
+}
+
+impl Default for StaleRpcAlert {
+    fn default() -> Self {
+        StaleRpcAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            max_block_regression: 0,
+        }
+    }
+}
+
+// alerts when the provider returns no logs for a block its header says has logs (a lagging log index would
+// otherwise look like zero deposit and withdrawal activity)
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct EmptyLogsAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+}
+
+impl Default for EmptyLogsAlert {
+    fn default() -> Self {
+        EmptyLogsAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+        }
+    }
+}
+
+// alerts when the admin of any role on the bridge contracts changes from what was read on startup
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct OwnershipAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl Default for OwnershipAlert {
+    fn default() -> Self {
+        OwnershipAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+        }
+    }
+}
+
+// periodically reports the distribution of intervals between state contract commits
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct CommitCadenceAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_commit_sample_count")]
+    pub sample_count: usize,
+    #[serde(default = "default_commit_cadence_report_interval")]
+    pub report_interval_secs: u32,
+}
+
+impl Default for CommitCadenceAlert {
+    fn default() -> Self {
+        CommitCadenceAlert {
+            alert_level: default_alert_level(),
+            enabled: None,
+            details: AlertDetails::default(),
+            sample_count: default_commit_sample_count(),
+            report_interval_secs: default_commit_cadence_report_interval(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct DepositAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
-    #[serde(default = "default_alert_action")]
-    pub alert_action: EthereumAction,
-    #[serde(default = "default_token_name")]
-    pub token_name: String,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    pub token_name: Option<String>,
     #[serde(default = "default_token_decimals_ethereum")]
     pub token_decimals: u8,
     #[serde(default = "default_token_address")]
@@ -82,14 +1528,157 @@ pub struct DepositAlert {
     pub time_frame: u32,
     #[serde(default = "default_amount")]
     pub amount: f64,
+    pub warning_fraction: Option<f64>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct ImbalanceAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    pub token_name: Option<String>,
+    #[serde(default = "default_token_decimals_ethereum")]
+    pub token_decimals: u8,
+    #[serde(default = "default_token_address")]
+    pub token_address: String,
+    #[serde(default = "default_time_frame")]
+    pub time_frame: u32,
+    #[serde(default = "default_amount")]
+    pub imbalance_threshold: f64,
+}
+
+// compares the base asset released on ethereum with what was withdrawn on fuel
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct SupplyMismatchAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_supply_mismatch_time_frame")]
+    pub time_frame: u32,
+    #[serde(default = "default_supply_mismatch_tolerance")]
+    pub tolerance: f64,
+}
+
+impl Default for SupplyMismatchAlert {
+    fn default() -> Self {
+        SupplyMismatchAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            time_frame: default_supply_mismatch_time_frame(),
+            tolerance: default_supply_mismatch_tolerance(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct DiscoveredTokenAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_token_decimals_ethereum")]
+    pub token_decimals: u8,
+    #[serde(default = "default_time_frame")]
+    pub time_frame: u32,
+    #[serde(default = "default_amount")]
+    pub amount: f64,
+}
+
+impl Default for DiscoveredTokenAlert {
+    fn default() -> Self {
+        DiscoveredTokenAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            token_decimals: default_token_decimals_ethereum(),
+            time_frame: default_time_frame(),
+            amount: default_amount(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct UnexpectedTokenAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_time_frame")]
+    pub time_frame: u32,
+    #[serde(default)]
+    pub allowed_token_addresses: Vec<String>,
+}
+
+impl Default for UnexpectedTokenAlert {
+    fn default() -> Self {
+        UnexpectedTokenAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            time_frame: default_time_frame(),
+            allowed_token_addresses: vec![],
+        }
+    }
+}
+
+impl UnexpectedTokenAlert {
+    pub fn is_token_allowed(&self, token_address: &str) -> bool {
+        is_address_in_list(token_address, &self.allowed_token_addresses)
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct InactivityAlert {
+    #[serde(default = "default_alert_level")]
+    pub alert_level: AlertLevel,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
+    #[serde(default = "default_inactivity_window")]
+    pub inactivity_window_secs: u32,
+}
+
+impl Default for InactivityAlert {
+    fn default() -> Self {
+        InactivityAlert {
+            alert_level: default_alert_level(),
+            alert_action: None,
+            enabled: None,
+            details: AlertDetails::default(),
+            inactivity_window_secs: default_inactivity_window(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct WithdrawAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
-    #[serde(default = "default_alert_action")]
-    pub alert_action: EthereumAction,
+    #[serde(default)]
+    pub alert_action: Option<EthereumAction>,
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub details: AlertDetails,
     #[serde(default = "default_token_name")]
     pub token_name: String,
     #[serde(default = "default_token_decimals_fuel")]
@@ -100,11 +1689,61 @@ pub struct WithdrawAlert {
     pub time_frame: u32,
     #[serde(default = "default_amount")]
     pub amount: f64,
+    pub single_tx_threshold: Option<f64>,
+    pub warning_fraction: Option<f64>,
 }
 
 // deserialization default functions
-pub fn default_alert_action() -> EthereumAction {
-    EthereumAction::None
+pub fn default_max_scan_timeframe() -> u32 {
+    7 * 24 * 60 * 60
+}
+pub fn default_withdrawal_scan_mode() -> WithdrawalScanMode {
+    WithdrawalScanMode::Full
+}
+pub fn default_withdrawal_sample_rate() -> f64 {
+    0.25
+}
+pub fn default_withdrawal_receipt_filter() -> WithdrawalReceiptFilter {
+    WithdrawalReceiptFilter::All
+}
+pub fn default_alert_retries() -> u32 {
+    3
+}
+pub fn default_alert_timeout() -> u64 {
+    10
+}
+pub fn default_smtp_port() -> u16 {
+    587
+}
+pub fn default_alert_history_size() -> usize {
+    100
+}
+pub fn default_pause_cooldown() -> u32 {
+    300
+}
+pub fn default_pause_confirmations() -> usize {
+    3
+}
+pub fn default_action_queue_capacity() -> usize {
+    32
+}
+pub fn default_fuel_poll_logging_skip() -> u64 {
+    75
+}
+pub fn default_ethereum_poll_logging_skip() -> u64 {
+    50
+}
+pub fn default_startup_stagger() -> u64 {
+    1000
+}
+pub fn default_check_stagger() -> u64 {
+    100
+}
+pub fn default_system_name() -> String {
+    String::from("Fuel Canary Watchtower")
+}
+pub fn default_min_notify_level() -> AlertLevel {
+    AlertLevel::Warn
 }
 pub fn default_alert_level() -> AlertLevel {
     AlertLevel::None
@@ -112,6 +1751,39 @@ pub fn default_alert_level() -> AlertLevel {
 pub fn default_max_block_time() -> u32 {
     60
 }
+pub fn default_rpc_outage_pause() -> u32 {
+    600
+}
+pub fn default_max_stalled_cycles() -> u32 {
+    15
+}
+pub fn default_balance_runway_hours() -> u32 {
+    72
+}
+pub fn default_max_commit_interval() -> u32 {
+    3600
+}
+pub fn default_max_finality_lag_blocks() -> u64 {
+    96
+}
+pub fn default_max_gas_price_gwei() -> f64 {
+    200.0
+}
+pub fn default_commit_sample_count() -> usize {
+    50
+}
+pub fn default_commit_cadence_report_interval() -> u32 {
+    24 * 60 * 60
+}
+pub fn default_consecutive_failures_to_alert() -> u32 {
+    1
+}
+pub fn default_check_every_n_cycles() -> u64 {
+    1
+}
+pub fn default_provider_capability() -> bool {
+    true
+}
 pub fn default_minimum_balance() -> f64 {
     0.1
 }
@@ -127,6 +1799,15 @@ pub fn default_token_decimals_fuel() -> u8 {
 pub fn default_token_decimals_ethereum() -> u8 {
     18
 }
+pub fn default_inactivity_window() -> u32 {
+    24 * 60 * 60
+}
+pub fn default_supply_mismatch_time_frame() -> u32 {
+    24 * 60 * 60
+}
+pub fn default_supply_mismatch_tolerance() -> f64 {
+    1.0
+}
 pub fn default_time_frame() -> u32 {
     300
 }
@@ -134,26 +1815,938 @@ pub fn default_amount() -> f64 {
     1000.0
 }
 
+// the shape of a config file (only used to describe it in the json schema)
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum ConfigFile {
+    Single(Box<WatchtowerConfig>),
+    Multiple(Vec<WatchtowerConfig>),
+}
+
+// gets a json schema for the config file (fields, defaults and allowed values) for validating configs in an editor
+pub fn config_json_schema() -> Value {
+    serde_json::to_value(schema_for!(ConfigFile)).unwrap_or(Value::Null)
+}
+
 // loads a config from a json file
 pub fn load_config(file_path: &str) -> Result<WatchtowerConfig> {
-    let json_string = fs::read_to_string(file_path)?;
-    let mut config: WatchtowerConfig = serde_json::from_str(&json_string)?;
+    let json_string = fs::read_to_string(file_path)
+        .map_err(|e| WatchtowerError::Config(format!("Failed to read {file_path}: {e}")))?;
+    let config: WatchtowerConfig = serde_json::from_str(&json_string)
+        .map_err(|e| WatchtowerError::Config(format!("Failed to parse {file_path}: {e}")))?;
+    prepare_config(config)
+}
+
+// loads the configs for one or more bridge deployments from a json file (either a single config or a list of them)
+pub fn load_configs(file_path: &str) -> Result<Vec<WatchtowerConfig>> {
+    let json_string = fs::read_to_string(file_path)
+        .map_err(|e| WatchtowerError::Config(format!("Failed to read {file_path}: {e}")))?;
+    let json_value: Value = serde_json::from_str(&json_string)
+        .map_err(|e| WatchtowerError::Config(format!("Failed to parse {file_path}: {e}")))?;
+    let configs: Vec<WatchtowerConfig> = match json_value {
+        Value::Array(_) => serde_json::from_value(json_value),
+        _ => serde_json::from_value(json_value).map(|config| vec![config]),
+    }
+    .map_err(|e| WatchtowerError::Config(format!("Failed to parse {file_path}: {e}")))?;
+
+    if configs.len() > 1 && configs.iter().any(|config| config.deployment_name.is_none()) {
+        return Err(WatchtowerError::Config(String::from(
+            "A deployment_name is required for each deployment when monitoring multiple deployments.",
+        )));
+    }
+    check_shared_rpc_limit(&configs)?;
+    configs.into_iter().map(prepare_config).collect()
+}
+
+// the rpc request cap is shared by the whole process, so every deployment has to ask for the same one
+fn check_shared_rpc_limit(configs: &[WatchtowerConfig]) -> Result<()> {
+    match configs.split_first() {
+        Some((first, rest))
+            if rest
+                .iter()
+                .any(|config| config.max_concurrent_rpc != first.max_concurrent_rpc) =>
+        {
+            Err(WatchtowerError::Config(String::from(
+                "max_concurrent_rpc is shared by all deployments and must be the same for each of them.",
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+// checks a url parses and uses one of the expected schemes (the url itself is left out of errors as it may hold a key)
+fn validate_url(url: &str, name: &str, schemes: &[&str]) -> Result<()> {
+    let url = Url::parse(url).map_err(|e| WatchtowerError::Config(format!("Invalid {name}: {e}")))?;
+    if !schemes.contains(&url.scheme()) {
+        return Err(WatchtowerError::Config(format!(
+            "Invalid {name}: expected a {} url but found {}.",
+            schemes.join(" or "),
+            url.scheme()
+        )));
+    }
+    Ok(())
+}
+
+// reads a private key mounted as a file (refused if other users can read it)
+fn read_wallet_key_file(path: &str) -> Result<String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| WatchtowerError::Config(format!("Failed to read ethereum wallet key file {path}: {e}")))?;
+    if metadata.permissions().mode() & 0o004 != 0 {
+        log::warn!("Ethereum wallet key file {path} is world readable.");
+        return Err(WatchtowerError::Config(format!(
+            "Ethereum wallet key file {path} is world readable. Restrict its permissions (e.g. chmod 600)."
+        )));
+    }
+    let wallet_key = fs::read_to_string(path)
+        .map_err(|e| WatchtowerError::Config(format!("Failed to read ethereum wallet key file {path}: {e}")))?;
+    match wallet_key.trim() {
+        "" => Err(WatchtowerError::Config(format!(
+            "Ethereum wallet key file {path} is empty."
+        ))),
+        wallet_key => Ok(wallet_key.to_string()),
+    }
+}
+
+// picks the ethereum wallet key from the env and the config (env key > env key file > config key file > config key)
+fn resolve_wallet_key(
+    env_key: Option<String>,
+    env_key_path: Option<String>,
+    config: &WatchtowerConfig,
+) -> Result<Option<String>> {
+    let key_path = match env_key_path {
+        Some(path) => Some(path),
+        None => config.ethereum_wallet_key_path.clone(),
+    };
+    match (env_key, key_path, config.ethereum_wallet_key.clone()) {
+        (Some(wallet_key), _, _) => Ok(Some(wallet_key)),
+        (None, Some(path), _) => Ok(Some(read_wallet_key_file(&path)?)),
+        (None, None, Some(wallet_key)) => {
+            log::warn!("Specifying the ethereum private key in the config file is not safe. Please use the {} environment variable or a key file instead.", PRIVATE_KEY_ENV_VAR);
+            Ok(Some(wallet_key))
+        }
+        (None, None, None) if config.require_wallet => Err(WatchtowerError::Config(format!(
+            "{} environment variable not specified and require_wallet is set.",
+            PRIVATE_KEY_ENV_VAR
+        ))),
+        (None, None, None) => {
+            log::warn!(
+                "{} environment variable not specified. Some alerts and actions have been disabled.",
+                PRIVATE_KEY_ENV_VAR
+            );
+            Ok(None)
+        }
+    }
+}
+
+// fills in and validates a parsed config
+fn prepare_config(config: WatchtowerConfig) -> Result<WatchtowerConfig> {
+    let mut config = config;
 
     // fill in the ethereum wallet key
-    if config.ethereum_wallet_key.is_some() {
-        log::warn!("Specifying the ethereum private key in the config file is not safe. Please use the {} environment variable instead.", PRIVATE_KEY_ENV_VAR);
-    } else {
-        config.ethereum_wallet_key = match env::var(PRIVATE_KEY_ENV_VAR) {
-            Ok(wallet_key) => Some(wallet_key),
-            Err(_) => {
-                log::warn!(
-                    "{} environment variable not specified. Some alerts and actions have been disabled.",
-                    PRIVATE_KEY_ENV_VAR
-                );
-                None
+    config.ethereum_wallet_key = resolve_wallet_key(
+        env::var(PRIVATE_KEY_ENV_VAR).ok(),
+        env::var(PRIVATE_KEY_FILE_ENV_VAR).ok(),
+        &config,
+    )?;
+
+    // validate ethereum addresses and store them in their checksummed form
+    config.state_contract_address = checksum_address(&config.state_contract_address, "state_contract_address")?;
+    config.portal_contract_address = checksum_address(&config.portal_contract_address, "portal_contract_address")?;
+    config.gateway_contract_address = checksum_address(&config.gateway_contract_address, "gateway_contract_address")?;
+    let watch_config = &mut config.ethereum_client_watcher;
+    for gateway_deposit_alert in &mut watch_config.gateway_deposit_alerts {
+        gateway_deposit_alert.token_address =
+            checksum_token_address(&gateway_deposit_alert.token_address, "gateway_deposit_alerts")?;
+    }
+    for gateway_imbalance_alert in &mut watch_config.gateway_imbalance_alerts {
+        gateway_imbalance_alert.token_address =
+            checksum_token_address(&gateway_imbalance_alert.token_address, "gateway_imbalance_alerts")?;
+    }
+    for token_address in &mut watch_config.ignored_token_addresses {
+        *token_address = checksum_address(token_address, "ignored_token_addresses")?;
+    }
+    for token_address in &mut watch_config.unexpected_token_alert.allowed_token_addresses {
+        *token_address = checksum_address(token_address, "allowed_token_addresses")?;
+    }
+
+    // catch malformed urls now rather than on first use
+    validate_url(&config.ethereum_rpc, "ethereum_rpc", &["http", "https"])?;
+    match &config.ethereum_ws_rpc {
+        Some(url) => validate_url(url, "ethereum_ws_rpc", &["ws", "wss"])?,
+        None => {}
+    }
+    for (url, name) in [
+        (&config.ethereum_rpc_secondary, "ethereum_rpc_secondary"),
+        (&config.heartbeat_url, "heartbeat_url"),
+        (&config.deadmanswitch_url, "deadmanswitch_url"),
+        (&config.teams_webhook_url, "teams_webhook_url"),
+    ] {
+        match url {
+            Some(url) => validate_url(url, name, &["http", "https"])?,
+            None => {}
+        }
+    }
+    for (limit, name) in [
+        (config.worker_threads, "worker_threads"),
+        (config.max_blocking_threads, "max_blocking_threads"),
+        (config.max_concurrent_rpc, "max_concurrent_rpc"),
+    ] {
+        match limit {
+            Some(0) => {
+                return Err(WatchtowerError::Config(format!(
+                    "Invalid {name} (0). Must be at least 1."
+                )))
             }
-        };
+            _ => {}
+        }
+    }
+    match &config.message_bus {
+        Some(message_bus) if message_bus.servers.is_empty() || message_bus.subject.is_empty() => {
+            return Err(WatchtowerError::Config(String::from(
+                "Invalid message_bus. Both servers and subject must be set.",
+            )));
+        }
+        _ => {}
+    }
+
+    apply_action_policy(&mut config);
+    apply_provider_capabilities(&mut config);
+    for action in config.get_configured_actions() {
+        match action {
+            EthereumAction::PauseGatewayToken(token_address) => {
+                checksum_address(&token_address, "PauseGatewayToken")?;
+            }
+            _ => {}
+        }
+    }
+
+    for window in &config.maintenance_windows {
+        if window.start_time >= window.end_time {
+            return Err(WatchtowerError::Config(format!(
+                "Invalid maintenance window ({} to {}). The start_time must be before the end_time.",
+                window.start_time, window.end_time
+            )));
+        }
+    }
+
+    let funds_alert = &config.ethereum_client_watcher.account_funds_alert;
+    match funds_alert.max_balance {
+        Some(max_balance) if max_balance <= funds_alert.min_balance => {
+            return Err(WatchtowerError::Config(format!(
+                "Invalid max_balance ({max_balance}). Must be greater than min_balance ({}).",
+                funds_alert.min_balance
+            )));
+        }
+        _ => {}
+    }
+    match funds_alert.max_balance_change {
+        Some(max_change) if max_change <= 0.0 => {
+            return Err(WatchtowerError::Config(format!(
+                "Invalid max_balance_change ({max_change}). Must be greater than 0."
+            )));
+        }
+        _ => {}
+    }
+
+    for (name, alert) in [
+        ("fuel", &config.fuel_client_watcher.block_production_alert),
+        ("ethereum", &config.ethereum_client_watcher.block_production_alert),
+    ] {
+        match alert.critical_block_time {
+            Some(critical_block_time) if critical_block_time <= alert.max_block_time => {
+                return Err(WatchtowerError::Config(format!(
+                    "Invalid {name} critical_block_time ({critical_block_time}). Must be greater than max_block_time ({}).",
+                    alert.max_block_time
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    let deposit_fractions = config
+        .ethereum_client_watcher
+        .portal_deposit_alerts
+        .iter()
+        .chain(&config.ethereum_client_watcher.gateway_deposit_alerts)
+        .map(|alert| alert.warning_fraction);
+    let withdraw_fractions = config
+        .fuel_client_watcher
+        .portal_withdraw_alerts
+        .iter()
+        .chain(&config.fuel_client_watcher.gateway_withdraw_alerts)
+        .map(|alert| alert.warning_fraction);
+    for warning_fraction in deposit_fractions.chain(withdraw_fractions) {
+        match warning_fraction {
+            Some(fraction) if !(fraction > 0.0 && fraction < 1.0) => {
+                return Err(WatchtowerError::Config(format!(
+                    "Invalid warning_fraction ({fraction}). Must be greater than 0 and less than 1."
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    let sample_count = config.ethereum_client_watcher.commit_cadence_alert.sample_count;
+    if sample_count < 2 {
+        return Err(WatchtowerError::Config(format!(
+            "Invalid commit_cadence_alert sample_count ({sample_count}). Must be at least 2."
+        )));
+    }
+
+    let sample_rate = config.fuel_client_watcher.withdrawal_sample_rate;
+    if !(sample_rate > 0.0 && sample_rate <= 1.0) {
+        return Err(WatchtowerError::Config(format!(
+            "Invalid withdrawal_sample_rate ({sample_rate}). Must be greater than 0 and at most 1."
+        )));
+    }
+
+    // keep scans bounded (a huge time frame means scanning millions of blocks every cycle)
+    let max_time_frame = config.max_scan_timeframe_secs;
+    if max_time_frame == 0 || max_time_frame > MAX_TIME_FRAME_SECS {
+        return Err(WatchtowerError::Config(format!(
+            "Invalid max_scan_timeframe_secs ({max_time_frame}). Must be between 1 and {MAX_TIME_FRAME_SECS}."
+        )));
+    }
+    let watch_config = &mut config.ethereum_client_watcher;
+    for alert in &mut watch_config.portal_deposit_alerts {
+        alert.time_frame = clamp_time_frame(
+            alert.time_frame,
+            max_time_frame,
+            ETHEREUM_BLOCK_TIME,
+            "portal_deposit_alerts",
+        )?;
+    }
+    for alert in &mut watch_config.gateway_deposit_alerts {
+        alert.time_frame = clamp_time_frame(
+            alert.time_frame,
+            max_time_frame,
+            ETHEREUM_BLOCK_TIME,
+            "gateway_deposit_alerts",
+        )?;
+    }
+    for alert in &mut watch_config.portal_imbalance_alerts {
+        alert.time_frame = clamp_time_frame(
+            alert.time_frame,
+            max_time_frame,
+            ETHEREUM_BLOCK_TIME,
+            "portal_imbalance_alerts",
+        )?;
+    }
+    for alert in &mut watch_config.gateway_imbalance_alerts {
+        alert.time_frame = clamp_time_frame(
+            alert.time_frame,
+            max_time_frame,
+            ETHEREUM_BLOCK_TIME,
+            "gateway_imbalance_alerts",
+        )?;
+    }
+    let alert = &mut watch_config.supply_mismatch_alert;
+    alert.time_frame = clamp_time_frame(
+        alert.time_frame,
+        max_time_frame,
+        ETHEREUM_BLOCK_TIME,
+        "supply_mismatch_alert",
+    )?;
+    let alert = &mut watch_config.unexpected_token_alert;
+    alert.time_frame = clamp_time_frame(
+        alert.time_frame,
+        max_time_frame,
+        ETHEREUM_BLOCK_TIME,
+        "unexpected_token_alert",
+    )?;
+    let alert = &mut watch_config.discovered_token_deposit_alert;
+    alert.time_frame = clamp_time_frame(
+        alert.time_frame,
+        max_time_frame,
+        ETHEREUM_BLOCK_TIME,
+        "discovered_token_deposit_alert",
+    )?;
+    let alert = &mut watch_config.inactivity_alert;
+    alert.inactivity_window_secs = clamp_time_frame(
+        alert.inactivity_window_secs,
+        max_time_frame,
+        ETHEREUM_BLOCK_TIME,
+        "inactivity_alert",
+    )?;
+    let watch_config = &mut config.fuel_client_watcher;
+    for alert in &mut watch_config.portal_withdraw_alerts {
+        alert.time_frame = clamp_time_frame(
+            alert.time_frame,
+            max_time_frame,
+            FUEL_BLOCK_TIME,
+            "portal_withdraw_alerts",
+        )?;
+    }
+    for alert in &mut watch_config.gateway_withdraw_alerts {
+        alert.time_frame = clamp_time_frame(
+            alert.time_frame,
+            max_time_frame,
+            FUEL_BLOCK_TIME,
+            "gateway_withdraw_alerts",
+        )?;
     }
 
     Ok(config)
 }
+
+// caps a time frame at the max scan time frame (time frames too large to be intentional are rejected)
+// time frames shorter than a block are rejected too since they would round down to scanning no blocks
+fn clamp_time_frame(time_frame: u32, max_time_frame: u32, block_time: u64, field_name: &str) -> Result<u32> {
+    if (time_frame as u64) < block_time {
+        return Err(WatchtowerError::Config(format!(
+            "Invalid time_frame for {field_name} ({time_frame}s). Must be at least the block time ({block_time}s)."
+        )));
+    }
+    if time_frame > MAX_TIME_FRAME_SECS {
+        return Err(WatchtowerError::Config(format!(
+            "Invalid time_frame for {field_name} ({time_frame}s). Must be at most {MAX_TIME_FRAME_SECS}s."
+        )));
+    }
+    if time_frame > max_time_frame {
+        log::warn!(
+            "The time_frame for {field_name} ({time_frame}s) exceeds max_scan_timeframe_secs. Using {max_time_frame}s instead."
+        );
+        return Ok(max_time_frame);
+    }
+    Ok(time_frame)
+}
+
+// same as checksum_address for the token of a gateway alert, which has to be set
+// (the alert structs are shared with the base asset alerts, so the field falls back to a placeholder when left out)
+fn checksum_token_address(address: &str, alerts_name: &str) -> Result<String> {
+    if address == default_token_address() {
+        return Err(WatchtowerError::Config(format!(
+            "Missing token_address in {alerts_name}. Each gateway alert must set the ERC20 token it watches."
+        )));
+    }
+    checksum_address(address, &format!("{alerts_name} token_address"))
+}
+
+// parses an ethereum address, verifying its EIP-55 checksum if it has one, and returns the checksummed form
+fn checksum_address(address: &str, field_name: &str) -> Result<String> {
+    let address = address.trim();
+    let parsed = Address::from_str(address)
+        .map_err(|e| WatchtowerError::Config(format!("Invalid address for {field_name} ({address}): {e}")))?;
+    let checksummed = to_checksum(&parsed, None);
+
+    // mixed case means the address carries a checksum which must match
+    let hex = address.trim_start_matches("0x");
+    if hex == hex.to_lowercase() || hex == hex.to_uppercase() {
+        log::warn!("Address for {field_name} ({address}) is not checksummed. Please use {checksummed} instead.");
+    } else if hex != checksummed.trim_start_matches("0x") {
+        return Err(WatchtowerError::Config(format!(
+            "Invalid checksum for {field_name} ({address}). Expected {checksummed}."
+        )));
+    }
+    Ok(checksummed)
+}
+
+// gets the proxies for http and https requests (none if neither is configured so reqwest reads the env vars itself)
+// setting any proxy turns off reqwest's env var lookup, so a scheme without a configured proxy uses its env var here
+fn get_proxy_urls(
+    http_proxy: Option<String>,
+    https_proxy: Option<String>,
+    env_var: impl Fn(&str) -> Option<String>,
+) -> (Option<String>, Option<String>) {
+    match (&http_proxy, &https_proxy) {
+        (None, None) => return (None, None),
+        _ => {}
+    }
+    let from_env = |name: &str| {
+        env_var(name)
+            .or_else(|| env_var(&name.to_lowercase()))
+            .filter(|url| !url.is_empty())
+    };
+    (
+        http_proxy.or_else(|| from_env("HTTP_PROXY")),
+        https_proxy.or_else(|| from_env("HTTPS_PROXY")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn checksum_address_accepts_valid_checksum() {
+        let address = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+        assert_eq!(checksum_address(address, "contract").unwrap(), address);
+    }
+
+    #[test]
+    fn checksum_address_checksums_lowercase() {
+        let address = "0x5fbdb2315678afecb367f032d93f642f64180aa3";
+        assert_eq!(
+            checksum_address(address, "contract").unwrap(),
+            "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+        );
+    }
+
+    #[test]
+    fn checksum_address_rejects_invalid_checksum() {
+        let address = "0x5FbDB2315678afecb367f032d93F642f64180Aa3";
+        assert!(checksum_address(address, "contract").is_err());
+    }
+
+    #[test]
+    fn checksum_address_rejects_malformed() {
+        assert!(checksum_address("0x5FbDB2315678afecb367f032d93F642f64180a", "contract").is_err());
+        assert!(checksum_address("not an address", "contract").is_err());
+        assert!(checksum_address("", "contract").is_err());
+    }
+
+    #[test]
+    fn checksum_token_address_rejects_missing_token() {
+        let err = checksum_token_address(&default_token_address(), "gateway_deposit_alerts").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Missing token_address in gateway_deposit_alerts"));
+
+        let token = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512";
+        assert_eq!(checksum_token_address(token, "gateway_deposit_alerts").unwrap(), token);
+    }
+
+    #[test]
+    fn redact_secrets_replaces_every_secret() {
+        let secrets = vec![String::from("hunter2"), String::from("0xdeadbeef")];
+        let text = "key 0xdeadbeef failed, password hunter2 (hunter2)";
+        assert_eq!(redact_secrets(text, &secrets), "key *** failed, password *** (***)");
+    }
+
+    #[test]
+    fn redact_secrets_without_secrets_keeps_text() {
+        assert_eq!(redact_secrets("nothing to hide", &[]), "nothing to hide");
+    }
+
+    #[test]
+    fn clamp_time_frame_caps_at_max_time_frame() {
+        assert_eq!(clamp_time_frame(600, 3600, 12, "alert").unwrap(), 600);
+        assert_eq!(clamp_time_frame(3600, 3600, 12, "alert").unwrap(), 3600);
+        assert_eq!(clamp_time_frame(7200, 3600, 12, "alert").unwrap(), 3600);
+    }
+
+    #[test]
+    fn clamp_time_frame_rejects_runaway_time_frame() {
+        assert!(clamp_time_frame(MAX_TIME_FRAME_SECS + 1, 3600, 12, "alert").is_err());
+    }
+
+    #[test]
+    fn clamp_time_frame_rejects_time_frame_below_block_time() {
+        assert!(clamp_time_frame(11, 3600, 12, "alert").is_err());
+        assert!(clamp_time_frame(0, 3600, 1, "alert").is_err());
+        assert_eq!(clamp_time_frame(12, 3600, 12, "alert").unwrap(), 12);
+    }
+
+    fn test_builder() -> WatchtowerConfigBuilder {
+        WatchtowerConfigBuilder::new()
+            .fuel_graphql("http://localhost:4000/graphql")
+            .ethereum_rpc("http://localhost:8545")
+            .contract_addresses(
+                "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+                "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512",
+                "0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0",
+            )
+    }
+
+    #[test]
+    fn builder_builds_a_validated_config() {
+        let config = test_builder()
+            .deployment_name("testnet")
+            .duplicate_alert_delay(120)
+            .min_notify_level(AlertLevel::Error)
+            .max_cycles(3)
+            .build()
+            .unwrap();
+        assert_eq!(config.deployment_name, Some(String::from("testnet")));
+        assert_eq!(
+            config.state_contract_address,
+            "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+        );
+        assert_eq!(config.duplicate_alert_delay, 120);
+        assert_eq!(config.min_notify_level, AlertLevel::Error);
+        assert_eq!(config.max_cycles, Some(3));
+    }
+
+    #[test]
+    fn builder_requires_endpoints() {
+        let err = WatchtowerConfigBuilder::new().build().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Both fuel_graphql and ethereum_rpc must be set."));
+    }
+
+    #[test]
+    fn builder_validates_like_a_config_file() {
+        assert!(test_builder().ethereum_ws_rpc("http://localhost:8546").build().is_err());
+        assert!(test_builder()
+            .contract_addresses("0x1234", "0x1234", "0x1234")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn wallet_key_precedence() {
+        let dir = std::env::temp_dir();
+        let env_key_file = dir.join(format!("watchtower-env-key-{}", std::process::id()));
+        let config_key_file = dir.join(format!("watchtower-config-key-{}", std::process::id()));
+        for (path, key) in [(&env_key_file, "env_file_key\n"), (&config_key_file, "config_file_key")] {
+            fs::write(path, key).unwrap();
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        let env_key_file = String::from(env_key_file.to_str().unwrap());
+        let config_key_file = String::from(config_key_file.to_str().unwrap());
+
+        let mut config = WatchtowerConfig {
+            ethereum_wallet_key: Some(String::from("config_key")),
+            ethereum_wallet_key_path: Some(config_key_file.clone()),
+            ..WatchtowerConfig::default()
+        };
+        let resolve = |env_key: Option<&str>, env_key_path: Option<&str>, config: &WatchtowerConfig| {
+            resolve_wallet_key(env_key.map(String::from), env_key_path.map(String::from), config).unwrap()
+        };
+
+        let env_key = resolve(Some("env_key"), Some(&env_key_file), &config);
+        let env_file_key = resolve(None, Some(&env_key_file), &config);
+        let config_file_key = resolve(None, None, &config);
+        config.ethereum_wallet_key_path = None;
+        let config_key = resolve(None, None, &config);
+        config.ethereum_wallet_key = None;
+        let no_key = resolve(None, None, &config);
+        config.require_wallet = true;
+        let required = resolve_wallet_key(None, None, &config);
+
+        fs::remove_file(&env_key_file).unwrap();
+        fs::remove_file(&config_key_file).unwrap();
+        assert_eq!(env_key, Some(String::from("env_key")));
+        assert_eq!(env_file_key, Some(String::from("env_file_key")));
+        assert_eq!(config_file_key, Some(String::from("config_file_key")));
+        assert_eq!(config_key, Some(String::from("config_key")));
+        assert_eq!(no_key, None);
+        assert!(required.is_err());
+    }
+
+    #[test]
+    fn world_readable_key_file_is_refused() {
+        let path = std::env::temp_dir().join(format!("watchtower-open-key-{}", std::process::id()));
+        fs::write(&path, "key").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let result = read_wallet_key_file(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn action_policy_fills_in_unset_actions_only() {
+        let mut config = WatchtowerConfig {
+            action_policy: HashMap::from([
+                (AlertType::FuelConnection, EthereumAction::PauseAll),
+                (AlertType::EthereumConnection, EthereumAction::PauseGateway),
+            ]),
+            ..WatchtowerConfig::default()
+        };
+        config.ethereum_client_watcher.connection_alert.alert_action = Some(EthereumAction::PauseState);
+        apply_action_policy(&mut config);
+
+        assert_eq!(
+            config.fuel_client_watcher.connection_alert.alert_action,
+            Some(EthereumAction::PauseAll)
+        );
+        assert_eq!(
+            config.ethereum_client_watcher.connection_alert.alert_action,
+            Some(EthereumAction::PauseState)
+        );
+        assert_eq!(config.fuel_client_watcher.block_production_alert.alert_action, None);
+    }
+
+    #[test]
+    fn explicit_none_action_overrides_the_policy() {
+        let mut config: Value = serde_json::from_str(include_str!("../watchtower_config.json.example")).unwrap();
+        config["fuel_graphql"] = Value::from("http://localhost:4000/graphql");
+        config["ethereum_rpc"] = Value::from("http://localhost:8545");
+        config["action_policy"] = json!({ "FuelConnection": "PauseAll", "FuelBlockProduction": "PauseAll" });
+        config["fuel_client_watcher"]["connection_alert"]["alert_action"] = Value::from("None");
+        let config = prepare_config(serde_json::from_value(config).unwrap()).unwrap();
+
+        assert_eq!(
+            config.fuel_client_watcher.connection_alert.alert_action,
+            Some(EthereumAction::None)
+        );
+        assert_eq!(
+            config.fuel_client_watcher.block_production_alert.alert_action,
+            Some(EthereumAction::PauseAll)
+        );
+        assert!(!config.get_summary().contains("connection (Warn, action"));
+    }
+
+    #[test]
+    fn deployments_must_share_the_rpc_limit() {
+        let deployment = |max_concurrent_rpc| WatchtowerConfig {
+            max_concurrent_rpc,
+            ..WatchtowerConfig::default()
+        };
+        assert!(check_shared_rpc_limit(&[]).is_ok());
+        assert!(check_shared_rpc_limit(&[deployment(Some(4))]).is_ok());
+        assert!(check_shared_rpc_limit(&[deployment(Some(4)), deployment(Some(4))]).is_ok());
+        assert!(check_shared_rpc_limit(&[deployment(None), deployment(None)]).is_ok());
+        assert!(check_shared_rpc_limit(&[deployment(Some(4)), deployment(Some(8))]).is_err());
+        assert!(check_shared_rpc_limit(&[deployment(Some(4)), deployment(None)]).is_err());
+    }
+
+    fn test_env(name: &str) -> Option<String> {
+        match name {
+            "HTTP_PROXY" => Some(String::from("http://env-http:3128")),
+            "https_proxy" => Some(String::from("http://env-https:3128")),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn proxies_fall_back_to_env_per_scheme() {
+        assert_eq!(
+            get_proxy_urls(Some(String::from("http://proxy:8080")), None, test_env),
+            (
+                Some(String::from("http://proxy:8080")),
+                Some(String::from("http://env-https:3128"))
+            )
+        );
+        assert_eq!(
+            get_proxy_urls(None, Some(String::from("http://proxy:8443")), test_env),
+            (
+                Some(String::from("http://env-http:3128")),
+                Some(String::from("http://proxy:8443"))
+            )
+        );
+    }
+
+    #[test]
+    fn proxies_are_left_to_reqwest_when_none_configured() {
+        assert_eq!(get_proxy_urls(None, None, test_env), (None, None));
+    }
+
+    #[tokio::test]
+    async fn http_client_sends_through_the_configured_proxy() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_url = format!("http://{}", listener.local_addr().unwrap());
+        let request_line = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            std::io::BufRead::read_line(&mut reader, &mut request_line).unwrap();
+            std::io::Write::write_all(
+                &mut stream,
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+            request_line
+        });
+
+        let mut config = test_builder().build().unwrap();
+        config.http_proxy = Some(proxy_url);
+        let client = config.http_client(Some(Duration::from_secs(5))).unwrap();
+        let response = client.get("http://watchtower.invalid/ping").send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            request_line.join().unwrap().trim_end(),
+            "GET http://watchtower.invalid/ping HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn inactivity_window_is_clamped_to_max_scan_time_frame() {
+        let mut ethereum_client_watcher = EthereumClientWatcher::default();
+        ethereum_client_watcher.inactivity_alert.inactivity_window_secs = 30 * 24 * 60 * 60;
+        let config = test_builder()
+            .ethereum_client_watcher(ethereum_client_watcher.clone())
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.ethereum_client_watcher.inactivity_alert.inactivity_window_secs,
+            default_max_scan_timeframe()
+        );
+
+        ethereum_client_watcher.inactivity_alert.inactivity_window_secs = 1;
+        assert!(test_builder()
+            .ethereum_client_watcher(ethereum_client_watcher)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn block_production_is_slow_past_the_max_and_stopped_past_the_critical_time() {
+        let alert = BlockProductionAlert {
+            max_block_time: 60,
+            critical_block_time: Some(600),
+            ..BlockProductionAlert::default()
+        };
+        assert_eq!(alert.get_block_production(12), BlockProduction::OnTime);
+        assert_eq!(alert.get_block_production(60), BlockProduction::OnTime);
+        assert_eq!(alert.get_block_production(61), BlockProduction::Slow);
+        assert_eq!(alert.get_block_production(600), BlockProduction::Slow);
+        assert_eq!(
+            alert.get_block_production(601),
+            BlockProduction::Stopped {
+                critical_block_time: 600
+            }
+        );
+    }
+
+    #[test]
+    fn block_production_is_never_stopped_without_a_critical_time() {
+        let alert = BlockProductionAlert {
+            max_block_time: 60,
+            ..BlockProductionAlert::default()
+        };
+        assert_eq!(alert.get_block_production(u32::MAX), BlockProduction::Slow);
+    }
+
+    #[test]
+    fn schema_accepts_the_example_config_and_rejects_bad_ones() {
+        let schema = jsonschema::JSONSchema::compile(&config_json_schema()).unwrap();
+        let example: Value = serde_json::from_str(include_str!("../watchtower_config.json.example")).unwrap();
+        assert!(schema.is_valid(&example));
+        assert!(schema.is_valid(&json!([example.clone(), example.clone()])));
+
+        let mut unknown_level = example.clone();
+        unknown_level["fuel_client_watcher"]["connection_alert"]["alert_level"] = Value::from("Loud");
+        assert!(!schema.is_valid(&unknown_level));
+
+        let mut wrong_type = example.clone();
+        wrong_type["ethereum_client_watcher"]["portal_deposit_alerts"][0]["time_frame"] = Value::from("300");
+        assert!(!schema.is_valid(&wrong_type));
+
+        let mut missing_field = example.clone();
+        missing_field.as_object_mut().unwrap().remove("fuel_graphql");
+        assert!(!schema.is_valid(&missing_field));
+        assert!(!schema.is_valid(&json!("watchtower_config.json")));
+    }
+
+    #[test]
+    fn explicit_enabled_overrides_the_alert_level() {
+        assert!(is_check_enabled(Some(true), &AlertLevel::Warn));
+        assert!(is_check_enabled(Some(true), &AlertLevel::None));
+        assert!(!is_check_enabled(Some(false), &AlertLevel::Warn));
+        assert!(!is_check_enabled(Some(false), &AlertLevel::None));
+
+        // left unset, a check only runs when it alerts
+        assert!(is_check_enabled(None, &AlertLevel::Error));
+        assert!(!is_check_enabled(None, &AlertLevel::None));
+    }
+
+    #[test]
+    fn summary_mentions_every_configured_alert() {
+        fn warn<T: serde::de::DeserializeOwned>() -> T {
+            serde_json::from_value(json!({ "alert_level": "Warn" })).unwrap()
+        }
+        let mut config = WatchtowerConfig::default();
+        let fuel = &mut config.fuel_client_watcher;
+        fuel.connection_alert.alert_level = AlertLevel::Warn;
+        fuel.block_production_alert.alert_level = AlertLevel::Warn;
+        fuel.block_production_alert.critical_block_time = Some(600);
+        fuel.block_height_stall_alert.alert_level = AlertLevel::Warn;
+        fuel.portal_withdraw_alerts = vec![warn()];
+        fuel.gateway_withdraw_alerts = vec![warn()];
+        let ethereum = &mut config.ethereum_client_watcher;
+        ethereum.connection_alert.alert_level = AlertLevel::Warn;
+        ethereum.block_production_alert.alert_level = AlertLevel::Warn;
+        ethereum.block_production_alert.critical_block_time = Some(600);
+        ethereum.account_funds_alert.alert_level = AlertLevel::Warn;
+        ethereum.balance_runway_alert.alert_level = AlertLevel::Warn;
+        ethereum.unexpected_wallet_activity_alert.alert_level = AlertLevel::Warn;
+        ethereum.invalid_state_commit_alert.alert_level = AlertLevel::Warn;
+        ethereum.commit_stall_alert.alert_level = AlertLevel::Warn;
+        ethereum.finality_lag_alert.alert_level = AlertLevel::Warn;
+        ethereum.gas_price_alert.alert_level = AlertLevel::Warn;
+        ethereum.stale_rpc_alert.alert_level = AlertLevel::Warn;
+        ethereum.ownership_alert.alert_level = AlertLevel::Warn;
+        ethereum.empty_logs_alert.alert_level = AlertLevel::Warn;
+        ethereum.commit_cadence_alert.alert_level = AlertLevel::Warn;
+        ethereum.portal_deposit_alerts = vec![warn()];
+        ethereum.gateway_deposit_alerts = vec![warn()];
+        ethereum.auto_discover_tokens = true;
+        ethereum.discovered_token_deposit_alert.alert_level = AlertLevel::Warn;
+        ethereum.portal_imbalance_alerts = vec![warn()];
+        ethereum.gateway_imbalance_alerts = vec![warn()];
+        ethereum.supply_mismatch_alert.alert_level = AlertLevel::Warn;
+        ethereum.inactivity_alert.alert_level = AlertLevel::Warn;
+
+        let summary = config.get_summary();
+        let (fuel_summary, ethereum_summary) = summary.split_once("Ethereum checks:").unwrap();
+        for check in [
+            "connection (Warn",
+            "block production (Warn",
+            "block production stopped (Error",
+            "block height stall (Warn",
+            "base asset withdrawals (Warn",
+            "token withdrawals (Warn",
+        ] {
+            assert!(fuel_summary.contains(check), "{check} missing from {fuel_summary}");
+        }
+        for check in [
+            "connection (Warn",
+            "block production (Warn",
+            "block production stopped (Error",
+            "account funds (Warn",
+            "balance runway (Warn",
+            "unexpected wallet activity (Warn",
+            "invalid state commits (Warn",
+            "commit stall (Warn",
+            "finality lag (Warn",
+            "gas price (Warn",
+            "stale rpc node (Warn",
+            "ownership (Warn",
+            "empty logs (Warn",
+            "commit cadence (Warn",
+            "base asset deposits (Warn",
+            "token deposits (Warn",
+            "discovered token deposits (Warn",
+            "base asset imbalance (Warn",
+            "token imbalance (Warn",
+            "supply mismatch (Warn",
+            "bridge inactivity (Warn",
+        ] {
+            assert!(
+                ethereum_summary.contains(check),
+                "{check} missing from {ethereum_summary}"
+            );
+        }
+
+        // checks left at no alert level are left out
+        let summary = WatchtowerConfig::default().get_summary();
+        assert!(summary.ends_with("Fuel checks: []. Ethereum checks: []."), "{summary}");
+    }
+
+    #[test]
+    fn unsupported_methods_turn_off_the_checks_needing_them() {
+        let mut config = WatchtowerConfig::default();
+        config.ethereum_client_watcher.provider_capabilities.finalized_block_tag = false;
+        apply_provider_capabilities(&mut config);
+        let watch_config = &config.ethereum_client_watcher;
+        assert_eq!(watch_config.finality_lag_alert.enabled, Some(false));
+        assert!(!is_check_enabled(
+            watch_config.finality_lag_alert.enabled,
+            &AlertLevel::Error
+        ));
+        assert_eq!(watch_config.commit_stall_alert.enabled, None);
+        assert_eq!(watch_config.gas_price_alert.enabled, None);
+
+        let mut config = WatchtowerConfig::default();
+        config.ethereum_client_watcher.provider_capabilities.get_logs = false;
+        config.ethereum_client_watcher.auto_discover_tokens = true;
+        apply_provider_capabilities(&mut config);
+        let watch_config = &config.ethereum_client_watcher;
+        assert_eq!(watch_config.invalid_state_commit_alert.enabled, Some(false));
+        assert_eq!(watch_config.commit_stall_alert.enabled, Some(false));
+        assert_eq!(watch_config.inactivity_alert.enabled, Some(false));
+        assert!(!watch_config.auto_discover_tokens);
+        assert_eq!(watch_config.finality_lag_alert.enabled, None);
+        assert_eq!(watch_config.gas_price_alert.enabled, None);
+
+        // everything is left alone when the provider supports it all
+        let mut config = WatchtowerConfig::default();
+        apply_provider_capabilities(&mut config);
+        assert_eq!(config.ethereum_client_watcher.commit_stall_alert.enabled, None);
+        assert_eq!(config.ethereum_client_watcher.finality_lag_alert.enabled, None);
+    }
+}
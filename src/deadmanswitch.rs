@@ -0,0 +1,116 @@
+use crate::config::redact_secrets;
+use crate::error::Result;
+use crate::WatchtowerConfig;
+
+use reqwest::Client;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+// the watchers that must each complete a healthy cycle before the switch is pinged
+pub static WATCHERS: [&str; 2] = ["fuel", "ethereum"];
+
+// pings an external dead man's switch (e.g. healthchecks.io) after healthy cycles so it raises an alarm if they stop
+#[derive(Clone, Debug)]
+pub struct DeadManSwitchClient {
+    client: Client,
+    url: String,
+    secrets: Vec<String>,
+    healthy_watchers: Arc<Mutex<HashSet<&'static str>>>,
+}
+
+impl DeadManSwitchClient {
+    // (errors are logged with the secrets redacted, since they include the url)
+    pub fn new(client: Client, url: &str, secrets: Vec<String>) -> Self {
+        DeadManSwitchClient {
+            client,
+            url: String::from(url.trim_end_matches('/')),
+            secrets,
+            healthy_watchers: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn from_config(config: &WatchtowerConfig) -> Result<Option<Self>> {
+        match &config.deadmanswitch_url {
+            Some(url) => Ok(Some(DeadManSwitchClient::new(
+                config.http_client(None)?,
+                url,
+                config.secrets(),
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    // records the end of a watcher cycle, pinging once every watcher has had a healthy cycle since the last ping
+    // (an unhealthy cycle signals failure right away)
+    pub async fn report_cycle(&self, watcher: &'static str, healthy: bool) {
+        if !healthy {
+            self.send_ping(&format!("{}/fail", self.url)).await;
+            return;
+        }
+        let all_healthy = {
+            let mut healthy_watchers = self.healthy_watchers.lock().unwrap();
+            healthy_watchers.insert(watcher);
+            if healthy_watchers.len() < WATCHERS.len() {
+                false
+            } else {
+                healthy_watchers.clear();
+                true
+            }
+        };
+        if all_healthy {
+            self.send_ping(&self.url).await;
+        }
+    }
+
+    async fn send_ping(&self, url: &str) {
+        match self.client.get(url).send().await {
+            Ok(response) => {
+                if !response.status().is_success() {
+                    log::warn!("Dead man's switch ping returned status: {}", response.status());
+                }
+            }
+            Err(e) => log::warn!(
+                "Failed to send dead man's switch ping: {}",
+                redact_secrets(&e.to_string(), &self.secrets)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockHttp;
+
+    fn get_paths(server: &MockHttp) -> Vec<String> {
+        server.requests().into_iter().map(|request| request.path).collect()
+    }
+
+    #[tokio::test]
+    async fn ping_waits_for_a_healthy_cycle_from_both_watchers() {
+        let server = MockHttp::start(vec![]);
+        let deadmanswitch = DeadManSwitchClient::new(Client::new(), &format!("{}/check-id/", server.url), vec![]);
+
+        deadmanswitch.report_cycle("fuel", true).await;
+        assert!(server.requests().is_empty());
+        deadmanswitch.report_cycle("ethereum", true).await;
+        assert_eq!(get_paths(&server), vec!["/check-id"]);
+
+        // a watcher cycling faster than the other doesn't ping on its own
+        deadmanswitch.report_cycle("fuel", true).await;
+        deadmanswitch.report_cycle("fuel", true).await;
+        assert_eq!(get_paths(&server), vec!["/check-id"]);
+        deadmanswitch.report_cycle("ethereum", true).await;
+        assert_eq!(get_paths(&server), vec!["/check-id", "/check-id"]);
+        assert!(server.requests().iter().all(|request| request.method == "GET"));
+    }
+
+    #[tokio::test]
+    async fn unhealthy_cycle_signals_failure_right_away() {
+        let server = MockHttp::start(vec![]);
+        let deadmanswitch = DeadManSwitchClient::new(Client::new(), &format!("{}/check-id", server.url), vec![]);
+
+        deadmanswitch.report_cycle("ethereum", false).await;
+        assert_eq!(get_paths(&server), vec!["/check-id/fail"]);
+    }
+}
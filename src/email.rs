@@ -0,0 +1,179 @@
+use crate::alerts::AlertLevel;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+// delivers composed emails (kept separate from the client so messages can be built without a real SMTP server)
+pub trait EmailTransport: Send + Sync + Debug {
+    fn send(&self, message: &Message) -> Result<()>;
+    fn test_connection(&self) -> Result<()>;
+}
+
+#[derive(Debug)]
+pub struct SmtpEmailTransport {
+    transport: SmtpTransport,
+}
+
+impl SmtpEmailTransport {
+    pub fn new(host: &str, port: u16, credentials: Option<(String, String)>) -> Result<Self> {
+        let mut builder = SmtpTransport::starttls_relay(host)?.port(port);
+        match credentials {
+            Some((username, password)) => builder = builder.credentials(Credentials::new(username, password)),
+            None => {}
+        }
+        Ok(SmtpEmailTransport {
+            transport: builder.build(),
+        })
+    }
+}
+
+impl EmailTransport for SmtpEmailTransport {
+    fn send(&self, message: &Message) -> Result<()> {
+        self.transport.send(message)?;
+        Ok(())
+    }
+
+    fn test_connection(&self) -> Result<()> {
+        match self.transport.test_connection()? {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!("SMTP server did not accept the connection")),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EmailClient {
+    transport: Arc<dyn EmailTransport>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    system_name: String,
+}
+
+impl EmailClient {
+    // builds a client from the smtp settings in the config (none if no smtp host is configured)
+    pub fn from_config(config: &WatchtowerConfig) -> Result<Option<Self>> {
+        let host = match &config.smtp_host {
+            Some(host) => host,
+            None => return Ok(None),
+        };
+        let credentials = match (&config.smtp_username, &config.smtp_password) {
+            (Some(username), Some(password)) => Some((username.clone(), password.clone())),
+            _ => None,
+        };
+        let transport = SmtpEmailTransport::new(host, config.smtp_port, credentials)?;
+        let from = match &config.email_from {
+            Some(from) => from,
+            None => return Err(anyhow::anyhow!("email_from must be set when smtp_host is set.")),
+        };
+        let client = EmailClient::new(Arc::new(transport), from, &config.email_to, &config.system_name)?;
+        Ok(Some(client))
+    }
+
+    pub fn new(transport: Arc<dyn EmailTransport>, from: &str, to: &[String], system_name: &str) -> Result<Self> {
+        if to.is_empty() {
+            return Err(anyhow::anyhow!("email_to must list at least one recipient."));
+        }
+        let mut recipients = vec![];
+        for address in to {
+            recipients.push(address.parse()?);
+        }
+        Ok(EmailClient {
+            transport,
+            from: from.parse()?,
+            to: recipients,
+            system_name: String::from(system_name),
+        })
+    }
+
+    pub async fn send_alert(&self, text: &str, level: &AlertLevel, context: Option<&Value>) -> Result<()> {
+        let message = self.build_message(text, level, context)?;
+
+        // smtp delivery blocks, so keep it off the async workers
+        let transport = self.transport.clone();
+        tokio::task::spawn_blocking(move || transport.send(&message)).await?
+    }
+
+    // checks the smtp server can be reached (and accepts the credentials) without sending anything
+    pub async fn test_connection(&self) -> Result<()> {
+        let transport = self.transport.clone();
+        tokio::task::spawn_blocking(move || transport.test_connection()).await?
+    }
+
+    // builds the email for an alert (the subject carries the level and system so alerts can be filtered)
+    // any structured alert context is appended to the body as json
+    pub fn build_message(&self, text: &str, level: &AlertLevel, context: Option<&Value>) -> Result<Message> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(format!("[{:?}] {}", level, self.system_name));
+        for recipient in &self.to {
+            builder = builder.to(recipient.clone());
+        }
+        let body = match context {
+            Some(context) => format!("{text}\n\nDetails:\n{}", serde_json::to_string_pretty(context)?),
+            None => String::from(text),
+        };
+        Ok(builder.body(body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::FakeEmailTransport;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn alert_email_has_the_level_system_recipients_and_context() {
+        let transport = Arc::new(FakeEmailTransport::default());
+        let to = vec![String::from("oncall@example.com"), String::from("bridge@example.com")];
+        let client = EmailClient::new(
+            transport.clone(),
+            "watchtower@example.com",
+            &to,
+            "Fuel Canary Watchtower",
+        )
+        .unwrap();
+        let context = json!({ "amount": "12.5", "token": "0xaa" });
+        client
+            .send_alert("Deposit over the limit.", &AlertLevel::Error, Some(&context))
+            .await
+            .unwrap();
+
+        let sent = transport.sent();
+        assert_eq!(sent.len(), 1);
+        let recipients: Vec<String> = sent[0].envelope().to().iter().map(|to| to.to_string()).collect();
+        assert_eq!(recipients, to);
+        let email = String::from_utf8(sent[0].formatted()).unwrap();
+        assert!(email.contains("Subject: [Error] Fuel Canary Watchtower\r\n"));
+        assert!(email.contains("From: watchtower@example.com\r\n"));
+        let (_, body) = email.split_once("\r\n\r\n").unwrap();
+        assert_eq!(
+            body.replace("\r\n", "\n"),
+            format!(
+                "Deposit over the limit.\n\nDetails:\n{}",
+                serde_json::to_string_pretty(&context).unwrap()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn alert_email_without_context_is_just_the_text() {
+        let transport = Arc::new(FakeEmailTransport::default());
+        let to = vec![String::from("oncall@example.com")];
+        let client = EmailClient::new(transport.clone(), "watchtower@example.com", &to, "Bridge").unwrap();
+        client
+            .send_alert("Watchtower started.", &AlertLevel::Info, None)
+            .await
+            .unwrap();
+
+        let email = String::from_utf8(transport.sent()[0].formatted()).unwrap();
+        assert!(email.contains("Subject: [Info] Bridge\r\n"));
+        assert!(email.ends_with("\r\n\r\nWatchtower started."));
+    }
+}
@@ -0,0 +1,56 @@
+use ethers::providers::ProviderError;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, WatchtowerError>;
+
+#[derive(Error, Debug)]
+pub enum WatchtowerError {
+    #[error("Connection failure: {0}")]
+    Connection(String),
+    #[error("RPC request timed out: {0}")]
+    RpcTimeout(String),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Contract not initialized: {0}")]
+    ContractNotInitialized(String),
+    #[error("Invalid config: {0}")]
+    Config(String),
+    #[error("Invalid wallet: {0}")]
+    Wallet(String),
+    #[error("Ethereum account not configured.")]
+    WalletNotConfigured,
+    #[error("Unexpected chain data: {0}")]
+    ChainData(String),
+    #[error("Block time is {0} seconds ahead of current time")]
+    ClockSkew(u64),
+    #[error("Transaction failed: {0}")]
+    Transaction(String),
+    #[error("Connected to the wrong chain: {0}")]
+    WrongChain(String),
+    #[error("{source} (failed after {attempts} attempts)")]
+    RetriesExhausted {
+        source: Box<WatchtowerError>,
+        attempts: u64,
+    },
+}
+
+impl WatchtowerError {
+    // wraps the error from the final attempt of a retry loop so sustained failures can be told apart from blips
+    pub fn retries_exhausted(error: WatchtowerError, attempts: u64) -> Self {
+        WatchtowerError::RetriesExhausted {
+            source: Box::new(error),
+            attempts,
+        }
+    }
+}
+
+impl From<ProviderError> for WatchtowerError {
+    fn from(e: ProviderError) -> Self {
+        match &e {
+            ProviderError::HTTPError(http_error) if http_error.is_timeout() => {
+                WatchtowerError::RpcTimeout(e.to_string())
+            }
+            _ => WatchtowerError::Connection(e.to_string()),
+        }
+    }
+}
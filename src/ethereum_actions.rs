@@ -1,34 +1,45 @@
-use crate::alerts::{AlertLevel, WatchtowerAlerts};
-use crate::config::WatchtowerConfig;
+use crate::alerts::{AlertLevel, AlertType, WatchtowerAlerts};
+use crate::config::{redact_secrets, WatchtowerConfig, PRIVATE_KEY_ENV_VAR};
+use crate::error::Result as WatchtowerResult;
 use crate::ethereum_watcher::state_contract::StateContract;
 use crate::ethereum_watcher::gateway_contract::GatewayContract;
 use crate::ethereum_watcher::portal_contract::PortalContract;
+use crate::ethereum_watcher::SentTransactions;
 
 use anyhow::Result;
-use ethers::providers::{Http, Middleware, Provider};
-use serde::Deserialize;
-use tokio::sync::mpsc::{self, UnboundedSender};
+use ethers::providers::Middleware;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{self, Sender};
 
 pub static THREAD_CONNECTIONS_ERR: &str = "Connections to the ethereum actions thread have all closed.";
 
-#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum EthereumAction {
     None,
     PauseState,
     PauseGateway,
     PausePortal,
     PauseAll,
+    PauseGatewayToken(String),
 }
 
 #[derive(Clone, Debug)]
 pub struct WatchtowerEthereumActions {
-    action_sender: UnboundedSender<ActionParams>,
+    action_sender: Sender<ActionParams>,
+    alerts: WatchtowerAlerts,
+    sent_transactions: SentTransactions,
 }
 
 impl WatchtowerEthereumActions {
     pub async fn new(config: &WatchtowerConfig, alerts: WatchtowerAlerts) -> Result<Self> {
         // setup provider and check that it is valid
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
+        let provider = config.ethereum_http_provider()?;
         let provider_result = provider.get_chainid().await;
         match provider_result {
             Err(_) => return Err(anyhow::anyhow!("Invalid ethereum RPC.")),
@@ -40,106 +51,687 @@ impl WatchtowerEthereumActions {
         let gateway_contract = GatewayContract::new(config).await?;
         let portal_contract = PortalContract::new(config).await?;
 
+        // make sure the account can actually pause the contracts before it's needed
+        // (without a wallet the configured actions can never be taken, so say so once rather than on every pause)
+        let read_only = config.ethereum_wallet_key.is_none();
+        match read_only {
+            false => {
+                let state_permission = (state_contract.is_paused().await, state_contract.can_pause().await);
+                check_pause_permission("state", state_permission, &alerts);
+                let gateway_permission = (gateway_contract.is_paused().await, gateway_contract.can_pause().await);
+                check_pause_permission("gateway", gateway_permission, &alerts);
+                let portal_permission = (portal_contract.is_paused().await, portal_contract.can_pause().await);
+                check_pause_permission("portal", portal_permission, &alerts);
+            }
+            true => {
+                let configured_actions = config.get_configured_actions();
+                if !configured_actions.is_empty() {
+                    alerts.alert(
+                        format!(
+                            "Ethereum actions {:?} are configured but no ethereum wallet is set so they will be skipped. Set the {} environment variable to enable them.",
+                            configured_actions, PRIVATE_KEY_ENV_VAR
+                        ),
+                        AlertLevel::Warn,
+                        AlertType::EthereumActionsDisabledNoWallet,
+                    );
+                }
+            }
+        }
+
         // start handler thread for action function
-        let (tx, mut rx) = mpsc::unbounded_channel::<ActionParams>();
+        let pause_cooldown = Duration::from_secs(config.pause_cooldown_secs as u64);
+        let secrets = config.secrets();
+        // the receiver is moved into the handler thread, so each instance has exactly one consumer (cloned instances share it)
+        let (tx, mut rx) = mpsc::channel::<ActionParams>(max(config.action_queue_capacity, 1));
+        let thread_alerts = alerts.clone();
+        let sent_transactions: SentTransactions = Arc::new(Mutex::new(HashSet::new()));
+        let thread_sent_transactions = sent_transactions.clone();
         tokio::spawn(async move {
+            let alerts = thread_alerts;
+            let mut last_pause_times: HashMap<EthereumAction, SystemTime> = HashMap::new();
             loop {
                 let received_result = rx.recv().await;
                 match received_result {
                     Some(params) => {
-                        match params.action {
-                            EthereumAction::PauseState => {
-                                alerts.alert(String::from("Pausing state contract."), AlertLevel::Info);
-                                match state_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused state contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
-                                }
+                        let mut pending_actions = vec![params];
+                        while let Ok(next_params) = rx.try_recv() {
+                            coalesce(&mut pending_actions, next_params);
+                        }
+                        for params in pending_actions {
+                            // already warned about at startup
+                            if read_only {
+                                log::info!("Skipping {:?} action (no ethereum wallet configured).", params.action);
+                                continue;
                             }
-                            EthereumAction::PauseGateway => {
-                                alerts.alert(String::from("Pausing gateway contract."), AlertLevel::Info);
-                                match gateway_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused gateway contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
+                            let pause_actions = match params.action {
+                                EthereumAction::None => vec![],
+                                EthereumAction::PauseAll => {
+                                    alerts.alert(
+                                        String::from("Pausing all contracts."),
+                                        AlertLevel::Info,
+                                        AlertType::EthereumPauseAll,
+                                    );
+                                    vec![
+                                        EthereumAction::PauseState,
+                                        EthereumAction::PauseGateway,
+                                        EthereumAction::PausePortal,
+                                    ]
                                 }
-                            }
-                            EthereumAction::PausePortal => {
-                                alerts.alert(String::from("Pausing portal contract."), AlertLevel::Info);
-                                match portal_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused portal contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
+                                EthereumAction::PauseGatewayToken(token_address) => {
+                                    let can_pause_token = gateway_contract.can_pause_token(&token_address).await;
+                                    get_gateway_token_pause_actions(token_address, can_pause_token, &alerts)
                                 }
-                            }
-                            EthereumAction::PauseAll => {
-                                alerts.alert(String::from("Pausing all contracts."), AlertLevel::Info);
-                                match state_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level.clone()),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused state contract."),
-                                            AlertLevel::Info,
-                                        );
+                                action => vec![action],
+                            };
+                            for action in pause_actions {
+                                let (contract_name, alert_type) = match &action {
+                                    EthereumAction::PauseState => {
+                                        (String::from("state"), AlertType::EthereumPauseState)
                                     }
+                                    EthereumAction::PauseGateway => {
+                                        (String::from("gateway"), AlertType::EthereumPauseGateway)
+                                    }
+                                    EthereumAction::PauseGatewayToken(token_address) => (
+                                        format!("gateway (token {token_address})"),
+                                        AlertType::EthereumPauseGateway,
+                                    ),
+                                    _ => (String::from("portal"), AlertType::EthereumPausePortal),
                                 };
-                                match gateway_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level.clone()),
-                                    Ok(_) => {
+
+                                // skip contracts that are already paused (saves gas and avoids reverts)
+                                // (a paused gateway already covers each of its tokens)
+                                let paused_result = match &action {
+                                    EthereumAction::PauseState => state_contract.is_paused().await,
+                                    EthereumAction::PauseGateway | EthereumAction::PauseGatewayToken(_) => {
+                                        gateway_contract.is_paused().await
+                                    }
+                                    _ => portal_contract.is_paused().await,
+                                };
+                                let in_cooldown =
+                                    is_in_cooldown(last_pause_times.get(&action), pause_cooldown, SystemTime::now());
+                                let decision = match get_pause_decision(&paused_result, in_cooldown) {
+                                    PauseDecision::AlreadyPaused => {
+                                        let secondary_paused_result = match &action {
+                                            EthereumAction::PauseState => state_contract.is_paused_on_secondary().await,
+                                            EthereumAction::PauseGateway | EthereumAction::PauseGatewayToken(_) => {
+                                                gateway_contract.is_paused_on_secondary().await
+                                            }
+                                            _ => portal_contract.is_paused_on_secondary().await,
+                                        };
+                                        get_secondary_pause_decision(&secondary_paused_result)
+                                    }
+                                    decision => decision,
+                                };
+                                match decision {
+                                    PauseDecision::AlreadyPaused => {
                                         alerts.alert(
-                                            String::from("Successfully paused gateway contract."),
+                                            format!("The {contract_name} contract is already paused. Skipping pause."),
                                             AlertLevel::Info,
+                                            alert_type,
                                         );
+                                        continue;
+                                    }
+                                    PauseDecision::PauseOnDisagreement => alerts.alert(
+                                        format!("The primary ethereum rpc reports the {contract_name} contract is paused but the secondary rpc does not. Pausing anyway."),
+                                        AlertLevel::Error,
+                                        AlertType::RpcDisagreement,
+                                    ),
+                                    PauseDecision::InCooldown => {
+                                        log::info!("Skipping pause of {contract_name} contract (paused recently).");
+                                        continue;
                                     }
+                                    PauseDecision::Pause => {}
+                                }
+                                match &paused_result {
+                                    Err(e) => log::warn!(
+                                        "Failed to check if {contract_name} contract is paused: {}",
+                                        redact_secrets(&e.to_string(), &secrets)
+                                    ),
+                                    _ => {}
+                                }
+
+                                alerts.alert(
+                                    format!("Pausing {contract_name} contract."),
+                                    AlertLevel::Info,
+                                    alert_type,
+                                );
+                                let pause_result = match &action {
+                                    EthereumAction::PauseState => state_contract.pause(&thread_sent_transactions).await,
+                                    EthereumAction::PauseGateway => {
+                                        gateway_contract.pause(&thread_sent_transactions).await
+                                    }
+                                    EthereumAction::PauseGatewayToken(token_address) => {
+                                        gateway_contract
+                                            .pause_token(token_address, &thread_sent_transactions)
+                                            .await
+                                    }
+                                    _ => portal_contract.pause(&thread_sent_transactions).await,
                                 };
-                                match portal_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level.clone()),
+                                match pause_result {
+                                    Err(e) => alerts.alert(e.to_string(), params.alert_level.clone(), alert_type),
                                     Ok(_) => {
+                                        last_pause_times.insert(action.clone(), SystemTime::now());
                                         alerts.alert(
-                                            String::from("Successfully paused portal contract."),
+                                            format!("Successfully paused {contract_name} contract."),
                                             AlertLevel::Info,
+                                            alert_type,
                                         );
                                     }
-                                };
+                                }
                             }
-                            EthereumAction::None => {}
-                        };
+                        }
                     }
                     None => {
-                        alerts.alert(String::from(THREAD_CONNECTIONS_ERR), AlertLevel::Error);
+                        alerts.alert(
+                            String::from(THREAD_CONNECTIONS_ERR),
+                            AlertLevel::Error,
+                            AlertType::EthereumActionsThreadFailure,
+                        );
                         panic!("{}", THREAD_CONNECTIONS_ERR);
                     }
                 }
             }
         });
 
-        Ok(WatchtowerEthereumActions { action_sender: tx })
+        Ok(WatchtowerEthereumActions {
+            action_sender: tx,
+            alerts,
+            sent_transactions,
+        })
     }
 
-    pub fn action(&self, action: EthereumAction, alert_level: Option<AlertLevel>) {
+    // gets the hashes of the transactions sent while taking actions
+    pub fn sent_transactions(&self) -> SentTransactions {
+        self.sent_transactions.clone()
+    }
+
+    // takes an alert's action (none if the alert has no action)
+    pub fn action(&self, action: Option<EthereumAction>, alert_level: Option<AlertLevel>) {
         let alert_level = match alert_level {
             Some(level) => level,
             None => AlertLevel::Info,
         };
+        let action = match action {
+            Some(action) if action != EthereumAction::None => action,
+            _ => return,
+        };
         let params = ActionParams { action, alert_level };
-        self.action_sender.send(params).unwrap();
+        match self.action_sender.try_send(params) {
+            Ok(_) => {}
+            Err(TrySendError::Full(params)) => {
+                self.alerts.alert(
+                    format!("Ethereum actions queue is full. Dropped action: {:?}", params.action),
+                    AlertLevel::Error,
+                    AlertType::EthereumActionsQueueOverflow,
+                );
+            }
+            Err(TrySendError::Closed(_)) => panic!("{}", THREAD_CONNECTIONS_ERR),
+        }
+    }
+}
+
+// decides whether to pause a contract from its paused state
+// (the cooldown is only consulted when the paused state couldn't be read, since a contract that reads as unpaused
+// needs pausing again even right after a pause)
+fn get_pause_decision(paused: &WatchtowerResult<bool>, in_cooldown: bool) -> PauseDecision {
+    match paused {
+        Ok(true) => PauseDecision::AlreadyPaused,
+        Ok(false) => PauseDecision::Pause,
+        // unable to tell if the contract was unpaused so respect the cooldown
+        Err(_) if in_cooldown => PauseDecision::InCooldown,
+        Err(_) => PauseDecision::Pause,
     }
 }
 
+// adds an action to the pending ones, coalescing it with an earlier request for the same action so only the most
+// recent request of each is processed
+fn coalesce(pending_actions: &mut Vec<ActionParams>, next_params: ActionParams) {
+    pending_actions.retain(|p| p.action != next_params.action);
+    pending_actions.push(next_params);
+}
+
+// rechecks a contract the primary rpc reports as paused against the secondary rpc
+// (a primary rpc wrongly reporting paused would otherwise leave the contract open)
+fn get_secondary_pause_decision(secondary_paused: &WatchtowerResult<Option<bool>>) -> PauseDecision {
+    match secondary_paused {
+        Ok(Some(false)) => PauseDecision::PauseOnDisagreement,
+        // the secondary agrees, isn't configured or couldn't be read
+        _ => PauseDecision::AlreadyPaused,
+    }
+}
+
+// checks if a contract was paused within the cooldown (a clock that went backwards ends the cooldown)
+fn is_in_cooldown(last_pause: Option<&SystemTime>, cooldown: Duration, now: SystemTime) -> bool {
+    match last_pause {
+        Some(last_pause) => match now.duration_since(*last_pause) {
+            Ok(elapsed) => elapsed < cooldown,
+            Err(_) => false,
+        },
+        None => false,
+    }
+}
+
+// alerts if the pause simulation failed (a paused contract can't be simulated so it is skipped)
+fn check_pause_permission(
+    contract_name: &str,
+    (paused, can_pause): (WatchtowerResult<bool>, WatchtowerResult<bool>),
+    alerts: &WatchtowerAlerts,
+) {
+    match (paused, can_pause) {
+        (Ok(true), _) => log::info!("The {contract_name} contract is already paused. Skipping pause permission check."),
+        (_, Ok(true)) => {}
+        (_, Ok(false)) => alerts.alert(
+            format!("Ethereum account does not have permission to pause the {contract_name} contract."),
+            AlertLevel::Error,
+            AlertType::EthereumPausePermissionMissing,
+        ),
+        (_, Err(e)) => alerts.alert(
+            format!("Failed to check permission to pause the {contract_name} contract: {e}"),
+            AlertLevel::Warn,
+            AlertType::EthereumPausePermissionMissing,
+        ),
+    }
+}
+
+// pauses just the token if the gateway contract supports it, otherwise falls back to pausing the whole gateway
+// (also when the check itself failed, since a token that should be paused can't be left open)
+fn get_gateway_token_pause_actions(
+    token_address: String,
+    can_pause_token: WatchtowerResult<bool>,
+    alerts: &WatchtowerAlerts,
+) -> Vec<EthereumAction> {
+    match can_pause_token {
+        Ok(true) => return vec![EthereumAction::PauseGatewayToken(token_address)],
+        Ok(false) => alerts.alert(
+            format!("The gateway contract can't pause token {token_address} on its own. Pausing the whole gateway instead."),
+            AlertLevel::Warn,
+            AlertType::EthereumPauseGateway,
+        ),
+        Err(e) => alerts.alert(
+            format!("Failed to check if the gateway contract can pause token {token_address} on its own: {e}. Pausing the whole gateway instead."),
+            AlertLevel::Error,
+            AlertType::EthereumPauseGateway,
+        ),
+    }
+    vec![EthereumAction::PauseGateway]
+}
+
+// what to do with a pause once the contract's paused state has been read
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PauseDecision {
+    Pause,
+    AlreadyPaused,
+    PauseOnDisagreement,
+    InCooldown,
+}
+
 #[derive(Clone, Debug)]
 struct ActionParams {
     action: EthereumAction,
     alert_level: AlertLevel,
 }
+
+// collects the actions requested through a WatchtowerEthereumActions::collector instance
+#[cfg(test)]
+pub struct ActionCollector {
+    receiver: mpsc::Receiver<ActionParams>,
+}
+
+#[cfg(test)]
+impl ActionCollector {
+    // takes the actions requested since the last call
+    pub fn take(&mut self) -> Vec<EthereumAction> {
+        let mut actions = vec![];
+        while let Ok(params) = self.receiver.try_recv() {
+            actions.push(params.action);
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+impl WatchtowerEthereumActions {
+    // an instance whose actions are collected instead of taken, so tests can check which actions a check requested
+    pub fn collector(alerts: WatchtowerAlerts) -> (WatchtowerEthereumActions, ActionCollector) {
+        let (tx, rx) = mpsc::channel::<ActionParams>(100);
+        let actions = WatchtowerEthereumActions {
+            action_sender: tx,
+            alerts,
+            sent_transactions: Arc::new(Mutex::new(HashSet::new())),
+        };
+        (actions, ActionCollector { receiver: rx })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertRecord, RaisedAlert};
+    use crate::config::WatchtowerConfigBuilder;
+    use crate::error::WatchtowerError;
+    use crate::test_utils::{abi_bool, call_selector, selector, test_config_builder, MockRpc, WALLET_KEY};
+
+    static TOKEN: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    fn test_alerts() -> WatchtowerAlerts {
+        let config = WatchtowerConfigBuilder::new()
+            .fuel_graphql("http://localhost:4000/graphql")
+            .ethereum_rpc("http://localhost:8545")
+            .contract_addresses(
+                "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512",
+                "0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0",
+            )
+            .build()
+            .unwrap();
+        WatchtowerAlerts::new(&config).unwrap()
+    }
+
+    // waits for the alerts thread to record the given number of alerts
+    async fn wait_for_alerts(alerts: &WatchtowerAlerts, count: usize) -> Vec<AlertRecord> {
+        for _ in 0..50 {
+            if alerts.recent_alerts(10).len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        alerts.recent_alerts(10)
+    }
+
+    #[tokio::test]
+    async fn token_is_paused_on_its_own_when_supported() {
+        let alerts = test_alerts();
+        let actions = get_gateway_token_pause_actions(String::from(TOKEN), Ok(true), &alerts);
+        assert_eq!(actions, vec![EthereumAction::PauseGatewayToken(String::from(TOKEN))]);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(alerts.recent_alerts(10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn gateway_is_paused_when_token_pause_is_unsupported() {
+        let alerts = test_alerts();
+        let actions = get_gateway_token_pause_actions(String::from(TOKEN), Ok(false), &alerts);
+        assert_eq!(actions, vec![EthereumAction::PauseGateway]);
+        let records = wait_for_alerts(&alerts, 1).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, AlertLevel::Warn);
+        assert!(records[0].text.contains("can't pause token"));
+    }
+
+    #[tokio::test]
+    async fn gateway_is_paused_with_the_error_when_the_check_fails() {
+        let alerts = test_alerts();
+        let error = WatchtowerError::Connection(String::from("rpc unreachable"));
+        let actions = get_gateway_token_pause_actions(String::from(TOKEN), Err(error), &alerts);
+        assert_eq!(actions, vec![EthereumAction::PauseGateway]);
+        let records = wait_for_alerts(&alerts, 1).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, AlertLevel::Error);
+        assert!(records[0]
+            .text
+            .contains("Failed to check if the gateway contract can pause token"));
+        assert!(records[0].text.contains("rpc unreachable"));
+        assert!(!records[0].text.contains("can't pause token"));
+    }
+
+    #[test]
+    fn cooldown_lasts_for_the_cooldown_time() {
+        let cooldown = Duration::from_secs(300);
+        let first_pause = SystemTime::now();
+        assert!(!is_in_cooldown(None, cooldown, first_pause));
+        assert!(is_in_cooldown(
+            Some(&first_pause),
+            cooldown,
+            first_pause + Duration::from_secs(60)
+        ));
+        assert!(!is_in_cooldown(
+            Some(&first_pause),
+            cooldown,
+            first_pause + Duration::from_secs(300)
+        ));
+        assert!(!is_in_cooldown(
+            Some(&first_pause),
+            cooldown,
+            first_pause - Duration::from_secs(1)
+        ));
+    }
+
+    fn read_error() -> WatchtowerResult<bool> {
+        Err(WatchtowerError::Connection(String::from("rpc unreachable")))
+    }
+
+    #[test]
+    fn repeat_pause_is_skipped_only_while_in_cooldown_and_unreadable() {
+        let cooldown = Duration::from_secs(300);
+        let mut last_pause_times: HashMap<EthereumAction, SystemTime> = HashMap::new();
+        let first_pause = SystemTime::now();
+        let decide = |last_pause_times: &HashMap<EthereumAction, SystemTime>, now: SystemTime| {
+            let in_cooldown = is_in_cooldown(last_pause_times.get(&EthereumAction::PauseState), cooldown, now);
+            get_pause_decision(&read_error(), in_cooldown)
+        };
+
+        // the first PauseAll pauses and the second one a minute later is a no-op
+        assert_eq!(decide(&last_pause_times, first_pause), PauseDecision::Pause);
+        last_pause_times.insert(EthereumAction::PauseState, first_pause);
+        assert_eq!(
+            decide(&last_pause_times, first_pause + Duration::from_secs(60)),
+            PauseDecision::InCooldown
+        );
+        assert_eq!(
+            decide(&last_pause_times, first_pause + Duration::from_secs(300)),
+            PauseDecision::Pause
+        );
+    }
+
+    #[test]
+    fn cooldown_is_only_consulted_when_the_paused_state_is_unreadable() {
+        assert_eq!(get_pause_decision(&Ok(false), true), PauseDecision::Pause);
+        assert_eq!(get_pause_decision(&Ok(true), true), PauseDecision::AlreadyPaused);
+        assert_eq!(get_pause_decision(&Ok(true), false), PauseDecision::AlreadyPaused);
+        assert_eq!(get_pause_decision(&read_error(), true), PauseDecision::InCooldown);
+        assert_eq!(get_pause_decision(&read_error(), false), PauseDecision::Pause);
+    }
+
+    #[test]
+    fn secondary_rpc_only_overrides_a_paused_read_when_it_disagrees() {
+        assert_eq!(
+            get_secondary_pause_decision(&Ok(Some(true))),
+            PauseDecision::AlreadyPaused
+        );
+        assert_eq!(get_secondary_pause_decision(&Ok(None)), PauseDecision::AlreadyPaused);
+        assert_eq!(
+            get_secondary_pause_decision(&Err(WatchtowerError::Connection(String::from("rpc unreachable")))),
+            PauseDecision::AlreadyPaused
+        );
+        assert_eq!(
+            get_secondary_pause_decision(&Ok(Some(false))),
+            PauseDecision::PauseOnDisagreement
+        );
+    }
+
+    // an rpc whose contracts all report the given paused state
+    fn paused_rpc(paused: bool) -> MockRpc {
+        MockRpc::start(move |method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(paused))),
+            _ => None,
+        })
+    }
+
+    // takes an action and collects the alerts raised until the pause is skipped or attempted
+    async fn take_pause_action(config: &WatchtowerConfig, action: EthereumAction) -> Vec<RaisedAlert> {
+        let (alerts, mut collector) = WatchtowerAlerts::collector();
+        let actions = WatchtowerEthereumActions::new(config, alerts).await.unwrap();
+        actions.action(Some(action), Some(AlertLevel::Error));
+        let mut raised_alerts = vec![];
+        for _ in 0..100 {
+            raised_alerts.extend(collector.take());
+            let done = raised_alerts
+                .iter()
+                .any(|alert| alert.text.contains("already paused") || alert.text.starts_with("Pausing state contract"));
+            if done {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        raised_alerts
+    }
+
+    #[tokio::test]
+    async fn already_paused_contract_is_not_paused_again() {
+        let rpc = paused_rpc(true);
+        let secondary_rpc = paused_rpc(true);
+        let config = test_config_builder(&rpc.url)
+            .ethereum_rpc_secondary(&secondary_rpc.url)
+            .ethereum_wallet_key(WALLET_KEY)
+            .build()
+            .unwrap();
+        let raised_alerts = take_pause_action(&config, EthereumAction::PauseState).await;
+        assert!(raised_alerts
+            .iter()
+            .any(|alert| alert.text == "The state contract is already paused. Skipping pause."));
+        assert!(!raised_alerts.iter().any(|alert| alert.text.starts_with("Pausing")));
+        assert!(!raised_alerts
+            .iter()
+            .any(|alert| alert.alert_type == AlertType::RpcDisagreement));
+        assert!(rpc.requests("eth_sendRawTransaction").is_empty());
+    }
+
+    #[tokio::test]
+    async fn contract_is_paused_when_the_secondary_rpc_disagrees() {
+        let rpc = paused_rpc(true);
+        let secondary_rpc = paused_rpc(false);
+        let config = test_config_builder(&rpc.url)
+            .ethereum_rpc_secondary(&secondary_rpc.url)
+            .ethereum_wallet_key(WALLET_KEY)
+            .build()
+            .unwrap();
+        let raised_alerts = take_pause_action(&config, EthereumAction::PauseState).await;
+        assert!(raised_alerts
+            .iter()
+            .any(|alert| alert.alert_type == AlertType::RpcDisagreement && alert.level == AlertLevel::Error));
+        assert!(raised_alerts
+            .iter()
+            .any(|alert| alert.text == "Pausing state contract."));
+        assert!(!raised_alerts.iter().any(|alert| alert.text.contains("already paused")));
+    }
+
+    fn action_params(action: EthereumAction, alert_level: AlertLevel) -> ActionParams {
+        ActionParams { action, alert_level }
+    }
+
+    #[test]
+    fn repeated_actions_are_coalesced_into_the_latest_request() {
+        let mut pending_actions = vec![action_params(EthereumAction::PauseAll, AlertLevel::Warn)];
+        coalesce(
+            &mut pending_actions,
+            action_params(EthereumAction::PauseAll, AlertLevel::Warn),
+        );
+        coalesce(
+            &mut pending_actions,
+            action_params(EthereumAction::PauseState, AlertLevel::Warn),
+        );
+        coalesce(
+            &mut pending_actions,
+            action_params(EthereumAction::PauseAll, AlertLevel::Error),
+        );
+        let pending: Vec<_> = pending_actions
+            .iter()
+            .map(|p| (p.action.clone(), p.alert_level.clone()))
+            .collect();
+        assert_eq!(
+            pending,
+            vec![
+                (EthereumAction::PauseState, AlertLevel::Warn),
+                (EthereumAction::PauseAll, AlertLevel::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn full_queue_drops_the_action_with_an_alert() {
+        let (alerts, mut collector) = WatchtowerAlerts::collector();
+        // nothing takes from the queue, so it stays full after the first action
+        let (tx, _rx) = mpsc::channel::<ActionParams>(1);
+        let actions = WatchtowerEthereumActions {
+            action_sender: tx,
+            alerts,
+            sent_transactions: Arc::new(Mutex::new(HashSet::new())),
+        };
+        actions.action(Some(EthereumAction::PauseAll), Some(AlertLevel::Error));
+        assert!(collector.take().is_empty());
+
+        actions.action(Some(EthereumAction::PauseState), Some(AlertLevel::Error));
+        let raised_alerts = collector.take();
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].alert_type, AlertType::EthereumActionsQueueOverflow);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Error);
+        assert!(raised_alerts[0].text.contains("PauseState"));
+    }
+
+    #[tokio::test]
+    async fn reverted_pause_simulation_alerts_missing_permission() {
+        let rpc = MockRpc::start(|method, params| match method {
+            "eth_call" if call_selector(params) == selector("pause()") => Some(Err(String::from(
+                "execution reverted: AccessControl: account is missing role",
+            ))),
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url)
+            .ethereum_wallet_key(WALLET_KEY)
+            .build()
+            .unwrap();
+        let (alerts, mut collector) = WatchtowerAlerts::collector();
+        WatchtowerEthereumActions::new(&config, alerts).await.unwrap();
+        let raised_alerts = collector.take();
+        let texts: Vec<_> = raised_alerts.iter().map(|alert| alert.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Ethereum account does not have permission to pause the state contract.",
+                "Ethereum account does not have permission to pause the gateway contract.",
+                "Ethereum account does not have permission to pause the portal contract.",
+            ]
+        );
+        assert!(raised_alerts.iter().all(|alert| {
+            alert.alert_type == AlertType::EthereumPausePermissionMissing && alert.level == AlertLevel::Error
+        }));
+    }
+
+    #[test]
+    fn pause_permission_is_only_checked_on_unpaused_contracts() {
+        let (alerts, mut collector) = WatchtowerAlerts::collector();
+        check_pause_permission("state", (Ok(true), Ok(false)), &alerts);
+        check_pause_permission("state", (Ok(false), Ok(true)), &alerts);
+        assert!(collector.take().is_empty());
+
+        // a paused state that couldn't be read still gets the permission check
+        check_pause_permission("state", (read_error(), Ok(false)), &alerts);
+        check_pause_permission("state", (Ok(false), read_error()), &alerts);
+        let raised_alerts = collector.take();
+        assert_eq!(raised_alerts.len(), 2);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Error);
+        assert_eq!(raised_alerts[1].level, AlertLevel::Warn);
+        assert!(raised_alerts[1]
+            .text
+            .starts_with("Failed to check permission to pause the state contract"));
+    }
+
+    #[tokio::test]
+    async fn configured_actions_without_a_wallet_alert_once_and_are_skipped() {
+        let rpc = paused_rpc(false);
+        let mut config = test_config_builder(&rpc.url).build().unwrap();
+        let (alerts, mut collector) = WatchtowerAlerts::collector();
+        WatchtowerEthereumActions::new(&config, alerts).await.unwrap();
+        assert!(collector.take().is_empty());
+
+        config.ethereum_client_watcher.connection_alert.alert_action = Some(EthereumAction::PauseAll);
+        let raised_alerts = take_pause_action(&config, EthereumAction::PauseAll).await;
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].alert_type, AlertType::EthereumActionsDisabledNoWallet);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert!(raised_alerts[0].text.contains("PauseAll"));
+        assert!(raised_alerts[0].text.contains(PRIVATE_KEY_ENV_VAR));
+        assert!(rpc.requests("eth_sendRawTransaction").is_empty());
+    }
+}
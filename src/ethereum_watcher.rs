@@ -1,16 +1,27 @@
-use crate::alerts::{AlertLevel, WatchtowerAlerts};
-use crate::ethereum_actions::WatchtowerEthereumActions;
+use crate::alerts::{AlertLevel, AlertType, Chain, CheckOutcome, WatchtowerAlerts};
+use crate::config::{
+    is_check_enabled, BlockProduction, DepositAlert, EthereumClientWatcher, ImbalanceAlert, WithdrawalReceiptFilter,
+};
+use crate::deadmanswitch::DeadManSwitchClient;
+use crate::error::WatchtowerError;
+use crate::ethereum_actions::{EthereumAction, WatchtowerEthereumActions};
 use crate::fuel_watcher::fuel_chain::FuelChain;
+use crate::token_amount::{get_threshold_band, ThresholdBand, TokenAmount};
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
 use state_contract::StateContract;
 use ethereum_chain::EthereumChain;
+use ethereum_chain::U256;
+use ethers::types::{Log, H160, H256};
+use ethers::utils::{format_units, keccak256};
 use gateway_contract::GatewayContract;
 use portal_contract::PortalContract;
-use std::cmp::max;
-use std::thread;
-use std::time::Duration;
+use serde_json::{json, Value};
+use std::cmp::{max, min};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::task::JoinHandle;
 
 pub mod state_contract;
@@ -19,15 +30,99 @@ pub mod gateway_contract;
 pub mod portal_contract;
 
 pub static POLL_DURATION: Duration = Duration::from_millis(6000);
-pub static POLL_LOGGING_SKIP: u64 = 50;
 pub static COMMIT_CHECK_STARTING_OFFSET: u64 = 24 * 60 * 60;
 pub static ETHEREUM_CONNECTION_RETRIES: u64 = 2;
 pub static ETHEREUM_BLOCK_TIME: u64 = 12;
+pub static BALANCE_SAMPLE_PERIOD: u64 = 10 * 60;
+pub static BALANCE_SAMPLE_COUNT: usize = 144;
+pub static CONTRACT_CACHE_TTL: Duration = Duration::from_millis(2000);
+#[cfg(not(test))]
+pub static TX_POLL_INTERVAL: Duration = Duration::from_millis(7000);
+// short enough for tests to follow a transaction through a mock rpc
+#[cfg(test)]
+pub static TX_POLL_INTERVAL: Duration = Duration::from_millis(50);
+pub static MAX_WALLET_SCAN_BLOCKS: u64 = 300;
+pub static MAX_COMMIT_SCAN_BLOCKS: u64 = 24 * 60 * 60 / 12;
+pub static MAX_ROLE_SCAN_BLOCKS: u64 = 24 * 60 * 60 / 12;
+pub static ROLE_EVENTS: [&str; 2] = [
+    "RoleGranted(bytes32,address,address)",
+    "RoleRevoked(bytes32,address,address)",
+];
+
+// hashes of the transactions sent by the watchtower itself
+pub type SentTransactions = Arc<Mutex<HashSet<H256>>>;
+
+// the state contract commits seen so far (when the last one was seen and the blocks of the most recent ones)
+struct CommitHistory {
+    last_commit_time: SystemTime,
+    commit_blocks: VecDeque<u64>,
+}
+
+// the role grants and revocations seen on the bridge contracts (and the last block scanned for them)
+struct RoleHistory {
+    last_checked_block: Option<u64>,
+    changes: Vec<String>,
+}
+
+// an access control role granted to or revoked from an account
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoleChange {
+    pub role_name: String,
+    pub account: H160,
+    pub granted: bool,
+}
+
+// gets the id of an access control role from its name (the default admin role is all zeros)
+pub fn get_role_id(role_name: &str) -> [u8; 32] {
+    match role_name {
+        "DEFAULT_ADMIN_ROLE" => [0; 32],
+        _ => keccak256(role_name),
+    }
+}
+
+// gets the role changes from RoleGranted and RoleRevoked logs (roles missing from role_names are labelled by their id)
+pub fn parse_role_logs(logs: Vec<Log>, role_names: &[&str]) -> Vec<RoleChange> {
+    let granted_topic = H256::from(keccak256(ROLE_EVENTS[0]));
+    let mut role_changes = vec![];
+    for log in logs {
+        // skip malformed logs rather than dropping the whole batch
+        if log.topics.len() < 3 {
+            log::warn!(
+                "Skipping malformed role log (tx: {:?}, topics: {}).",
+                log.transaction_hash,
+                log.topics.len()
+            );
+            continue;
+        }
+        let role_id = log.topics[1];
+        let role_name = match role_names
+            .iter()
+            .find(|role_name| H256::from(get_role_id(role_name)) == role_id)
+        {
+            Some(role_name) => String::from(*role_name),
+            None => format!("{:#x}", role_id),
+        };
+        role_changes.push(RoleChange {
+            role_name,
+            account: H160::from(log.topics[2]),
+            granted: log.topics[0] == granted_topic,
+        });
+    }
+    role_changes
+}
+
+// gets the first block covered by a time frame ending at the given block
+pub fn get_start_block(timeframe: u32, latest_block_num: u64) -> u64 {
+    let block_offset = timeframe as u64 / ETHEREUM_BLOCK_TIME;
+    max(latest_block_num, block_offset) - block_offset
+}
 
 pub async fn start_ethereum_watcher(
     config: &WatchtowerConfig,
+    live_watch_config: Arc<RwLock<EthereumClientWatcher>>,
     actions: WatchtowerEthereumActions,
     alerts: WatchtowerAlerts,
+    deadmanswitch: Option<DeadManSwitchClient>,
 ) -> Result<JoinHandle<()>> {
     let fuel_chain = FuelChain::new(config).await?;
     let ethereum_chain = EthereumChain::new(config).await?;
@@ -35,7 +130,6 @@ pub async fn start_ethereum_watcher(
     let gateway_contract = GatewayContract::new(config).await?;
     let portal_contract = PortalContract::new(config).await?;
 
-    let watch_config = config.ethereum_client_watcher.clone();
     let account_address = match &config.ethereum_wallet_key {
         Some(key) => Some(EthereumChain::get_public_address(key).await?),
         None => None,
@@ -45,231 +139,3145 @@ pub async fn start_ethereum_watcher(
         ethereum_chain.get_latest_block_number().await?,
         commit_start_block_offset,
     ) - commit_start_block_offset;
+    let mut commit_history = CommitHistory {
+        last_commit_time: SystemTime::now(),
+        commit_blocks: VecDeque::new(),
+    };
+    let mut last_cadence_report = SystemTime::now();
+    let mut balance_samples: VecDeque<(u64, U256)> = VecDeque::new();
+    let mut last_balance: Option<U256> = None;
+    let mut outage_start: Option<SystemTime> = None;
+    let sent_transactions = actions.sent_transactions();
+    let mut last_wallet_nonce: Option<(u64, u64)> = None;
+    let mut max_block_seen: u64 = 0;
+    let mut role_history = RoleHistory {
+        last_checked_block: None,
+        changes: vec![],
+    };
+
+    let max_cycles = config.max_cycles;
+    // pause briefly between checks so rpc calls are spread out instead of bursting at the start of each cycle
+    let check_stagger = Duration::from_millis(config.check_stagger_ms);
+    let mut cycles: u64 = 0;
 
     // start thread
     let handle = tokio::spawn(async move {
         loop {
+            // stamp everything alerted this cycle with the cycle number
+            let alerts = alerts.for_cycle(cycles);
+
+            // pick up any changes from a config reload
+            let watch_config = live_watch_config.read().unwrap().clone();
+            let poll_logging_skip = max(watch_config.poll_logging_skip, 1);
+
             // update the log every so often to notify that everything is working
-            alerts.alert(String::from("Watching ethereum chain."), AlertLevel::Info);
-            for _i in 0..POLL_LOGGING_SKIP {
-                // check chain connection
-                if watch_config.connection_alert.alert_level != AlertLevel::None {
-                    match ethereum_chain.check_connection().await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check ethereum connection: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
+            if cycles.is_multiple_of(poll_logging_skip) {
+                alerts.alert(
+                    String::from("Watching ethereum chain."),
+                    AlertLevel::Info,
+                    AlertType::EthereumWatcherStatus,
+                );
+            }
+
+            // check chain connection
+            let connection_outcome =
+                check_chain_connection(&ethereum_chain, &mut outage_start, &watch_config, &actions, &alerts).await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check block production
+            check_block_production(&ethereum_chain, &watch_config, &actions, &alerts).await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check account balance
+            match &account_address {
+                Some(account_address) => {
+                    // the balance changes slowly so it can be checked less often than the other checks
+                    if is_balance_check_cycle(cycles, watch_config.account_funds_alert.check_every_n_cycles) {
+                        check_account_balance(
+                            &ethereum_chain,
+                            account_address,
+                            &mut balance_samples,
+                            &mut last_balance,
+                            &watch_config,
+                            &actions,
+                            &alerts,
+                        )
+                        .await;
                     }
+
+                    // check the account isn't sending transactions the watchtower didn't
+                    check_wallet_activity(
+                        &ethereum_chain,
+                        account_address,
+                        &mut last_wallet_nonce,
+                        &sent_transactions,
+                        &watch_config,
+                        &actions,
+                        &alerts,
+                    )
+                    .await;
                 }
+                None => {}
+            }
 
-                // check block production
-                if watch_config.block_production_alert.alert_level != AlertLevel::None {
-                    match ethereum_chain.get_seconds_since_last_block().await {
-                        Ok(seconds_since_last_block) => {
-                            if seconds_since_last_block > watch_config.block_production_alert.max_block_time {
-                                alerts.alert(
-                                    format!(
-                                        "Next ethereum block is taking longer than {} seconds. Last block was {} seconds ago.",
-                                        watch_config.block_production_alert.max_block_time, seconds_since_last_block
-                                    ),
-                                    watch_config.block_production_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    watch_config.block_production_alert.alert_action.clone(),
-                                    Some(watch_config.block_production_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check ethereum block production: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
+            tokio::time::sleep(check_stagger).await;
+            // check state contract commits
+            let check_invalid_commits = is_check_enabled(
+                watch_config.invalid_state_commit_alert.enabled,
+                &watch_config.invalid_state_commit_alert.alert_level,
+            );
+            let check_commit_stall = is_check_enabled(
+                watch_config.commit_stall_alert.enabled,
+                &watch_config.commit_stall_alert.alert_level,
+            );
+            let report_commit_cadence = is_check_enabled(
+                watch_config.commit_cadence_alert.enabled,
+                &watch_config.commit_cadence_alert.alert_level,
+            );
+            if check_invalid_commits || check_commit_stall || report_commit_cadence {
+                let commit_check_range = get_commit_check_range(
+                    &ethereum_chain,
+                    last_commit_check_block,
+                    &watch_config,
+                    &actions,
+                    &alerts,
+                )
+                .await;
+                match commit_check_range {
+                    Some((from_block, to_block)) => {
+                        check_state_commits(
+                            &state_contract,
+                            &fuel_chain,
+                            (from_block, to_block),
+                            &mut commit_history,
+                            &watch_config,
+                            &actions,
+                            &alerts,
+                        )
+                        .await;
+                        last_commit_check_block = to_block;
                     }
+                    None => {}
                 }
+            }
 
-                // check account balance
-                let account_address = account_address.clone();
-                if account_address.is_some() && watch_config.account_funds_alert.alert_level != AlertLevel::None {
-                    let account_address = account_address.unwrap();
-                    match ethereum_chain.get_account_balance(&account_address).await {
-                        Ok(balance) => {
-                            let min_balance =
-                                EthereumChain::get_value(watch_config.account_funds_alert.min_balance, 18);
-                            if balance < min_balance {
-                                alerts.alert(
-                                    format!(
-                                        "Ethereum account ({}) is low on funds. Current balance: {}",
-                                        &account_address, balance
-                                    ),
-                                    watch_config.account_funds_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    watch_config.account_funds_alert.alert_action.clone(),
-                                    Some(watch_config.account_funds_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check ethereum account funds: {e}"),
-                                watch_config.account_funds_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.account_funds_alert.alert_action.clone(),
-                                Some(watch_config.account_funds_alert.alert_level.clone()),
-                            );
-                        }
-                    }
+            // check commits are still being made
+            check_commit_stall_time(commit_history.last_commit_time, &watch_config, &actions, &alerts);
+
+            // report how often commits are being made
+            check_commit_cadence(
+                &commit_history.commit_blocks,
+                &mut last_cadence_report,
+                &watch_config,
+                &alerts,
+            );
+
+            tokio::time::sleep(check_stagger).await;
+            // check how far the chain head is ahead of finality
+            check_finality_lag(&ethereum_chain, &watch_config, &actions, &alerts).await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check the provider isn't serving stale blocks
+            check_stale_rpc(&ethereum_chain, &mut max_block_seen, &watch_config, &actions, &alerts).await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check control of the contracts hasn't been handed over
+            check_ownership(
+                &ethereum_chain,
+                (&state_contract, &gateway_contract, &portal_contract),
+                &mut role_history,
+                &watch_config,
+                &actions,
+                &alerts,
+            )
+            .await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check gas is affordable enough for a pause to get through
+            check_gas_price(&ethereum_chain, &watch_config, &actions, &alerts).await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check the provider isn't silently returning no logs (the deposit and imbalance checks rely on them)
+            check_empty_logs(
+                &ethereum_chain,
+                last_commit_check_block,
+                &watch_config,
+                &actions,
+                &alerts,
+            )
+            .await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check base asset deposits
+            for portal_deposit_alert in &watch_config.portal_deposit_alerts {
+                check_base_asset_deposits(
+                    &portal_contract,
+                    portal_deposit_alert,
+                    last_commit_check_block,
+                    &actions,
+                    &alerts,
+                )
+                .await;
+            }
+
+            tokio::time::sleep(check_stagger).await;
+            // check ERC20 token deposits
+            for gateway_deposit_alert in &watch_config.gateway_deposit_alerts {
+                if !watch_config.is_token_ignored(&gateway_deposit_alert.token_address) {
+                    check_token_deposits(
+                        &gateway_contract,
+                        gateway_deposit_alert,
+                        watch_config.verify_token_names,
+                        last_commit_check_block,
+                        &actions,
+                        &alerts,
+                    )
+                    .await;
                 }
+            }
 
-                // check invalid commits
-                if watch_config.invalid_state_commit_alert.alert_level != AlertLevel::None {
-                    match state_contract.get_latest_commits(last_commit_check_block).await {
-                        Ok(hashes) => {
-                            for hash in hashes {
-                                match fuel_chain.verify_block_commit(&hash).await {
-                                    Ok(valid) => {
-                                        if !valid {
-                                            alerts.alert(
-                                                format!("An invalid commit was made on the state contract. Hash: {hash}"),
-                                                watch_config.invalid_state_commit_alert.alert_level.clone(),
-                                            );
-                                            actions.action(
-                                                watch_config.invalid_state_commit_alert.alert_action.clone(),
-                                                Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
-                                            );
-                                        }
-                                    }
-                                    Err(e) => {
-                                        alerts.alert(
-                                            format!("Failed to check state contract commits: {e}"),
-                                            watch_config.invalid_state_commit_alert.alert_level.clone(),
-                                        );
-                                        actions.action(
-                                            watch_config.invalid_state_commit_alert.alert_action.clone(),
-                                            Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
+            // check ERC20 token deposits for tokens without their own alert
+            if watch_config.auto_discover_tokens {
+                check_discovered_token_deposits(
+                    &gateway_contract,
+                    &watch_config,
+                    last_commit_check_block,
+                    &actions,
+                    &alerts,
+                )
+                .await;
+            }
+
+            tokio::time::sleep(check_stagger).await;
+            // check net base asset flow (deposits vs withdrawals)
+            for portal_imbalance_alert in &watch_config.portal_imbalance_alerts {
+                check_base_asset_imbalance(
+                    &portal_contract,
+                    portal_imbalance_alert,
+                    last_commit_check_block,
+                    &actions,
+                    &alerts,
+                )
+                .await;
+            }
+
+            tokio::time::sleep(check_stagger).await;
+            // check the base asset released on ethereum was actually withdrawn on fuel
+            check_supply_mismatch(
+                &portal_contract,
+                &fuel_chain,
+                &watch_config,
+                last_commit_check_block,
+                &actions,
+                &alerts,
+            )
+            .await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check net ERC20 token flow (deposits vs withdrawals)
+            for gateway_imbalance_alert in &watch_config.gateway_imbalance_alerts {
+                if !watch_config.is_token_ignored(&gateway_imbalance_alert.token_address) {
+                    check_token_imbalance(
+                        &gateway_contract,
+                        gateway_imbalance_alert,
+                        watch_config.verify_token_names,
+                        last_commit_check_block,
+                        &actions,
+                        &alerts,
+                    )
+                    .await;
+                }
+            }
+
+            tokio::time::sleep(check_stagger).await;
+            // check for tokens bridged that are not on the allowlist
+            check_unexpected_tokens(
+                &gateway_contract,
+                last_commit_check_block,
+                &watch_config,
+                &actions,
+                &alerts,
+            )
+            .await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check the bridge is still being used
+            check_bridge_activity(
+                &ethereum_chain,
+                &portal_contract,
+                last_commit_check_block,
+                &watch_config,
+                &actions,
+                &alerts,
+            )
+            .await;
+
+            // push anything held back from this cycle (when aggregating alerts per cycle)
+            alerts.end_cycle(Chain::Ethereum);
+
+            // let the dead man's switch know the cycle completed (a failed connection counts as unhealthy)
+            match &deadmanswitch {
+                Some(deadmanswitch) => {
+                    let healthy = !matches!(connection_outcome, CheckOutcome::Failed(_));
+                    deadmanswitch.report_cycle("ethereum", healthy).await;
+                }
+                None => {}
+            }
+
+            // stop after a fixed number of cycles when configured to (used for testing)
+            cycles += 1;
+            match max_cycles {
+                Some(max_cycles) if cycles >= max_cycles => return,
+                _ => {}
+            }
+
+            tokio::time::sleep(POLL_DURATION).await;
+        }
+    });
+
+    Ok(handle)
+}
+
+// whether the account balance is checked on this cycle (every n cycles, starting with the first)
+fn is_balance_check_cycle(cycles: u64, check_every_n_cycles: u64) -> bool {
+    cycles.is_multiple_of(max(check_every_n_cycles, 1))
+}
+
+async fn check_chain_connection(
+    ethereum_chain: &EthereumChain,
+    outage_start: &mut Option<SystemTime>,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(
+        watch_config.connection_alert.enabled,
+        &watch_config.connection_alert.alert_level,
+    ) {
+        return CheckOutcome::Ok;
+    }
+
+    match ethereum_chain.check_connection().await {
+        Ok(_) => {
+            *outage_start = None;
+            alerts.record_success(AlertType::EthereumConnection, "");
+            CheckOutcome::Ok
+        }
+        Err(e) => {
+            let alert_failure = alerts.record_failure(
+                AlertType::EthereumConnection,
+                "",
+                watch_config.connection_alert.details.consecutive_failures_to_alert,
+            );
+            if alert_failure {
+                alerts.alert(
+                    watch_config
+                        .connection_alert
+                        .details
+                        .annotate(format!("Failed to check ethereum connection: {e}")),
+                    watch_config.connection_alert.alert_level.clone(),
+                    AlertType::EthereumConnection,
+                );
+            }
+            if !watch_config.pause_on_rpc_outage {
+                if alert_failure {
+                    actions.action(
+                        watch_config.connection_alert.alert_action.clone(),
+                        Some(watch_config.connection_alert.alert_level.clone()),
+                    );
+                }
+                return CheckOutcome::Failed(e.to_string());
+            }
+
+            // only act once the rpc has been down long enough that the watchtower is effectively blind
+            let outage_secs = get_outage_to_act_on(
+                outage_start,
+                watch_config.rpc_outage_pause_secs as u64,
+                SystemTime::now(),
+            );
+            match outage_secs {
+                Some(outage_secs) => {
+                    alerts.alert_with_dedup_suffix(
+                        watch_config.connection_alert.details.annotate(format!(
+                            "Ethereum RPC has been unreachable for {} seconds. Taking connection alert action {:?}.",
+                            outage_secs,
+                            watch_config
+                                .connection_alert
+                                .alert_action
+                                .clone()
+                                .unwrap_or(EthereumAction::None)
+                        )),
+                        watch_config.connection_alert.alert_level.clone(),
+                        AlertType::EthereumConnection,
+                        "outage",
+                    );
+                    actions.action(
+                        watch_config.connection_alert.alert_action.clone(),
+                        Some(watch_config.connection_alert.alert_level.clone()),
+                    );
+                }
+                None => {}
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+// gets how long the rpc has been unreachable once that is at least pause_after_secs (the outage starts at the first
+// failed check and is cleared by the next successful one)
+fn get_outage_to_act_on(outage_start: &mut Option<SystemTime>, pause_after_secs: u64, now: SystemTime) -> Option<u64> {
+    let started = *outage_start.get_or_insert(now);
+    let outage_secs = match now.duration_since(started) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+    match outage_secs >= pause_after_secs {
+        true => Some(outage_secs),
+        false => None,
+    }
+}
+
+async fn check_block_production(
+    ethereum_chain: &EthereumChain,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(
+        watch_config.block_production_alert.enabled,
+        &watch_config.block_production_alert.alert_level,
+    ) {
+        return CheckOutcome::Ok;
+    }
+
+    match ethereum_chain.get_seconds_since_last_block().await {
+        Ok(seconds_since_last_block) => {
+            alerts.record_success(AlertType::EthereumBlockProduction, "");
+
+            // a block time far beyond the normal threshold means production has stopped rather than slowed
+            let block_production_alert = &watch_config.block_production_alert;
+            match block_production_alert.get_block_production(seconds_since_last_block) {
+                BlockProduction::Stopped { critical_block_time } => {
+                    alerts.alert(
+                        block_production_alert.details.annotate(format!(
+                            "Ethereum block production has stopped. No block for over {} seconds. Last block was {} seconds ago.",
+                            critical_block_time, seconds_since_last_block
+                        )),
+                        AlertLevel::Error,
+                        AlertType::EthereumBlockProductionStopped,
+                    );
+                    actions.action(
+                        block_production_alert.critical_alert_action.clone(),
+                        Some(AlertLevel::Error),
+                    );
+                    CheckOutcome::Alerted(AlertType::EthereumBlockProductionStopped)
+                }
+                BlockProduction::Slow => {
+                    alerts.alert(
+                        block_production_alert.details.annotate(format!(
+                            "Next ethereum block is taking longer than {} seconds. Last block was {} seconds ago.",
+                            block_production_alert.max_block_time, seconds_since_last_block
+                        )),
+                        block_production_alert.alert_level.clone(),
+                        AlertType::EthereumBlockProduction,
+                    );
+                    actions.action(
+                        block_production_alert.alert_action.clone(),
+                        Some(block_production_alert.alert_level.clone()),
+                    );
+                    CheckOutcome::Alerted(AlertType::EthereumBlockProduction)
+                }
+                BlockProduction::OnTime => CheckOutcome::Ok,
+            }
+        }
+        Err(WatchtowerError::ClockSkew(seconds_ahead)) => {
+            // the local clock is behind the node, so don't mistake this for a block production issue
+            alerts.alert_for_chain(
+                watch_config.block_production_alert.details.annotate(format!(
+                    "Latest ethereum block time is {} seconds ahead of the local clock. Check NTP on the watchtower and node hosts.",
+                    seconds_ahead
+                )),
+                watch_config.block_production_alert.alert_level.clone(),
+                AlertType::ClockSkew,
+                Chain::Ethereum,
+            );
+            CheckOutcome::Alerted(AlertType::ClockSkew)
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumBlockProduction,
+                "",
+                watch_config.connection_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    watch_config
+                        .connection_alert
+                        .details
+                        .annotate(format!("Failed to check ethereum block production: {e}")),
+                    watch_config.connection_alert.alert_level.clone(),
+                    AlertType::EthereumBlockProduction,
+                );
+                actions.action(
+                    watch_config.connection_alert.alert_action.clone(),
+                    Some(watch_config.connection_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+async fn check_wallet_activity(
+    ethereum_chain: &EthereumChain,
+    account_address: &str,
+    last_wallet_nonce: &mut Option<(u64, u64)>,
+    sent_transactions: &SentTransactions,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let wallet_activity_alert = &watch_config.unexpected_wallet_activity_alert;
+    if !is_check_enabled(wallet_activity_alert.enabled, &wallet_activity_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let latest_block = ethereum_chain.get_latest_block_number().await;
+    let nonce = ethereum_chain.get_transaction_count(account_address).await;
+    let (latest_block, nonce) = match (latest_block, nonce) {
+        (Ok(latest_block), Ok(nonce)) => (latest_block, nonce),
+        (Err(e), _) | (_, Err(e)) => {
+            if alerts.record_failure(
+                AlertType::UnexpectedWalletActivity,
+                "",
+                wallet_activity_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    wallet_activity_alert
+                        .details
+                        .annotate(format!("Failed to check wallet activity: {e}")),
+                    wallet_activity_alert.alert_level.clone(),
+                    AlertType::UnexpectedWalletActivity,
+                );
+                actions.action(
+                    wallet_activity_alert.alert_action.clone(),
+                    Some(wallet_activity_alert.alert_level.clone()),
+                );
+            }
+            return CheckOutcome::Failed(e.to_string());
+        }
+    };
+    alerts.record_success(AlertType::UnexpectedWalletActivity, "");
+
+    // the nonce only moves when the account sends a transaction, so blocks are only scanned when it does
+    let (last_nonce, last_block) = match *last_wallet_nonce {
+        Some(last) => last,
+        None => {
+            *last_wallet_nonce = Some((nonce, latest_block));
+            return CheckOutcome::Ok;
+        }
+    };
+    if nonce <= last_nonce {
+        *last_wallet_nonce = Some((nonce, latest_block));
+        return CheckOutcome::Ok;
+    }
+    let start_block = max(
+        last_block + 1,
+        max(latest_block, MAX_WALLET_SCAN_BLOCKS) - MAX_WALLET_SCAN_BLOCKS,
+    );
+    let tx_hashes = match ethereum_chain
+        .get_transactions_from(account_address, start_block, latest_block)
+        .await
+    {
+        Ok(tx_hashes) => tx_hashes,
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::UnexpectedWalletActivity,
+                "scan",
+                wallet_activity_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    wallet_activity_alert
+                        .details
+                        .annotate(format!("Failed to check wallet activity: {e}")),
+                    wallet_activity_alert.alert_level.clone(),
+                    AlertType::UnexpectedWalletActivity,
+                );
+                actions.action(
+                    wallet_activity_alert.alert_action.clone(),
+                    Some(wallet_activity_alert.alert_level.clone()),
+                );
+            }
+            return CheckOutcome::Failed(e.to_string());
+        }
+    };
+    alerts.record_success(AlertType::UnexpectedWalletActivity, "scan");
+    *last_wallet_nonce = Some((nonce, latest_block));
+
+    // anything the account sent that isn't one of our own pause transactions is suspect
+    let unexpected: Vec<String> = {
+        let sent_transactions = sent_transactions.lock().unwrap();
+        tx_hashes
+            .iter()
+            .filter(|tx_hash| !sent_transactions.contains(tx_hash))
+            .map(|tx_hash| format!("{:#x}", tx_hash))
+            .collect()
+    };
+    let unaccounted = (nonce - last_nonce).saturating_sub(tx_hashes.len() as u64);
+    if unexpected.is_empty() && unaccounted == 0 {
+        return CheckOutcome::Ok;
+    }
+    let text = if !unexpected.is_empty() {
+        format!(
+            "The watchtower account sent transactions it did not initiate: {}. The account key may be compromised.",
+            unexpected.join(", ")
+        )
+    } else {
+        format!(
+            "The watchtower account sent {} transaction(s) it did not initiate (not found in blocks [{}, {}]). The account key may be compromised.",
+            unaccounted, start_block, latest_block
+        )
+    };
+    alerts.alert(
+        wallet_activity_alert.details.annotate(text),
+        wallet_activity_alert.alert_level.clone(),
+        AlertType::UnexpectedWalletActivity,
+    );
+    actions.action(
+        wallet_activity_alert.alert_action.clone(),
+        Some(wallet_activity_alert.alert_level.clone()),
+    );
+    CheckOutcome::Alerted(AlertType::UnexpectedWalletActivity)
+}
+
+async fn check_account_balance(
+    ethereum_chain: &EthereumChain,
+    account_address: &str,
+    balance_samples: &mut VecDeque<(u64, U256)>,
+    last_balance: &mut Option<U256>,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let check_min_balance = is_check_enabled(
+        watch_config.account_funds_alert.enabled,
+        &watch_config.account_funds_alert.alert_level,
+    );
+    let check_balance_runway = is_check_enabled(
+        watch_config.balance_runway_alert.enabled,
+        &watch_config.balance_runway_alert.alert_level,
+    );
+    if !check_min_balance && !check_balance_runway {
+        return CheckOutcome::Ok;
+    }
+
+    let balance = match ethereum_chain.get_account_balance(account_address).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumAccountFunds,
+                "",
+                watch_config.account_funds_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    watch_config
+                        .account_funds_alert
+                        .details
+                        .annotate(format!("Failed to check ethereum account funds: {e}")),
+                    watch_config.account_funds_alert.alert_level.clone(),
+                    AlertType::EthereumAccountFunds,
+                );
+                actions.action(
+                    watch_config.account_funds_alert.alert_action.clone(),
+                    Some(watch_config.account_funds_alert.alert_level.clone()),
+                );
+            }
+            return CheckOutcome::Failed(e.to_string());
+        }
+    };
+    alerts.record_success(AlertType::EthereumAccountFunds, "");
+
+    let mut outcome = CheckOutcome::Ok;
+    let min_balance = TokenAmount::from_float(watch_config.account_funds_alert.min_balance, 18);
+    if check_min_balance && TokenAmount::new(balance, 18) < min_balance {
+        alerts.alert_with_context(
+            watch_config.account_funds_alert.details.annotate(format!(
+                "Ethereum account ({}) is low on funds. Current balance: {}",
+                account_address, balance
+            )),
+            watch_config.account_funds_alert.alert_level.clone(),
+            AlertType::EthereumAccountFunds,
+            None,
+            json!({
+                "account": account_address,
+                "balance": balance.to_string(),
+                "threshold": min_balance.to_string(),
+            }),
+        );
+        actions.action(
+            watch_config.account_funds_alert.alert_action.clone(),
+            Some(watch_config.account_funds_alert.alert_level.clone()),
+        );
+        outcome = CheckOutcome::Alerted(AlertType::EthereumAccountFunds);
+    }
+
+    // an unexpectedly high balance can mean funds were routed to the wrong account
+    match watch_config.account_funds_alert.max_balance {
+        Some(max_balance)
+            if check_min_balance && TokenAmount::new(balance, 18) > TokenAmount::from_float(max_balance, 18) =>
+        {
+            alerts.alert_with_context(
+                watch_config.account_funds_alert.details.annotate(format!(
+                    "Ethereum account ({}) holds more funds than expected. Current balance: {}",
+                    account_address, balance
+                )),
+                watch_config.account_funds_alert.alert_level.clone(),
+                AlertType::EthereumAccountFundsHigh,
+                None,
+                json!({
+                    "account": account_address,
+                    "balance": balance.to_string(),
+                    "threshold": TokenAmount::from_float(max_balance, 18).to_string(),
+                }),
+            );
+            actions.action(
+                watch_config.account_funds_alert.alert_action.clone(),
+                Some(watch_config.account_funds_alert.alert_level.clone()),
+            );
+            outcome = CheckOutcome::Alerted(AlertType::EthereumAccountFundsHigh);
+        }
+        _ => {}
+    }
+
+    // compare against the previous cycle to catch sudden swings in either direction
+    let previous_balance = last_balance.replace(balance);
+    match (watch_config.account_funds_alert.max_balance_change, previous_balance) {
+        (Some(max_change), Some(previous_balance)) if check_min_balance => {
+            let change = if balance > previous_balance {
+                balance - previous_balance
+            } else {
+                previous_balance - balance
+            };
+            let max_change = TokenAmount::from_float(max_change, 18);
+            if TokenAmount::new(change, 18) > max_change {
+                alerts.alert_with_context(
+                    watch_config.account_funds_alert.details.annotate(format!(
+                        "Ethereum account ({}) balance changed by {} since the last check. Previous balance: {}, current balance: {}",
+                        account_address, change, previous_balance, balance
+                    )),
+                    watch_config.account_funds_alert.alert_level.clone(),
+                    AlertType::EthereumAccountFundsChange,
+                    None,
+                    json!({
+                        "account": account_address,
+                        "balance": balance.to_string(),
+                        "previous_balance": previous_balance.to_string(),
+                        "change": change.to_string(),
+                        "threshold": max_change.to_string(),
+                    }),
+                );
+                actions.action(
+                    watch_config.account_funds_alert.alert_action.clone(),
+                    Some(watch_config.account_funds_alert.alert_level.clone()),
+                );
+                outcome = CheckOutcome::Alerted(AlertType::EthereumAccountFundsChange);
+            }
+        }
+        _ => {}
+    }
+
+    // track the balance over time to project when it will run out
+    if check_balance_runway {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        match balance_samples.back() {
+            Some((_, last_balance)) if balance > *last_balance => {
+                // account was topped up so start a new trend
+                balance_samples.clear();
+                balance_samples.push_back((now, balance));
+            }
+            Some((last_time, _)) if now.saturating_sub(*last_time) < BALANCE_SAMPLE_PERIOD => {}
+            _ => {
+                balance_samples.push_back((now, balance));
+                if balance_samples.len() > BALANCE_SAMPLE_COUNT {
+                    balance_samples.pop_front();
+                }
+            }
+        }
+        match EthereumChain::get_seconds_until_depleted(balance_samples) {
+            Some(seconds_left) => {
+                let runway = watch_config.balance_runway_alert.balance_runway_hours as u64 * 3600;
+                if seconds_left < runway {
+                    alerts.alert(
+                        watch_config.balance_runway_alert.details.annotate(format!(
+                            "Ethereum account ({}) is projected to run out of funds in {} hours. Current balance: {}",
+                            account_address,
+                            seconds_left / 3600,
+                            balance
+                        )),
+                        watch_config.balance_runway_alert.alert_level.clone(),
+                        AlertType::EthereumAccountFundsRunway,
+                    );
+                    actions.action(
+                        watch_config.balance_runway_alert.alert_action.clone(),
+                        Some(watch_config.balance_runway_alert.alert_level.clone()),
+                    );
+                    outcome = CheckOutcome::Alerted(AlertType::EthereumAccountFundsRunway);
+                }
+            }
+            None => {}
+        }
+    }
+
+    outcome
+}
+
+// gets the block range for the next commit scan, starting where the last scan ended
+// (the range is capped so a long run of failures to get the latest block can't make the logs query huge)
+async fn get_commit_check_range(
+    ethereum_chain: &EthereumChain,
+    last_commit_check_block: u64,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> Option<(u64, u64)> {
+    match ethereum_chain.get_latest_block_number().await {
+        Ok(latest_block) => {
+            alerts.record_success(AlertType::EthereumInvalidStateCommit, "latest block");
+            let oldest_block = max(latest_block, MAX_COMMIT_SCAN_BLOCKS) - MAX_COMMIT_SCAN_BLOCKS;
+            if last_commit_check_block < oldest_block {
+                log::warn!(
+                    "State commit check fell behind, skipping blocks [{}, {}).",
+                    last_commit_check_block,
+                    oldest_block
+                );
+            }
+            let from_block = min(max(last_commit_check_block, oldest_block), latest_block);
+            Some((from_block, latest_block))
+        }
+        Err(e) => {
+            // the next scan starts from the same block, so alert if it stops advancing
+            if alerts.record_failure(
+                AlertType::EthereumInvalidStateCommit,
+                "latest block",
+                watch_config
+                    .invalid_state_commit_alert
+                    .details
+                    .consecutive_failures_to_alert,
+            ) {
+                alerts.alert_with_dedup_suffix(
+                    watch_config.invalid_state_commit_alert.details.annotate(format!(
+                        "State commit check has not advanced past block {last_commit_check_block}: {e}"
+                    )),
+                    watch_config.invalid_state_commit_alert.alert_level.clone(),
+                    AlertType::EthereumInvalidStateCommit,
+                    "latest block",
+                );
+                actions.action(
+                    watch_config.invalid_state_commit_alert.alert_action.clone(),
+                    Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
+                );
+            }
+            None
+        }
+    }
+}
+
+async fn check_state_commits(
+    state_contract: &StateContract,
+    fuel_chain: &FuelChain,
+    (from_block, to_block): (u64, u64),
+    commit_history: &mut CommitHistory,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let check_invalid_commits = is_check_enabled(
+        watch_config.invalid_state_commit_alert.enabled,
+        &watch_config.invalid_state_commit_alert.alert_level,
+    );
+    let commit_logs = match state_contract.get_latest_commits(from_block, to_block).await {
+        Ok(commit_logs) => commit_logs,
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumInvalidStateCommit,
+                "",
+                watch_config
+                    .invalid_state_commit_alert
+                    .details
+                    .consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    watch_config
+                        .invalid_state_commit_alert
+                        .details
+                        .annotate(format!("Failed to check state contract commits: {e}")),
+                    watch_config.invalid_state_commit_alert.alert_level.clone(),
+                    AlertType::EthereumInvalidStateCommit,
+                );
+                actions.action(
+                    watch_config.invalid_state_commit_alert.alert_action.clone(),
+                    Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
+                );
+            }
+            return CheckOutcome::Failed(e.to_string());
+        }
+    };
+    alerts.record_success(AlertType::EthereumInvalidStateCommit, "");
+
+    if !commit_logs.commits.is_empty() {
+        commit_history.last_commit_time = SystemTime::now();
+    }
+    for block in &commit_logs.commit_blocks {
+        // the first block of each scan overlaps the last scan so skip commits already recorded
+        match commit_history.commit_blocks.back() {
+            Some(last_block) if block <= last_block => continue,
+            _ => {}
+        }
+        commit_history.commit_blocks.push_back(*block);
+        if commit_history.commit_blocks.len() > watch_config.commit_cadence_alert.sample_count {
+            commit_history.commit_blocks.pop_front();
+        }
+    }
+    if !check_invalid_commits {
+        return CheckOutcome::Ok;
+    }
+
+    let mut outcome = CheckOutcome::Ok;
+    if commit_logs.skipped > 0 {
+        alerts.alert(
+            watch_config.invalid_state_commit_alert.details.annotate(format!(
+                "Skipped {} malformed state contract commit logs.",
+                commit_logs.skipped
+            )),
+            watch_config.invalid_state_commit_alert.alert_level.clone(),
+            AlertType::EthereumInvalidStateCommit,
+        );
+        outcome = CheckOutcome::Alerted(AlertType::EthereumInvalidStateCommit);
+    }
+    for (height, hash) in commit_logs.commits {
+        match fuel_chain.verify_block_commit(height, &hash).await {
+            Ok(valid) => {
+                if !valid {
+                    // confirm the commit really exists before acting so a single bad rpc can't trigger a pause
+                    let secondary_note = match state_contract
+                        .confirm_commit_on_secondary(height, &hash, from_block, to_block)
+                        .await
+                    {
+                        Ok(Some(false)) => {
                             alerts.alert(
-                                format!("Failed to check state contract commits: {e}"),
+                                format!(
+                                    "The primary ethereum rpc reported a commit the secondary rpc did not see. Height: {height}, Hash: {hash}"
+                                ),
                                 watch_config.invalid_state_commit_alert.alert_level.clone(),
+                                AlertType::RpcDisagreement,
                             );
-                            actions.action(
-                                watch_config.invalid_state_commit_alert.alert_action.clone(),
-                                Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
-                            );
+                            outcome = CheckOutcome::Alerted(AlertType::RpcDisagreement);
+                            continue;
                         }
-                    }
-                    last_commit_check_block = match ethereum_chain.get_latest_block_number().await {
-                        Ok(block_num) => block_num,
-                        Err(_) => last_commit_check_block,
+                        Ok(_) => String::new(),
+                        Err(e) => format!(" (failed to confirm on the secondary ethereum rpc: {e})"),
                     };
+                    alerts.alert(
+                        watch_config.invalid_state_commit_alert.details.annotate(format!(
+                            "An invalid commit was made on the state contract. Height: {height}, Hash: {hash}{secondary_note}"
+                        )),
+                        watch_config.invalid_state_commit_alert.alert_level.clone(),
+                        AlertType::EthereumInvalidStateCommit,
+                    );
+                    actions.action(
+                        watch_config.invalid_state_commit_alert.alert_action.clone(),
+                        Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
+                    );
+                    outcome = CheckOutcome::Alerted(AlertType::EthereumInvalidStateCommit);
                 }
+            }
+            Err(e) => {
+                // commits are not re-verified on the next cycle, so this failure can't wait for a repeat
+                alerts.alert(
+                    watch_config
+                        .invalid_state_commit_alert
+                        .details
+                        .annotate(format!("Failed to check state contract commits: {e}")),
+                    watch_config.invalid_state_commit_alert.alert_level.clone(),
+                    AlertType::EthereumInvalidStateCommit,
+                );
+                actions.action(
+                    watch_config.invalid_state_commit_alert.alert_action.clone(),
+                    Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
+                );
+                outcome = CheckOutcome::Failed(e.to_string());
+            }
+        }
+    }
 
-                // check base asset deposits
-                for portal_deposit_alert in &watch_config.portal_deposit_alerts {
-                    if portal_deposit_alert.alert_level != AlertLevel::None {
-                        let latest_block = last_commit_check_block;
-                        let time_frame = portal_deposit_alert.time_frame;
-                        match portal_contract.get_amount_deposited(time_frame, latest_block).await {
-                            Ok(amount) => {
-                                println!("Total ETH deposited: {:?}", amount);
-                                let amount_threshold = EthereumChain::get_value(portal_deposit_alert.amount, 18);
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "Base asset deposit threshold of {} over {} seconds has been reached. Amount deposited: {}",
-                                            amount_threshold, time_frame, amount
-                                        ),
-                                        portal_deposit_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        portal_deposit_alert.alert_action.clone(),
-                                        Some(portal_deposit_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check base asset deposits: {e}"),
-                                    portal_deposit_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    portal_deposit_alert.alert_action.clone(),
-                                    Some(portal_deposit_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                    }
+    outcome
+}
+
+fn check_commit_stall_time(
+    last_commit_time: SystemTime,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(
+        watch_config.commit_stall_alert.enabled,
+        &watch_config.commit_stall_alert.alert_level,
+    ) {
+        return CheckOutcome::Ok;
+    }
+
+    let seconds_since_last_commit = match SystemTime::now().duration_since(last_commit_time) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+    let max_commit_interval = watch_config.commit_stall_alert.max_commit_interval_secs as u64;
+    if seconds_since_last_commit > max_commit_interval {
+        alerts.alert(
+            watch_config.commit_stall_alert.details.annotate(format!(
+                "No commit has been made on the state contract for over {} seconds. Last commit was observed {} seconds ago.",
+                max_commit_interval, seconds_since_last_commit
+            )),
+            watch_config.commit_stall_alert.alert_level.clone(),
+            AlertType::EthereumCommitStalled,
+        );
+        actions.action(
+            watch_config.commit_stall_alert.alert_action.clone(),
+            Some(watch_config.commit_stall_alert.alert_level.clone()),
+        );
+        return CheckOutcome::Alerted(AlertType::EthereumCommitStalled);
+    }
+    CheckOutcome::Ok
+}
+
+fn check_commit_cadence(
+    commit_blocks: &VecDeque<u64>,
+    last_report: &mut SystemTime,
+    watch_config: &EthereumClientWatcher,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let cadence_alert = &watch_config.commit_cadence_alert;
+    if !is_check_enabled(cadence_alert.enabled, &cadence_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+    let seconds_since_report = match SystemTime::now().duration_since(*last_report) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+    if seconds_since_report < cadence_alert.report_interval_secs as u64 {
+        return CheckOutcome::Ok;
+    }
+
+    // wait until there are at least two commits to measure between
+    let (p50, p95) = match (
+        StateContract::get_commit_interval_percentile(commit_blocks, 50.0),
+        StateContract::get_commit_interval_percentile(commit_blocks, 95.0),
+    ) {
+        (Some(p50), Some(p95)) => (p50, p95),
+        _ => return CheckOutcome::Ok,
+    };
+    *last_report = SystemTime::now();
+    alerts.alert_with_context(
+        cadence_alert.details.annotate(format!(
+            "State contract commit interval over the last {} commits: p50 {p50}s, p95 {p95}s.",
+            commit_blocks.len()
+        )),
+        cadence_alert.alert_level.clone(),
+        AlertType::EthereumCommitCadence,
+        None,
+        json!({
+            "commits": commit_blocks.len(),
+            "p50_interval_secs": p50,
+            "p95_interval_secs": p95,
+            "start_block": commit_blocks.front(),
+            "end_block": commit_blocks.back(),
+        }),
+    );
+    CheckOutcome::Alerted(AlertType::EthereumCommitCadence)
+}
+
+async fn check_finality_lag(
+    ethereum_chain: &EthereumChain,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let finality_lag_alert = &watch_config.finality_lag_alert;
+    if !is_check_enabled(finality_lag_alert.enabled, &finality_lag_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    match ethereum_chain.get_finality_lag().await {
+        Ok(finality_lag) => {
+            alerts.record_success(AlertType::EthereumFinalityLag, "");
+            if finality_lag > finality_lag_alert.max_finality_lag_blocks {
+                alerts.alert(
+                    finality_lag_alert.details.annotate(format!(
+                        "Ethereum finality is lagging more than {} blocks behind the latest block. Current lag: {} blocks.",
+                        finality_lag_alert.max_finality_lag_blocks, finality_lag
+                    )),
+                    finality_lag_alert.alert_level.clone(),
+                    AlertType::EthereumFinalityLag,
+                );
+                actions.action(
+                    finality_lag_alert.alert_action.clone(),
+                    Some(finality_lag_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumFinalityLag);
+            }
+            CheckOutcome::Ok
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumFinalityLag,
+                "",
+                finality_lag_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    finality_lag_alert
+                        .details
+                        .annotate(format!("Failed to check ethereum finality lag: {e}")),
+                    finality_lag_alert.alert_level.clone(),
+                    AlertType::EthereumFinalityLag,
+                );
+                actions.action(
+                    finality_lag_alert.alert_action.clone(),
+                    Some(finality_lag_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+// checks the provider isn't serving blocks behind the highest one it has already reported
+async fn check_stale_rpc(
+    ethereum_chain: &EthereumChain,
+    max_block_seen: &mut u64,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let stale_rpc_alert = &watch_config.stale_rpc_alert;
+    if !is_check_enabled(stale_rpc_alert.enabled, &stale_rpc_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    match ethereum_chain.get_latest_block_number().await {
+        Ok(latest_block) => {
+            let regression =
+                get_regression_to_alert_on(max_block_seen, latest_block, stale_rpc_alert.max_block_regression);
+            match regression {
+                Some(regression) => {
+                    alerts.alert_with_context(
+                        stale_rpc_alert.details.annotate(format!(
+                            "Ethereum provider returned block {} which is {} blocks behind the highest block it has returned ({}). It may be serving data from a lagging node.",
+                            latest_block, regression, max_block_seen
+                        )),
+                        stale_rpc_alert.alert_level.clone(),
+                        AlertType::StaleRpcNode,
+                        None,
+                        json!({
+                            "latest_block": latest_block,
+                            "max_block_seen": *max_block_seen,
+                            "regression": regression,
+                        }),
+                    );
+                    actions.action(
+                        stale_rpc_alert.alert_action.clone(),
+                        Some(stale_rpc_alert.alert_level.clone()),
+                    );
+                    CheckOutcome::Alerted(AlertType::StaleRpcNode)
                 }
+                None => CheckOutcome::Ok,
+            }
+        }
+        // failing to reach the provider at all is reported by the connection check
+        Err(e) => CheckOutcome::Failed(e.to_string()),
+    }
+}
 
-                // check ERC20 token deposits
-                for gateway_deposit_alert in &watch_config.gateway_deposit_alerts {
-                    if gateway_deposit_alert.alert_level != AlertLevel::None {
-                        let latest_block = last_commit_check_block;
-                        match gateway_contract
-                            .get_amount_deposited(
-                                gateway_deposit_alert.time_frame,
-                                &gateway_deposit_alert.token_address,
-                                latest_block,
-                            )
-                            .await
-                        {
-                            Ok(amount) => {
-                                println!("Total Tokens deposited: {:?}", amount);
-                                let amount_threshold = EthereumChain::get_value(
-                                    gateway_deposit_alert.amount,
-                                    gateway_deposit_alert.token_decimals,
-                                );
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "ERC20 deposit threshold of {}{} over {} seconds has been reached. Amount deposited: {}{}",
-                                            amount_threshold, gateway_deposit_alert.token_name, gateway_deposit_alert.time_frame, amount, gateway_deposit_alert.token_name
-                                        ),
-                                        gateway_deposit_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        gateway_deposit_alert.alert_action.clone(),
-                                        Some(gateway_deposit_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check ERC20 deposits: {e}"),
-                                    gateway_deposit_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    gateway_deposit_alert.alert_action.clone(),
-                                    Some(gateway_deposit_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                    }
+// gets how far the latest block is behind the highest one seen once that is more than max_regression
+// (the highest block seen is updated as it goes, so only moving backwards counts)
+fn get_regression_to_alert_on(max_block_seen: &mut u64, latest_block: u64, max_regression: u64) -> Option<u64> {
+    let regression = max_block_seen.saturating_sub(latest_block);
+    *max_block_seen = max(*max_block_seen, latest_block);
+    match regression > max_regression {
+        true => Some(regression),
+        false => None,
+    }
+}
+
+// checks the provider returns logs for the block the log based checks scan up to
+async fn check_empty_logs(
+    ethereum_chain: &EthereumChain,
+    latest_block: u64,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let empty_logs_alert = &watch_config.empty_logs_alert;
+    if !is_check_enabled(empty_logs_alert.enabled, &empty_logs_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    match ethereum_chain.are_block_logs_indexed(latest_block).await {
+        Ok(true) => {
+            alerts.record_success(AlertType::SuspiciousEmptyLogs, "");
+            CheckOutcome::Ok
+        }
+        Ok(false) => {
+            alerts.record_success(AlertType::SuspiciousEmptyLogs, "");
+            alerts.alert_with_context(
+                empty_logs_alert.details.annotate(format!(
+                    "Ethereum provider returned no logs for block {latest_block} even though the block has logs. Deposit and withdrawal activity may be under reported until its log index catches up."
+                )),
+                empty_logs_alert.alert_level.clone(),
+                AlertType::SuspiciousEmptyLogs,
+                None,
+                json!({ "block": latest_block }),
+            );
+            actions.action(
+                empty_logs_alert.alert_action.clone(),
+                Some(empty_logs_alert.alert_level.clone()),
+            );
+            CheckOutcome::Alerted(AlertType::SuspiciousEmptyLogs)
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::SuspiciousEmptyLogs,
+                "",
+                empty_logs_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    empty_logs_alert
+                        .details
+                        .annotate(format!("Failed to check the ethereum provider's logs: {e}")),
+                    empty_logs_alert.alert_level.clone(),
+                    AlertType::SuspiciousEmptyLogs,
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+// gets the role grants and revocations on every bridge contract since the last checked block (labelled with the
+// contract they belong to) along with the block checked up to
+// (the first check only sets where scanning starts, and a long outage skips blocks so the logs query can't get huge)
+async fn get_role_changes(
+    ethereum_chain: &EthereumChain,
+    (state_contract, gateway_contract, portal_contract): (&StateContract, &GatewayContract, &PortalContract),
+    last_checked_block: Option<u64>,
+) -> Result<(u64, Vec<String>)> {
+    let latest_block = ethereum_chain.get_latest_block_number().await?;
+    let from_block = match last_checked_block {
+        Some(last_checked_block) if last_checked_block < latest_block => max(
+            last_checked_block + 1,
+            max(latest_block, MAX_ROLE_SCAN_BLOCKS) - MAX_ROLE_SCAN_BLOCKS,
+        ),
+        Some(last_checked_block) => return Ok((last_checked_block, vec![])),
+        None => return Ok((latest_block, vec![])),
+    };
+
+    let mut role_changes = vec![];
+    for role_change in state_contract.get_role_changes(from_block, latest_block).await? {
+        role_changes.push(describe_role_change("state", &role_change));
+    }
+    for role_change in gateway_contract.get_role_changes(from_block, latest_block).await? {
+        role_changes.push(describe_role_change("gateway", &role_change));
+    }
+    for role_change in portal_contract.get_role_changes(from_block, latest_block).await? {
+        role_changes.push(describe_role_change("portal", &role_change));
+    }
+    Ok((latest_block, role_changes))
+}
+
+fn describe_role_change(contract_name: &str, role_change: &RoleChange) -> String {
+    match role_change.granted {
+        true => format!(
+            "{contract_name} {} granted to {:#x}",
+            role_change.role_name, role_change.account
+        ),
+        false => format!(
+            "{contract_name} {} revoked from {:#x}",
+            role_change.role_name, role_change.account
+        ),
+    }
+}
+
+// checks no role on the bridge contracts has been granted to or revoked from an account since startup
+// (the changes are kept so they keep alerting until the watchtower is restarted)
+async fn check_ownership(
+    ethereum_chain: &EthereumChain,
+    contracts: (&StateContract, &GatewayContract, &PortalContract),
+    role_history: &mut RoleHistory,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let ownership_alert = &watch_config.ownership_alert;
+    if !is_check_enabled(ownership_alert.enabled, &ownership_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let (checked_block, role_changes) =
+        match get_role_changes(ethereum_chain, contracts, role_history.last_checked_block).await {
+            Ok(role_changes) => role_changes,
+            Err(e) => {
+                if alerts.record_failure(
+                    AlertType::OwnershipChanged,
+                    "",
+                    ownership_alert.details.consecutive_failures_to_alert,
+                ) {
+                    alerts.alert(
+                        ownership_alert
+                            .details
+                            .annotate(format!("Failed to check contract ownership: {e}")),
+                        ownership_alert.alert_level.clone(),
+                        AlertType::OwnershipChanged,
+                    );
                 }
+                return CheckOutcome::Failed(e.to_string());
+            }
+        };
+    alerts.record_success(AlertType::OwnershipChanged, "");
+    role_history.last_checked_block = Some(checked_block);
+    role_history.changes.extend(role_changes);
+    if role_history.changes.is_empty() {
+        return CheckOutcome::Ok;
+    }
+
+    alerts.alert_with_context(
+        ownership_alert.details.annotate(format!(
+            "Control of the bridge contracts has changed: {}.",
+            role_history.changes.join(", ")
+        )),
+        ownership_alert.alert_level.clone(),
+        AlertType::OwnershipChanged,
+        None,
+        json!({ "changes": role_history.changes }),
+    );
+    actions.action(
+        ownership_alert.alert_action.clone(),
+        Some(ownership_alert.alert_level.clone()),
+    );
+    CheckOutcome::Alerted(AlertType::OwnershipChanged)
+}
+
+async fn check_gas_price(
+    ethereum_chain: &EthereumChain,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let gas_price_alert = &watch_config.gas_price_alert;
+    if !is_check_enabled(gas_price_alert.enabled, &gas_price_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
 
-                thread::sleep(POLL_DURATION);
+    match ethereum_chain.get_gas_price().await {
+        Ok(gas_price) => {
+            alerts.record_success(AlertType::EthereumGasSpike, "");
+            let max_gas_price = TokenAmount::from_float(gas_price_alert.max_gas_price_gwei, 9).raw();
+            if gas_price > max_gas_price {
+                alerts.alert_with_context(
+                    gas_price_alert.details.annotate(format!(
+                        "Ethereum gas price is above {} gwei so pause transactions may be expensive or slow. Current gas price: {} gwei.",
+                        gas_price_alert.max_gas_price_gwei,
+                        format_units(gas_price, "gwei").unwrap_or_default()
+                    )),
+                    gas_price_alert.alert_level.clone(),
+                    AlertType::EthereumGasSpike,
+                    None,
+                    json!({
+                        "gas_price": gas_price.to_string(),
+                        "threshold": max_gas_price.to_string(),
+                    }),
+                );
+                actions.action(
+                    gas_price_alert.alert_action.clone(),
+                    Some(gas_price_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumGasSpike);
             }
+            CheckOutcome::Ok
         }
-    });
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumGasSpike,
+                "",
+                gas_price_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    gas_price_alert
+                        .details
+                        .annotate(format!("Failed to check ethereum gas price: {e}")),
+                    gas_price_alert.alert_level.clone(),
+                    AlertType::EthereumGasSpike,
+                );
+                actions.action(
+                    gas_price_alert.alert_action.clone(),
+                    Some(gas_price_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
 
-    Ok(handle)
+async fn check_base_asset_deposits(
+    portal_contract: &PortalContract,
+    portal_deposit_alert: &DepositAlert,
+    latest_block: u64,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(portal_deposit_alert.enabled, &portal_deposit_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let time_frame = portal_deposit_alert.time_frame;
+    let check_id = time_frame.to_string();
+    match portal_contract.get_amount_deposited(time_frame, latest_block).await {
+        Ok(amount) => {
+            alerts.record_success(AlertType::EthereumPortalDeposit, &check_id);
+            log::debug!("Total ETH deposited: {:?}", amount);
+            let amount = TokenAmount::new(amount, 18);
+            let amount_threshold = TokenAmount::from_float(portal_deposit_alert.amount, 18);
+            let band = get_threshold_band(
+                amount,
+                portal_deposit_alert.amount,
+                portal_deposit_alert.warning_fraction,
+                18,
+            );
+            if band == ThresholdBand::Over {
+                let start_block = get_start_block(time_frame, latest_block);
+                alerts.alert_with_context(
+                    portal_deposit_alert.details.annotate(format!(
+                        "Base asset deposit threshold of {} over {} seconds has been reached. Amount deposited: {} (blocks [{}, {}])",
+                        amount_threshold, time_frame, amount, start_block, latest_block
+                    )),
+                    portal_deposit_alert.alert_level.clone(),
+                    AlertType::EthereumPortalDeposit,
+                    None,
+                    get_amount_context(amount, amount_threshold, None, time_frame, start_block, latest_block),
+                );
+                actions.action(
+                    portal_deposit_alert.alert_action.clone(),
+                    Some(portal_deposit_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumPortalDeposit);
+            }
+
+            // warn early once deposits get close to the threshold (no action is taken)
+            if band == ThresholdBand::Warning {
+                let start_block = get_start_block(time_frame, latest_block);
+                alerts.alert_with_context(
+                    portal_deposit_alert.details.annotate(format!(
+                        "Base asset deposits over {} seconds are approaching the threshold of {}. Amount deposited: {} (blocks [{}, {}])",
+                        time_frame, amount_threshold, amount, start_block, latest_block
+                    )),
+                    AlertLevel::Warn,
+                    AlertType::EthereumPortalDeposit,
+                    Some("warning"),
+                    get_amount_context(amount, amount_threshold, None, time_frame, start_block, latest_block),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumPortalDeposit);
+            }
+            CheckOutcome::Ok
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumPortalDeposit,
+                &check_id,
+                portal_deposit_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    portal_deposit_alert
+                        .details
+                        .annotate(format!("Failed to check base asset deposits: {e}")),
+                    portal_deposit_alert.alert_level.clone(),
+                    AlertType::EthereumPortalDeposit,
+                );
+                actions.action(
+                    portal_deposit_alert.alert_action.clone(),
+                    Some(portal_deposit_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+async fn check_token_deposits(
+    gateway_contract: &GatewayContract,
+    gateway_deposit_alert: &DepositAlert,
+    verify_token_names: bool,
+    latest_block: u64,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(gateway_deposit_alert.enabled, &gateway_deposit_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let check_id = format!(
+        "{}/{}",
+        gateway_deposit_alert.token_address, gateway_deposit_alert.time_frame
+    );
+    match gateway_contract
+        .get_amount_deposited(
+            gateway_deposit_alert.time_frame,
+            &gateway_deposit_alert.token_address,
+            latest_block,
+        )
+        .await
+    {
+        Ok(amount) => {
+            alerts.record_success(AlertType::EthereumGatewayDeposit, &check_id);
+            log::debug!("Total tokens deposited: {:?}", amount);
+            let amount = TokenAmount::new(amount, gateway_deposit_alert.token_decimals);
+            let amount_threshold =
+                TokenAmount::from_float(gateway_deposit_alert.amount, gateway_deposit_alert.token_decimals);
+            let band = get_threshold_band(
+                amount,
+                gateway_deposit_alert.amount,
+                gateway_deposit_alert.warning_fraction,
+                gateway_deposit_alert.token_decimals,
+            );
+            if band == ThresholdBand::Over {
+                let token_name = get_token_name(
+                    gateway_contract,
+                    &gateway_deposit_alert.token_name,
+                    &gateway_deposit_alert.token_address,
+                    verify_token_names,
+                    alerts,
+                )
+                .await;
+                let time_frame = gateway_deposit_alert.time_frame;
+                let start_block = get_start_block(time_frame, latest_block);
+                alerts.alert_with_context(
+                    gateway_deposit_alert.details.annotate(format!(
+                        "ERC20 deposit threshold of {}{} over {} seconds has been reached. Amount deposited: {}{} (blocks [{}, {}])",
+                        amount_threshold, token_name, time_frame, amount, token_name, start_block, latest_block
+                    )),
+                    gateway_deposit_alert.alert_level.clone(),
+                    AlertType::EthereumGatewayDeposit,
+                    Some(&gateway_deposit_alert.token_address),
+                    get_amount_context(
+                        amount,
+                        amount_threshold,
+                        Some(&gateway_deposit_alert.token_address),
+                        time_frame,
+                        start_block,
+                        latest_block,
+                    ),
+                );
+                actions.action(
+                    gateway_deposit_alert.alert_action.clone(),
+                    Some(gateway_deposit_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumGatewayDeposit);
+            }
+
+            // warn early once deposits get close to the threshold (no action is taken)
+            if band == ThresholdBand::Warning {
+                let token_name = get_token_name(
+                    gateway_contract,
+                    &gateway_deposit_alert.token_name,
+                    &gateway_deposit_alert.token_address,
+                    verify_token_names,
+                    alerts,
+                )
+                .await;
+                let time_frame = gateway_deposit_alert.time_frame;
+                let start_block = get_start_block(time_frame, latest_block);
+                alerts.alert_with_context(
+                    gateway_deposit_alert.details.annotate(format!(
+                        "ERC20 deposits over {} seconds are approaching the threshold of {}{}. Amount deposited: {}{} (blocks [{}, {}])",
+                        time_frame, amount_threshold, token_name, amount, token_name, start_block, latest_block
+                    )),
+                    AlertLevel::Warn,
+                    AlertType::EthereumGatewayDeposit,
+                    Some(&format!("{}/warning", gateway_deposit_alert.token_address)),
+                    get_amount_context(
+                        amount,
+                        amount_threshold,
+                        Some(&gateway_deposit_alert.token_address),
+                        time_frame,
+                        start_block,
+                        latest_block,
+                    ),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumGatewayDeposit);
+            }
+            CheckOutcome::Ok
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumGatewayDeposit,
+                &check_id,
+                gateway_deposit_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert_with_dedup_suffix(
+                    gateway_deposit_alert
+                        .details
+                        .annotate(format!("Failed to check ERC20 deposits: {e}")),
+                    gateway_deposit_alert.alert_level.clone(),
+                    AlertType::EthereumGatewayDeposit,
+                    &gateway_deposit_alert.token_address,
+                );
+                actions.action(
+                    gateway_deposit_alert.alert_action.clone(),
+                    Some(gateway_deposit_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+async fn check_discovered_token_deposits(
+    gateway_contract: &GatewayContract,
+    watch_config: &EthereumClientWatcher,
+    latest_block: u64,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let discovered_token_alert = &watch_config.discovered_token_deposit_alert;
+    if !is_check_enabled(discovered_token_alert.enabled, &discovered_token_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let amounts = match gateway_contract
+        .get_amounts_deposited_by_token(discovered_token_alert.time_frame, latest_block)
+        .await
+    {
+        Ok(amounts) => amounts,
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::EthereumGatewayDeposit,
+                "",
+                discovered_token_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    discovered_token_alert
+                        .details
+                        .annotate(format!("Failed to check discovered ERC20 deposits: {e}")),
+                    discovered_token_alert.alert_level.clone(),
+                    AlertType::EthereumGatewayDeposit,
+                );
+                actions.action(
+                    discovered_token_alert.alert_action.clone(),
+                    Some(discovered_token_alert.alert_level.clone()),
+                );
+            }
+            return CheckOutcome::Failed(e.to_string());
+        }
+    };
+    alerts.record_success(AlertType::EthereumGatewayDeposit, "");
+
+    // tokens with their own alert (or explicitly ignored) are left to those settings
+    let amount_threshold =
+        TokenAmount::from_float(discovered_token_alert.amount, discovered_token_alert.token_decimals);
+    let mut outcome = CheckOutcome::Ok;
+    for (token, amount) in amounts {
+        let amount = TokenAmount::new(amount, discovered_token_alert.token_decimals);
+        let token_address = format!("{:?}", token);
+        if watch_config.is_token_configured(&token_address) || watch_config.is_token_ignored(&token_address) {
+            continue;
+        }
+        if amount >= amount_threshold {
+            let token_name = get_token_name(gateway_contract, &None, &token_address, false, alerts).await;
+            let time_frame = discovered_token_alert.time_frame;
+            let start_block = get_start_block(time_frame, latest_block);
+            alerts.alert_with_context(
+                discovered_token_alert.details.annotate(format!(
+                    "ERC20 deposit threshold of {}{} over {} seconds has been reached for discovered token {}. Amount deposited: {}{} (blocks [{}, {}])",
+                    amount_threshold,
+                    token_name,
+                    time_frame,
+                    token_address,
+                    amount,
+                    token_name,
+                    start_block,
+                    latest_block
+                )),
+                discovered_token_alert.alert_level.clone(),
+                AlertType::EthereumGatewayDeposit,
+                Some(&token_address),
+                get_amount_context(
+                    amount,
+                    amount_threshold,
+                    Some(&token_address),
+                    time_frame,
+                    start_block,
+                    latest_block,
+                ),
+            );
+            actions.action(
+                discovered_token_alert.alert_action.clone(),
+                Some(discovered_token_alert.alert_level.clone()),
+            );
+            outcome = CheckOutcome::Alerted(AlertType::EthereumGatewayDeposit);
+        }
+    }
+    outcome
+}
+
+async fn check_base_asset_imbalance(
+    portal_contract: &PortalContract,
+    portal_imbalance_alert: &ImbalanceAlert,
+    latest_block: u64,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(portal_imbalance_alert.enabled, &portal_imbalance_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let time_frame = portal_imbalance_alert.time_frame;
+    let check_id = time_frame.to_string();
+    let deposited = portal_contract.get_amount_deposited(time_frame, latest_block).await;
+    let withdrawn = portal_contract.get_amount_withdrawn(time_frame, latest_block).await;
+    match (deposited, withdrawn) {
+        (Ok(deposited), Ok(withdrawn)) => {
+            alerts.record_success(AlertType::EthereumPortalImbalance, &check_id);
+            let threshold = TokenAmount::from_float(portal_imbalance_alert.imbalance_threshold, 18);
+            let (net, direction) = EthereumChain::get_net_flow(deposited, withdrawn);
+            let net = TokenAmount::new(net, 18);
+            if net >= threshold {
+                alerts.alert_with_context(
+                    portal_imbalance_alert.details.annotate(format!(
+                        "Base asset imbalance threshold of {} over {} seconds has been reached ({}). Deposited: {}, withdrawn: {}",
+                        threshold, time_frame, direction, deposited, withdrawn
+                    )),
+                    portal_imbalance_alert.alert_level.clone(),
+                    AlertType::EthereumPortalImbalance,
+                    None,
+                    json!({
+                        "deposited": deposited.to_string(),
+                        "withdrawn": withdrawn.to_string(),
+                        "net": net.to_string(),
+                        "direction": direction,
+                        "threshold": threshold.to_string(),
+                        "time_frame": time_frame,
+                        "start_block": get_start_block(time_frame, latest_block),
+                        "end_block": latest_block,
+                    }),
+                );
+                actions.action(
+                    portal_imbalance_alert.alert_action.clone(),
+                    Some(portal_imbalance_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumPortalImbalance);
+            }
+            CheckOutcome::Ok
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            if alerts.record_failure(
+                AlertType::EthereumPortalImbalance,
+                &check_id,
+                portal_imbalance_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    portal_imbalance_alert
+                        .details
+                        .annotate(format!("Failed to check base asset imbalance: {e}")),
+                    portal_imbalance_alert.alert_level.clone(),
+                    AlertType::EthereumPortalImbalance,
+                );
+                actions.action(
+                    portal_imbalance_alert.alert_action.clone(),
+                    Some(portal_imbalance_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+async fn check_supply_mismatch(
+    portal_contract: &PortalContract,
+    fuel_chain: &FuelChain,
+    watch_config: &EthereumClientWatcher,
+    latest_block: u64,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let supply_alert = &watch_config.supply_mismatch_alert;
+    if !is_check_enabled(supply_alert.enabled, &supply_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    // every fuel block is scanned (and every message receipt counted) so the fuel side is never under counted
+    let time_frame = supply_alert.time_frame;
+    let relayed = portal_contract.get_amount_withdrawn(time_frame, latest_block).await;
+    let withdrawn = fuel_chain
+        .get_amount_withdrawn(time_frame, 1.0, WithdrawalReceiptFilter::All)
+        .await;
+    match (relayed, withdrawn) {
+        (Ok(relayed), Ok(withdrawn)) => {
+            alerts.record_success(AlertType::SupplyMismatch, "");
+            match get_supply_mismatch(relayed, withdrawn.total, supply_alert.tolerance) {
+                Some(mismatch) => {
+                    alerts.alert_with_context(
+                        supply_alert.details.annotate(format!(
+                            "Base asset released on ethereum exceeds the amount withdrawn on fuel by more than {} over {} seconds. Released on ethereum: {}, withdrawn on fuel: {}",
+                            mismatch.tolerance, time_frame, relayed, mismatch.withdrawn
+                        )),
+                        supply_alert.alert_level.clone(),
+                        AlertType::SupplyMismatch,
+                        None,
+                        json!({
+                            "relayed": relayed.to_string(),
+                            "withdrawn": mismatch.withdrawn.to_string(),
+                            "difference": mismatch.difference.to_string(),
+                            "tolerance": mismatch.tolerance.to_string(),
+                            "time_frame": time_frame,
+                            "start_block": get_start_block(time_frame, latest_block),
+                            "end_block": latest_block,
+                        }),
+                    );
+                    actions.action(
+                        supply_alert.alert_action.clone(),
+                        Some(supply_alert.alert_level.clone()),
+                    );
+                    CheckOutcome::Alerted(AlertType::SupplyMismatch)
+                }
+                None => CheckOutcome::Ok,
+            }
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            if alerts.record_failure(
+                AlertType::SupplyMismatch,
+                "",
+                supply_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    supply_alert
+                        .details
+                        .annotate(format!("Failed to check base asset supply: {e}")),
+                    supply_alert.alert_level.clone(),
+                    AlertType::SupplyMismatch,
+                );
+                actions.action(
+                    supply_alert.alert_action.clone(),
+                    Some(supply_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+// base asset released on ethereum beyond what was withdrawn on fuel (all in ethereum's 18 decimals)
+#[derive(Debug, PartialEq, Eq)]
+struct SupplyMismatch {
+    withdrawn: U256,
+    difference: U256,
+    tolerance: U256,
+}
+
+// compares what was released on ethereum against what was withdrawn on fuel (which has 9 decimals where ethereum
+// has 18), withdrawals take time to be relayed so only releases beyond what fuel withdrew are a mismatch
+fn get_supply_mismatch(relayed: U256, withdrawn_on_fuel: u64, tolerance: f64) -> Option<SupplyMismatch> {
+    let withdrawn = TokenAmount::new(withdrawn_on_fuel, 9).rescale(18).raw();
+    let tolerance = TokenAmount::from_float(tolerance, 18).raw();
+    match relayed > withdrawn.saturating_add(tolerance) {
+        true => Some(SupplyMismatch {
+            withdrawn,
+            difference: relayed - withdrawn,
+            tolerance,
+        }),
+        false => None,
+    }
+}
+
+async fn check_token_imbalance(
+    gateway_contract: &GatewayContract,
+    gateway_imbalance_alert: &ImbalanceAlert,
+    verify_token_names: bool,
+    latest_block: u64,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(gateway_imbalance_alert.enabled, &gateway_imbalance_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let time_frame = gateway_imbalance_alert.time_frame;
+    let token_address = &gateway_imbalance_alert.token_address;
+    let check_id = format!("{token_address}/{time_frame}");
+    let deposited = gateway_contract
+        .get_amount_deposited(time_frame, token_address, latest_block)
+        .await;
+    let withdrawn = gateway_contract
+        .get_amount_withdrawn(time_frame, token_address, latest_block)
+        .await;
+    match (deposited, withdrawn) {
+        (Ok(deposited), Ok(withdrawn)) => {
+            alerts.record_success(AlertType::EthereumGatewayImbalance, &check_id);
+            let threshold = TokenAmount::from_float(
+                gateway_imbalance_alert.imbalance_threshold,
+                gateway_imbalance_alert.token_decimals,
+            );
+            let (net, direction) = EthereumChain::get_net_flow(deposited, withdrawn);
+            let net = TokenAmount::new(net, gateway_imbalance_alert.token_decimals);
+            if net >= threshold {
+                let token_name = get_token_name(
+                    gateway_contract,
+                    &gateway_imbalance_alert.token_name,
+                    token_address,
+                    verify_token_names,
+                    alerts,
+                )
+                .await;
+                alerts.alert_with_context(
+                    gateway_imbalance_alert.details.annotate(format!(
+                        "ERC20 imbalance threshold of {}{} over {} seconds has been reached ({}). Deposited: {}{}, withdrawn: {}{}",
+                        threshold, token_name, time_frame, direction, deposited, token_name, withdrawn, token_name
+                    )),
+                    gateway_imbalance_alert.alert_level.clone(),
+                    AlertType::EthereumGatewayImbalance,
+                    Some(token_address),
+                    json!({
+                        "deposited": deposited.to_string(),
+                        "withdrawn": withdrawn.to_string(),
+                        "net": net.to_string(),
+                        "direction": direction,
+                        "threshold": threshold.to_string(),
+                        "token": token_address,
+                        "time_frame": time_frame,
+                        "start_block": get_start_block(time_frame, latest_block),
+                        "end_block": latest_block,
+                    }),
+                );
+                actions.action(
+                    gateway_imbalance_alert.alert_action.clone(),
+                    Some(gateway_imbalance_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::EthereumGatewayImbalance);
+            }
+            CheckOutcome::Ok
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            if alerts.record_failure(
+                AlertType::EthereumGatewayImbalance,
+                &check_id,
+                gateway_imbalance_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert_with_dedup_suffix(
+                    gateway_imbalance_alert
+                        .details
+                        .annotate(format!("Failed to check ERC20 imbalance: {e}")),
+                    gateway_imbalance_alert.alert_level.clone(),
+                    AlertType::EthereumGatewayImbalance,
+                    token_address,
+                );
+                actions.action(
+                    gateway_imbalance_alert.alert_action.clone(),
+                    Some(gateway_imbalance_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+// gets the name of a token for alert text, resolving its on-chain symbol when no name is configured
+// (or when verifying configured names, warning if the name doesn't match the symbol)
+async fn get_token_name(
+    gateway_contract: &GatewayContract,
+    token_name: &Option<String>,
+    token_address: &str,
+    verify_token_names: bool,
+    alerts: &WatchtowerAlerts,
+) -> String {
+    match token_name {
+        Some(token_name) if !verify_token_names => return token_name.clone(),
+        _ => {}
+    }
+    let symbol = match gateway_contract.get_token_symbol(token_address).await {
+        Ok(symbol) => symbol,
+        Err(e) => {
+            log::warn!("Failed to get symbol for token {token_address}: {e}");
+            return token_name.clone().unwrap_or(String::from(token_address));
+        }
+    };
+    match token_name {
+        Some(token_name) => {
+            if !token_name.eq_ignore_ascii_case(&symbol) {
+                alerts.alert_with_dedup_suffix(
+                    format!(
+                        "Configured token name {token_name} does not match the on-chain symbol {symbol} for token {token_address}. Check the config for a mismatched name and address."
+                    ),
+                    AlertLevel::Warn,
+                    AlertType::TokenNameMismatch,
+                    token_address,
+                );
+            }
+            token_name.clone()
+        }
+        None => symbol,
+    }
+}
+
+// builds the structured context for a threshold alert (amounts are strings since they can exceed a json number)
+fn get_amount_context(
+    amount: TokenAmount,
+    threshold: TokenAmount,
+    token: Option<&str>,
+    time_frame: u32,
+    start_block: u64,
+    end_block: u64,
+) -> Value {
+    json!({
+        "amount": amount.to_string(),
+        "threshold": threshold.to_string(),
+        "token": token,
+        "time_frame": time_frame,
+        "start_block": start_block,
+        "end_block": end_block,
+    })
+}
+
+async fn check_unexpected_tokens(
+    gateway_contract: &GatewayContract,
+    latest_block: u64,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let unexpected_token_alert = &watch_config.unexpected_token_alert;
+    if !is_check_enabled(unexpected_token_alert.enabled, &unexpected_token_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    match gateway_contract
+        .get_tokens_bridged(unexpected_token_alert.time_frame, latest_block)
+        .await
+    {
+        Ok(tokens) => {
+            alerts.record_success(AlertType::UnexpectedToken, "");
+            let mut outcome = CheckOutcome::Ok;
+            for token in tokens {
+                let token_address = format!("{:#x}", token);
+                if !unexpected_token_alert.is_token_allowed(&token_address) {
+                    alerts.alert_with_dedup_suffix(
+                        unexpected_token_alert.details.annotate(format!(
+                            "Unexpected ERC20 token bridged through the gateway: {}",
+                            token_address
+                        )),
+                        unexpected_token_alert.alert_level.clone(),
+                        AlertType::UnexpectedToken,
+                        &token_address,
+                    );
+                    actions.action(
+                        unexpected_token_alert.alert_action.clone(),
+                        Some(unexpected_token_alert.alert_level.clone()),
+                    );
+                    outcome = CheckOutcome::Alerted(AlertType::UnexpectedToken);
+                }
+            }
+            outcome
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::UnexpectedToken,
+                "",
+                unexpected_token_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    unexpected_token_alert
+                        .details
+                        .annotate(format!("Failed to check for unexpected tokens: {e}")),
+                    unexpected_token_alert.alert_level.clone(),
+                    AlertType::UnexpectedToken,
+                );
+                actions.action(
+                    unexpected_token_alert.alert_action.clone(),
+                    Some(unexpected_token_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+async fn check_bridge_activity(
+    ethereum_chain: &EthereumChain,
+    portal_contract: &PortalContract,
+    latest_block: u64,
+    watch_config: &EthereumClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    let inactivity_alert = &watch_config.inactivity_alert;
+    if !is_check_enabled(inactivity_alert.enabled, &inactivity_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    // no activity is expected while the chain itself is stalled (that is covered by the block production check)
+    match ethereum_chain.get_seconds_since_last_block().await {
+        Ok(seconds_since_last_block)
+            if seconds_since_last_block <= watch_config.block_production_alert.max_block_time => {}
+        _ => return CheckOutcome::Ok,
+    }
+
+    let time_frame = inactivity_alert.inactivity_window_secs;
+    let deposited = portal_contract.get_amount_deposited(time_frame, latest_block).await;
+    let withdrawn = portal_contract.get_amount_withdrawn(time_frame, latest_block).await;
+    match (deposited, withdrawn) {
+        (Ok(deposited), Ok(withdrawn)) => {
+            alerts.record_success(AlertType::BridgeInactive, "");
+            if deposited.is_zero() && withdrawn.is_zero() {
+                alerts.alert(
+                    inactivity_alert.details.annotate(format!(
+                        "No base asset deposits or withdrawals have been made over the last {} seconds.",
+                        time_frame
+                    )),
+                    inactivity_alert.alert_level.clone(),
+                    AlertType::BridgeInactive,
+                );
+                actions.action(
+                    inactivity_alert.alert_action.clone(),
+                    Some(inactivity_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::BridgeInactive);
+            }
+            CheckOutcome::Ok
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            if alerts.record_failure(
+                AlertType::BridgeInactive,
+                "",
+                inactivity_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    inactivity_alert
+                        .details
+                        .annotate(format!("Failed to check bridge activity: {e}")),
+                    inactivity_alert.alert_level.clone(),
+                    AlertType::BridgeInactive,
+                );
+                actions.action(
+                    inactivity_alert.alert_action.clone(),
+                    Some(inactivity_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::RaisedAlert;
+    use crate::test_utils::{
+        abi_bool, abi_word, call_selector, selector, test_config_builder, MockRpc, GATEWAY_CONTRACT_ADDRESS,
+        PORTAL_CONTRACT_ADDRESS, STATE_CONTRACT_ADDRESS,
+    };
+    use ethers::abi::Token;
+
+    #[test]
+    fn balance_is_checked_every_n_cycles() {
+        let checked: Vec<u64> = (0..10).filter(|cycles| is_balance_check_cycle(*cycles, 3)).collect();
+        assert_eq!(checked, vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn balance_is_checked_every_cycle_when_unset() {
+        assert!((0..5).all(|cycles| is_balance_check_cycle(cycles, 1)));
+        assert!((0..5).all(|cycles| is_balance_check_cycle(cycles, 0)));
+    }
+
+    // a gateway deposit log for the given token
+    fn token_log(token: H160) -> Value {
+        json!({
+            "address": GATEWAY_CONTRACT_ADDRESS,
+            "topics": [H256::from(keccak256("Deposit(bytes32,address,bytes32,uint256)")), H256::zero(), H256::from(token)],
+            "data": format!("0x{}", "00".repeat(64)),
+            "blockNumber": "0x60",
+        })
+    }
+
+    #[tokio::test]
+    async fn only_tokens_off_the_allowlist_alert() {
+        let allowed_token = H160::from_low_u64_be(0xaa);
+        let unexpected_token = H160::from_low_u64_be(0xbb);
+        let rpc = MockRpc::start(move |method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            "eth_getLogs" => Some(Ok(json!([token_log(allowed_token), token_log(unexpected_token)]))),
+            _ => None,
+        });
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.unexpected_token_alert.alert_level = AlertLevel::Warn;
+        watch_config.unexpected_token_alert.allowed_token_addresses = vec![format!("{:#X}", allowed_token)];
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let gateway_contract = GatewayContract::new(&config).await.unwrap();
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, mut requested_actions) = WatchtowerEthereumActions::collector(alerts.clone());
+
+        let outcome = check_unexpected_tokens(&gateway_contract, 100, &watch_config, &actions, &alerts).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::UnexpectedToken));
+        let raised_alerts = raised_alerts.take();
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].alert_type, AlertType::UnexpectedToken);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert!(raised_alerts[0].text.contains(&format!("{:#x}", unexpected_token)));
+        assert_eq!(raised_alerts[0].dedup_suffix, Some(format!("{:#x}", unexpected_token)));
+        assert!(raised_alerts[0].context.is_none());
+        assert_eq!(requested_actions.take(), vec![]);
+
+        // the scan is bounded by the block the check ran at
+        let log_requests = rpc.requests("eth_getLogs");
+        assert_eq!(log_requests.len(), 2);
+        assert!(log_requests.iter().all(|params| params[0]["toBlock"] == "0x64"));
+    }
+
+    // a RoleGranted (or RoleRevoked) log from the given contract
+    fn role_log(contract_address: &str, role_name: &str, account: H160, granted: bool) -> Value {
+        let event = match granted {
+            true => ROLE_EVENTS[0],
+            false => ROLE_EVENTS[1],
+        };
+        json!({
+            "address": contract_address,
+            "topics": [
+                H256::from(keccak256(event)),
+                H256::from(get_role_id(role_name)),
+                H256::from(account),
+                H256::zero(),
+            ],
+            "data": "0x",
+            "blockNumber": "0x65",
+        })
+    }
+
+    #[test]
+    fn role_logs_are_labelled_with_the_role_and_account() {
+        let account = H160::from_low_u64_be(0xaa);
+        let mut logs: Vec<Log> = [
+            role_log(STATE_CONTRACT_ADDRESS, "PAUSER_ROLE", account, true),
+            role_log(STATE_CONTRACT_ADDRESS, "DEFAULT_ADMIN_ROLE", account, false),
+            role_log(STATE_CONTRACT_ADDRESS, "UPGRADER_ROLE", account, true),
+        ]
+        .into_iter()
+        .map(|log| serde_json::from_value(log).unwrap())
+        .collect();
+        let mut malformed_log = logs[0].clone();
+        malformed_log.topics.truncate(2);
+        logs.insert(1, malformed_log);
+
+        let role_changes = parse_role_logs(logs, &["DEFAULT_ADMIN_ROLE", "PAUSER_ROLE"]);
+        let role_change = |role_name: String, granted| RoleChange {
+            role_name,
+            account,
+            granted,
+        };
+        assert_eq!(
+            role_changes,
+            vec![
+                role_change(String::from("PAUSER_ROLE"), true),
+                role_change(String::from("DEFAULT_ADMIN_ROLE"), false),
+                role_change(format!("{:#x}", H256::from(get_role_id("UPGRADER_ROLE"))), true),
+            ]
+        );
+    }
+
+    // an rpc whose chain advances a block on every request for the latest block, with the given logs on the
+    // contracts (matched by address)
+    fn role_rpc(logs: Vec<Value>) -> MockRpc {
+        let block = Arc::new(Mutex::new(100u64));
+        MockRpc::start(move |method, params| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            "eth_blockNumber" => {
+                let mut block = block.lock().unwrap();
+                *block += 1;
+                Some(Ok(json!(format!("{:#x}", *block))))
+            }
+            "eth_getLogs" => {
+                let address = params[0]["address"].as_str().unwrap_or_default().to_lowercase();
+                let contract_logs: Vec<Value> = logs
+                    .iter()
+                    .filter(|log| log["address"].as_str().unwrap_or_default().to_lowercase() == address)
+                    .cloned()
+                    .collect();
+                Some(Ok(json!(contract_logs)))
+            }
+            _ => None,
+        })
+    }
+
+    // runs the ownership check for a few cycles, getting the outcome of each
+    async fn check_ownership_cycles(rpc: &MockRpc, cycles: usize) -> (Vec<CheckOutcome>, Vec<RaisedAlert>) {
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let state_contract = StateContract::new(&config).await.unwrap();
+        let gateway_contract = GatewayContract::new(&config).await.unwrap();
+        let portal_contract = PortalContract::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.ownership_alert.alert_level = AlertLevel::Error;
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, _) = WatchtowerEthereumActions::collector(alerts.clone());
+
+        let mut role_history = RoleHistory {
+            last_checked_block: None,
+            changes: vec![],
+        };
+        let mut outcomes = vec![];
+        for _ in 0..cycles {
+            outcomes.push(
+                check_ownership(
+                    &ethereum_chain,
+                    (&state_contract, &gateway_contract, &portal_contract),
+                    &mut role_history,
+                    &watch_config,
+                    &actions,
+                    &alerts,
+                )
+                .await,
+            );
+        }
+        (outcomes, raised_alerts.take())
+    }
+
+    #[tokio::test]
+    async fn stable_role_holders_do_not_alert() {
+        let rpc = role_rpc(vec![]);
+        let (outcomes, raised_alerts) = check_ownership_cycles(&rpc, 3).await;
+        assert_eq!(outcomes, vec![CheckOutcome::Ok; 3]);
+        assert!(raised_alerts.is_empty());
+
+        // the first cycle only sets where scanning starts, and each later one scans the blocks since the last
+        let log_requests = rpc.requests("eth_getLogs");
+        assert_eq!(log_requests.len(), 6);
+        assert!(log_requests
+            .iter()
+            .all(|params| params[0]["fromBlock"] == params[0]["toBlock"]));
+    }
+
+    #[tokio::test]
+    async fn role_granted_to_a_new_account_alerts() {
+        let new_pauser = H160::from_low_u64_be(0xbb);
+        let rpc = role_rpc(vec![role_log(
+            GATEWAY_CONTRACT_ADDRESS,
+            "PAUSER_ROLE",
+            new_pauser,
+            true,
+        )]);
+        let (outcomes, raised_alerts) = check_ownership_cycles(&rpc, 2).await;
+        assert_eq!(
+            outcomes,
+            vec![CheckOutcome::Ok, CheckOutcome::Alerted(AlertType::OwnershipChanged)]
+        );
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Error);
+        let change = format!("gateway PAUSER_ROLE granted to {:#x}", new_pauser);
+        assert!(raised_alerts[0].text.contains(&change));
+        assert_eq!(raised_alerts[0].context, Some(json!({ "changes": [change] })));
+    }
+
+    #[test]
+    fn outage_is_acted_on_once_it_reaches_the_pause_time() {
+        let first_failure = SystemTime::now();
+        let after = |secs| first_failure + Duration::from_secs(secs);
+        let mut outage_start = None;
+
+        // an outage staying below the pause time
+        assert_eq!(get_outage_to_act_on(&mut outage_start, 300, first_failure), None);
+        assert_eq!(get_outage_to_act_on(&mut outage_start, 300, after(299)), None);
+        assert_eq!(outage_start, Some(first_failure));
+
+        // crossing it
+        assert_eq!(get_outage_to_act_on(&mut outage_start, 300, after(300)), Some(300));
+        assert_eq!(get_outage_to_act_on(&mut outage_start, 300, after(420)), Some(420));
+
+        // a successful check clears the outage, so the next one starts over
+        outage_start = None;
+        assert_eq!(get_outage_to_act_on(&mut outage_start, 300, after(500)), None);
+        assert_eq!(get_outage_to_act_on(&mut outage_start, 300, after(700)), None);
+        assert_eq!(get_outage_to_act_on(&mut outage_start, 300, after(800)), Some(300));
+    }
+
+    #[test]
+    fn outage_is_acted_on_right_away_without_a_pause_time() {
+        assert_eq!(get_outage_to_act_on(&mut None, 0, SystemTime::now()), Some(0));
+    }
+
+    #[test]
+    fn only_a_block_regression_past_the_limit_alerts() {
+        let mut max_block_seen = 0;
+
+        // monotonic progress (or a repeated block) never alerts
+        for latest_block in [100, 101, 101, 105] {
+            assert_eq!(get_regression_to_alert_on(&mut max_block_seen, latest_block, 2), None);
+        }
+        assert_eq!(max_block_seen, 105);
+
+        // going back within the limit is tolerated
+        assert_eq!(get_regression_to_alert_on(&mut max_block_seen, 103, 2), None);
+
+        // going back further alerts, measured from the highest block seen rather than the last one
+        assert_eq!(get_regression_to_alert_on(&mut max_block_seen, 102, 2), Some(3));
+        assert_eq!(get_regression_to_alert_on(&mut max_block_seen, 90, 2), Some(15));
+        assert_eq!(max_block_seen, 105);
+
+        // catching back up clears it
+        assert_eq!(get_regression_to_alert_on(&mut max_block_seen, 106, 2), None);
+    }
+
+    #[test]
+    fn supply_is_compared_in_ethereum_decimals() {
+        let gwei = 1_000_000_000u64;
+        let eth = U256::exp10(18);
+
+        // 5 eth released against 5 eth withdrawn (in fuel's 9 decimals) matches
+        assert_eq!(get_supply_mismatch(eth * 5, 5 * gwei, 0.0), None);
+
+        // releasing less than was withdrawn is just relaying lag
+        assert_eq!(get_supply_mismatch(eth * 2, 5 * gwei, 0.0), None);
+
+        // releasing more than was withdrawn is a mismatch, reported in 18 decimals
+        assert_eq!(
+            get_supply_mismatch(eth * 6, 5 * gwei, 0.0),
+            Some(SupplyMismatch {
+                withdrawn: eth * 5,
+                difference: eth,
+                tolerance: U256::zero(),
+            })
+        );
+    }
+
+    #[test]
+    fn supply_mismatch_within_the_tolerance_is_ignored() {
+        let gwei = 1_000_000_000u64;
+        let eth = U256::exp10(18);
+        let half_eth = eth / 2;
+        assert_eq!(get_supply_mismatch(eth * 5 + half_eth, 5 * gwei, 0.5), None);
+        assert_eq!(
+            get_supply_mismatch(eth * 5 + half_eth + 1, 5 * gwei, 0.5),
+            Some(SupplyMismatch {
+                withdrawn: eth * 5,
+                difference: half_eth + 1,
+                tolerance: half_eth,
+            })
+        );
+    }
+
+    // runs the base asset deposit check (alerting at 10 eth and warning from 8) against the given deposits in gwei
+    // (none for a failing rpc)
+    async fn check_portal_deposits(
+        deposits: Option<Vec<u64>>,
+    ) -> (CheckOutcome, Vec<RaisedAlert>, Vec<EthereumAction>) {
+        let rpc = MockRpc::start(move |method, _| match (method, &deposits) {
+            ("eth_call", _) => Some(Ok(abi_bool(false))),
+            ("eth_getLogs", Some(deposits)) => {
+                let logs: Vec<Value> = deposits
+                    .iter()
+                    .map(|amount| {
+                        json!({
+                            "address": PORTAL_CONTRACT_ADDRESS,
+                            "topics": [H256::from(keccak256("MessageSent(bytes32,bytes32,uint256,uint64,bytes)"))],
+                            "data": abi_word(*amount),
+                            "blockNumber": "0x60",
+                        })
+                    })
+                    .collect();
+                Some(Ok(json!(logs)))
+            }
+            ("eth_getLogs", None) => Some(Err(String::from("unavailable"))),
+            _ => None,
+        });
+        let portal_deposit_alert: DepositAlert = serde_json::from_value(json!({
+            "alert_level": "Error",
+            "alert_action": "PauseAll",
+            "amount": 10.0,
+            "warning_fraction": 0.8,
+        }))
+        .unwrap();
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let portal_contract = PortalContract::new(&config).await.unwrap();
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, mut requested_actions) = WatchtowerEthereumActions::collector(alerts.clone());
+        let outcome = check_base_asset_deposits(&portal_contract, &portal_deposit_alert, 100, &actions, &alerts).await;
+        (outcome, raised_alerts.take(), requested_actions.take())
+    }
+
+    #[tokio::test]
+    async fn check_outcome_matches_what_the_check_sent() {
+        let gwei = 1_000_000_000;
+
+        // over the threshold: an alert and the configured action
+        let (outcome, raised_alerts, requested_actions) = check_portal_deposits(Some(vec![6 * gwei, 5 * gwei])).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::EthereumPortalDeposit));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].alert_type, AlertType::EthereumPortalDeposit);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Error);
+        assert_eq!(requested_actions, vec![EthereumAction::PauseAll]);
+
+        // within the warning band: an alert but no action
+        let (outcome, raised_alerts, requested_actions) = check_portal_deposits(Some(vec![9 * gwei])).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::EthereumPortalDeposit));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert_eq!(requested_actions, vec![]);
+
+        // under both: nothing sent
+        let (outcome, raised_alerts, requested_actions) = check_portal_deposits(Some(vec![gwei])).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+        assert_eq!(requested_actions, vec![]);
+    }
+
+    #[tokio::test]
+    async fn failed_check_outcome_carries_the_error() {
+        let (outcome, raised_alerts, requested_actions) = check_portal_deposits(None).await;
+        match outcome {
+            CheckOutcome::Failed(error) => assert!(error.contains("unavailable")),
+            other => panic!("expected a failed check, got {other:?}"),
+        }
+
+        // a single failure already alerts (and acts) with the default consecutive failure count of 1
+        assert_eq!(raised_alerts.len(), 1);
+        assert!(raised_alerts[0].text.starts_with("Failed to check base asset deposits"));
+        assert_eq!(requested_actions, vec![EthereumAction::PauseAll]);
+    }
+
+    #[test]
+    fn no_commit_within_the_max_interval_is_a_stall() {
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.commit_stall_alert.alert_level = AlertLevel::Error;
+        watch_config.commit_stall_alert.alert_action = Some(EthereumAction::PauseState);
+        watch_config.commit_stall_alert.max_commit_interval_secs = 600;
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, mut requested_actions) = WatchtowerEthereumActions::collector(alerts.clone());
+        let ago = |secs| SystemTime::now() - Duration::from_secs(secs);
+
+        // the last commit was seen recently (or the clock went backwards past it)
+        for last_commit_time in [ago(10), ago(590), SystemTime::now() + Duration::from_secs(60)] {
+            let outcome = check_commit_stall_time(last_commit_time, &watch_config, &actions, &alerts);
+            assert_eq!(outcome, CheckOutcome::Ok);
+        }
+        assert!(raised_alerts.take().is_empty());
+
+        // nothing was committed in the whole window
+        let outcome = check_commit_stall_time(ago(660), &watch_config, &actions, &alerts);
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::EthereumCommitStalled));
+        let raised_alerts = raised_alerts.take();
+        assert_eq!(raised_alerts.len(), 1);
+        assert!(raised_alerts[0].text.contains("for over 600 seconds"));
+        assert_eq!(requested_actions.take(), vec![EthereumAction::PauseState]);
+    }
+
+    // a gateway deposit log of the given amount of a token
+    fn deposit_log(token: H160, amount: U256) -> Value {
+        let mut amount_word = [0u8; 32];
+        amount.to_big_endian(&mut amount_word);
+        json!({
+            "address": GATEWAY_CONTRACT_ADDRESS,
+            "topics": [H256::from(keccak256("Deposit(bytes32,address,bytes32,uint256)")), H256::zero(), H256::from(token)],
+            "data": format!("0x{}{:x}", "00".repeat(32), H256::from(amount_word)),
+            "blockNumber": "0x60",
+        })
+    }
+
+    // runs the discovered token deposits check over the given deposit logs
+    async fn check_discovered_deposits(
+        watch_config: &EthereumClientWatcher,
+        logs: Vec<Value>,
+    ) -> (CheckOutcome, Vec<RaisedAlert>) {
+        let rpc = MockRpc::start(move |method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            "eth_getLogs" => Some(Ok(json!(logs))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let gateway_contract = GatewayContract::new(&config).await.unwrap();
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, _) = WatchtowerEthereumActions::collector(alerts.clone());
+        let outcome = check_discovered_token_deposits(&gateway_contract, watch_config, 100, &actions, &alerts).await;
+        (outcome, raised_alerts.take())
+    }
+
+    #[tokio::test]
+    async fn ignored_tokens_do_not_alert() {
+        let noisy_token = H160::from_low_u64_be(0xaa);
+        let other_token = H160::from_low_u64_be(0xbb);
+        let amount = U256::exp10(18) * 5000;
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.discovered_token_deposit_alert.alert_level = AlertLevel::Warn;
+        // ignored in a different case and without the prefix
+        watch_config.ignored_token_addresses = vec![format!("{:X}", noisy_token)];
+        assert!(watch_config.is_token_ignored(&format!("{:#x}", noisy_token)));
+        assert!(!watch_config.is_token_ignored(&format!("{:#x}", other_token)));
+
+        let (outcome, raised_alerts) = check_discovered_deposits(
+            &watch_config,
+            vec![deposit_log(noisy_token, amount), deposit_log(other_token, amount)],
+        )
+        .await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::EthereumGatewayDeposit));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].dedup_suffix, Some(format!("{:#x}", other_token)));
+
+        // nothing is raised when only the ignored token is active
+        let (outcome, raised_alerts) =
+            check_discovered_deposits(&watch_config, vec![deposit_log(noisy_token, amount)]).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+    }
+
+    // runs the block production check against a latest block with the given offset from the local clock
+    async fn check_block_production_at(seconds_from_now: i64) -> (CheckOutcome, Vec<RaisedAlert>) {
+        let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let block = json!({
+            "number": "0x64",
+            "hash": H256::zero(),
+            "timestamp": format!("{:#x}", seconds_now + seconds_from_now),
+            "transactions": [],
+        });
+        let rpc = MockRpc::start(move |method, _| match method {
+            "eth_getBlockByNumber" => Some(Ok(block.clone())),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.block_production_alert.alert_level = AlertLevel::Warn;
+        watch_config.block_production_alert.alert_action = Some(EthereumAction::PauseAll);
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, mut requested_actions) = WatchtowerEthereumActions::collector(alerts.clone());
+
+        let outcome = check_block_production(&ethereum_chain, &watch_config, &actions, &alerts).await;
+        assert_eq!(requested_actions.take(), vec![]);
+        (outcome, raised_alerts.take())
+    }
+
+    #[tokio::test]
+    async fn block_from_the_future_is_clock_skew() {
+        let (outcome, raised_alerts) = check_block_production_at(120).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::ClockSkew));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].alert_type, AlertType::ClockSkew);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert!(raised_alerts[0].text.contains("ahead of the local clock"));
+
+        // a recent block is on time
+        let (outcome, raised_alerts) = check_block_production_at(-5).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_report_the_attempt_count() {
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_blockNumber" => Some(Err(String::from("upstream unavailable"))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.block_production_alert.alert_level = AlertLevel::Warn;
+        watch_config.connection_alert.alert_level = AlertLevel::Error;
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, _) = WatchtowerEthereumActions::collector(alerts.clone());
+
+        let outcome = check_block_production(&ethereum_chain, &watch_config, &actions, &alerts).await;
+        assert!(matches!(outcome, CheckOutcome::Failed(_)));
+        assert_eq!(
+            rpc.requests("eth_blockNumber").len() as u64,
+            ETHEREUM_CONNECTION_RETRIES
+        );
+        let raised_alerts = raised_alerts.take();
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Error);
+        assert!(
+            raised_alerts[0]
+                .text
+                .contains(&format!("(failed after {ETHEREUM_CONNECTION_RETRIES} attempts)")),
+            "{}",
+            raised_alerts[0].text
+        );
+    }
+
+    // runs the finality lag check (allowing up to 64 blocks) with the latest block at 100
+    async fn check_finality_lag_at(finalized_block: u64) -> (CheckOutcome, Vec<RaisedAlert>, Vec<EthereumAction>) {
+        let rpc = MockRpc::start(move |method, params| match (method, params[0].as_str()) {
+            ("eth_getBlockByNumber", Some("finalized")) => Some(Ok(json!({
+                "number": format!("{:#x}", finalized_block),
+                "hash": H256::zero(),
+                "timestamp": "0x0",
+                "transactions": [],
+            }))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.finality_lag_alert.alert_level = AlertLevel::Warn;
+        watch_config.finality_lag_alert.alert_action = Some(EthereumAction::PauseState);
+        watch_config.finality_lag_alert.max_finality_lag_blocks = 64;
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, mut requested_actions) = WatchtowerEthereumActions::collector(alerts.clone());
+
+        let outcome = check_finality_lag(&ethereum_chain, &watch_config, &actions, &alerts).await;
+        (outcome, raised_alerts.take(), requested_actions.take())
+    }
+
+    #[tokio::test]
+    async fn finality_lag_alerts_only_past_the_max() {
+        let (outcome, raised_alerts, requested_actions) = check_finality_lag_at(36).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+        assert_eq!(requested_actions, vec![]);
+
+        let (outcome, raised_alerts, requested_actions) = check_finality_lag_at(35).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::EthereumFinalityLag));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert!(raised_alerts[0].text.contains("Current lag: 65 blocks."));
+        assert_eq!(requested_actions, vec![EthereumAction::PauseState]);
+    }
+
+    // runs the bridge activity check with the latest block produced the given seconds ago and the given base asset
+    // deposits and withdrawals in gwei
+    async fn check_activity(
+        seconds_since_last_block: u64,
+        deposits: Vec<u64>,
+        withdrawals: Vec<u64>,
+    ) -> (CheckOutcome, Vec<RaisedAlert>, MockRpc) {
+        let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let block = json!({
+            "number": "0x64",
+            "hash": H256::zero(),
+            "timestamp": format!("{:#x}", seconds_now - seconds_since_last_block),
+            "transactions": [],
+        });
+        let message_log = |event: &str, amount: &u64| {
+            json!({
+                "address": PORTAL_CONTRACT_ADDRESS,
+                "topics": [H256::from(keccak256(event))],
+                "data": abi_word(*amount),
+                "blockNumber": "0x60",
+            })
+        };
+        let deposit_logs: Vec<Value> = deposits
+            .iter()
+            .map(|amount| message_log("MessageSent(bytes32,bytes32,uint256,uint64,bytes)", amount))
+            .collect();
+        let withdrawal_logs: Vec<Value> = withdrawals
+            .iter()
+            .map(|amount| message_log("MessageRelayed(bytes32,bytes32,bytes32,uint64)", amount))
+            .collect();
+        let deposit_topic = format!(
+            "{:#x}",
+            H256::from(keccak256("MessageSent(bytes32,bytes32,uint256,uint64,bytes)"))
+        );
+        let rpc = MockRpc::start(move |method, params| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            "eth_getBlockByNumber" => Some(Ok(block.clone())),
+            "eth_getLogs" => match params[0]["topics"][0] == deposit_topic.as_str() {
+                true => Some(Ok(json!(deposit_logs))),
+                false => Some(Ok(json!(withdrawal_logs))),
+            },
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let portal_contract = PortalContract::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.inactivity_alert.alert_level = AlertLevel::Warn;
+        watch_config.block_production_alert.max_block_time = 60;
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, _) = WatchtowerEthereumActions::collector(alerts.clone());
+        let outcome =
+            check_bridge_activity(&ethereum_chain, &portal_contract, 100, &watch_config, &actions, &alerts).await;
+        (outcome, raised_alerts.take(), rpc)
+    }
+
+    #[tokio::test]
+    async fn no_deposits_or_withdrawals_is_inactivity() {
+        let (outcome, raised_alerts, _) = check_activity(5, vec![], vec![]).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::BridgeInactive));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].alert_type, AlertType::BridgeInactive);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+
+        // either kind of activity counts
+        for (deposits, withdrawals) in [(vec![1_000_000_000], vec![]), (vec![], vec![1_000_000_000])] {
+            let (outcome, raised_alerts, _) = check_activity(5, deposits, withdrawals).await;
+            assert_eq!(outcome, CheckOutcome::Ok);
+            assert!(raised_alerts.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn stalled_chain_is_not_inactivity() {
+        let (outcome, raised_alerts, rpc) = check_activity(600, vec![], vec![]).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+        assert!(rpc.requests("eth_getLogs").is_empty());
+    }
+
+    #[tokio::test]
+    async fn configured_name_differing_from_the_symbol_is_a_warning() {
+        let symbol = format!(
+            "0x{}",
+            ethers::utils::hex::encode(ethers::abi::encode(&[Token::String(String::from("USDC"))]))
+        );
+        let rpc = MockRpc::start(move |method, params| match method {
+            "eth_call" if call_selector(params) == selector("symbol()") => Some(Ok(json!(symbol))),
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let gateway_contract = GatewayContract::new(&config).await.unwrap();
+        let token_address = format!("{:#x}", H160::from_low_u64_be(0xaa));
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+
+        let token_name = get_token_name(
+            &gateway_contract,
+            &Some(String::from("DAI")),
+            &token_address,
+            true,
+            &alerts,
+        )
+        .await;
+        assert_eq!(token_name, "DAI");
+        let raised_alerts = raised_alerts.take();
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].alert_type, AlertType::TokenNameMismatch);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert!(raised_alerts[0]
+            .text
+            .contains("name DAI does not match the on-chain symbol USDC"));
+        assert_eq!(raised_alerts[0].dedup_suffix, Some(token_address.clone()));
+
+        // a name matching apart from case is fine, no name falls back to the symbol (which is cached by then)
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let token_name = get_token_name(
+            &gateway_contract,
+            &Some(String::from("usdc")),
+            &token_address,
+            true,
+            &alerts,
+        )
+        .await;
+        assert_eq!(token_name, "usdc");
+        assert_eq!(
+            get_token_name(&gateway_contract, &None, &token_address, false, &alerts).await,
+            "USDC"
+        );
+        assert!(raised_alerts.take().is_empty());
+        let symbol_calls = rpc
+            .requests("eth_call")
+            .iter()
+            .filter(|params| call_selector(params) == selector("symbol()"))
+            .count();
+        assert_eq!(symbol_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn unconfigured_tokens_get_the_default_threshold() {
+        let configured_token = H160::from_low_u64_be(0xaa);
+        let discovered_token = H160::from_low_u64_be(0xbb);
+        let eth = U256::exp10(18);
+        let mut watch_config = EthereumClientWatcher {
+            auto_discover_tokens: true,
+            ..EthereumClientWatcher::default()
+        };
+        watch_config.discovered_token_deposit_alert.alert_level = AlertLevel::Warn;
+        watch_config.gateway_deposit_alerts = vec![serde_json::from_value(json!({
+            "alert_level": "Warn",
+            "token_address": format!("{:#x}", configured_token),
+        }))
+        .unwrap()];
+        assert_eq!(watch_config.discovered_token_deposit_alert.amount, 1000.0);
+
+        // deposits of 600 and 500 put the discovered token over the default 1000, the configured one is left alone
+        let (outcome, raised_alerts) = check_discovered_deposits(
+            &watch_config,
+            vec![
+                deposit_log(configured_token, eth * 5000),
+                deposit_log(discovered_token, eth * 600),
+                deposit_log(discovered_token, eth * 500),
+            ],
+        )
+        .await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::EthereumGatewayDeposit));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert_eq!(raised_alerts[0].dedup_suffix, Some(format!("{:#x}", discovered_token)));
+        assert!(raised_alerts[0]
+            .text
+            .contains(&format!("Amount deposited: {}", eth * 1100)));
+
+        // under the default threshold
+        let (outcome, raised_alerts) =
+            check_discovered_deposits(&watch_config, vec![deposit_log(discovered_token, eth * 900)]).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+    }
+
+    // runs the wallet activity check for two cycles, the account sending 2 transactions in block 101 between them
+    // (the given ones are found in the block, and only the ones marked as ours were sent by the watchtower)
+    async fn check_wallet_cycles(block_txs: Vec<(H256, bool)>) -> (CheckOutcome, Vec<RaisedAlert>) {
+        let account = H160::from_low_u64_be(0xaa);
+        let transactions: Vec<ethers::types::Transaction> = block_txs
+            .iter()
+            .map(|(hash, _)| ethers::types::Transaction {
+                hash: *hash,
+                from: account,
+                block_number: Some(101.into()),
+                ..Default::default()
+            })
+            .collect();
+        let block = serde_json::to_value(ethers::types::Block {
+            number: Some(101.into()),
+            hash: Some(H256::zero()),
+            transactions,
+            ..Default::default()
+        })
+        .unwrap();
+        let cycle = Arc::new(Mutex::new(0u64));
+        let rpc_cycle = cycle.clone();
+        let rpc = MockRpc::start(move |method, _| {
+            let cycle = *rpc_cycle.lock().unwrap();
+            match method {
+                "eth_blockNumber" => Some(Ok(json!(format!("{:#x}", 100 + cycle)))),
+                "eth_getTransactionCount" => Some(Ok(json!(format!("{:#x}", 5 + cycle * 2)))),
+                "eth_getBlockByNumber" => Some(Ok(block.clone())),
+                _ => None,
+            }
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.unexpected_wallet_activity_alert.alert_level = AlertLevel::Error;
+        let sent_transactions: SentTransactions = Arc::new(Mutex::new(
+            block_txs
+                .iter()
+                .filter(|(_, ours)| *ours)
+                .map(|(hash, _)| *hash)
+                .collect(),
+        ));
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, _) = WatchtowerEthereumActions::collector(alerts.clone());
+        let account_address = format!("{:#x}", account);
+        let mut last_wallet_nonce = None;
+
+        let mut outcome = CheckOutcome::Ok;
+        for _ in 0..2 {
+            outcome = check_wallet_activity(
+                &ethereum_chain,
+                &account_address,
+                &mut last_wallet_nonce,
+                &sent_transactions,
+                &watch_config,
+                &actions,
+                &alerts,
+            )
+            .await;
+            *cycle.lock().unwrap() += 1;
+        }
+        (outcome, raised_alerts.take())
+    }
+
+    #[tokio::test]
+    async fn transaction_the_watchtower_did_not_send_is_unexpected() {
+        let pause_tx = H256::from_low_u64_be(1);
+        let external_tx = H256::from_low_u64_be(2);
+        let (outcome, raised_alerts) = check_wallet_cycles(vec![(pause_tx, true), (external_tx, false)]).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::UnexpectedWalletActivity));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Error);
+        assert!(raised_alerts[0]
+            .text
+            .contains(&format!("did not initiate: {:#x}.", external_tx)));
+
+        // our own transactions are expected
+        let other_pause_tx = H256::from_low_u64_be(3);
+        let (outcome, raised_alerts) = check_wallet_cycles(vec![(pause_tx, true), (other_pause_tx, true)]).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+    }
+
+    // runs the account balance check once for each of the given balances in eth (allowing 1 to 100 eth and a
+    // change of 10 eth between checks), getting the outcome and alerts of each
+    async fn check_balances(balances: Vec<u64>) -> Vec<(CheckOutcome, Vec<RaisedAlert>)> {
+        let check = Arc::new(Mutex::new(0usize));
+        let rpc_check = check.clone();
+        let rpc_balances = balances.clone();
+        let rpc = MockRpc::start(move |method, _| match method {
+            "eth_getBalance" => {
+                let balance = U256::exp10(18) * rpc_balances[*rpc_check.lock().unwrap()];
+                Some(Ok(json!(balance)))
+            }
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.account_funds_alert.alert_level = AlertLevel::Warn;
+        watch_config.account_funds_alert.min_balance = 1.0;
+        watch_config.account_funds_alert.max_balance = Some(100.0);
+        watch_config.account_funds_alert.max_balance_change = Some(10.0);
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, _) = WatchtowerEthereumActions::collector(alerts.clone());
+        let account_address = format!("{:#x}", H160::from_low_u64_be(0xaa));
+        let mut balance_samples = VecDeque::new();
+        let mut last_balance = None;
+
+        let mut results = vec![];
+        for i in 0..balances.len() {
+            *check.lock().unwrap() = i;
+            let outcome = check_account_balance(
+                &ethereum_chain,
+                &account_address,
+                &mut balance_samples,
+                &mut last_balance,
+                &watch_config,
+                &actions,
+                &alerts,
+            )
+            .await;
+            results.push((outcome, raised_alerts.take()));
+        }
+        results
+    }
+
+    #[tokio::test]
+    async fn balance_over_the_max_alerts() {
+        let results = check_balances(vec![100, 101]).await;
+        assert_eq!(results[0].0, CheckOutcome::Ok);
+        assert!(results[0].1.is_empty());
+        assert_eq!(results[1].0, CheckOutcome::Alerted(AlertType::EthereumAccountFundsHigh));
+        assert_eq!(results[1].1.len(), 1);
+        assert_eq!(results[1].1[0].alert_type, AlertType::EthereumAccountFundsHigh);
+        assert_eq!(results[1].1[0].level, AlertLevel::Warn);
+        assert!(results[1].1[0].text.contains("holds more funds than expected"));
+    }
+
+    #[tokio::test]
+    async fn balance_swinging_past_the_max_change_alerts() {
+        // the first check has nothing to compare against, after that a change of 10 is allowed but 11 isn't
+        let results = check_balances(vec![50, 60, 49, 60]).await;
+        let outcomes: Vec<CheckOutcome> = results.iter().map(|(outcome, _)| outcome.clone()).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                CheckOutcome::Ok,
+                CheckOutcome::Ok,
+                CheckOutcome::Alerted(AlertType::EthereumAccountFundsChange),
+                CheckOutcome::Alerted(AlertType::EthereumAccountFundsChange),
+            ]
+        );
+        let change_alert = &results[2].1[0];
+        assert_eq!(change_alert.alert_type, AlertType::EthereumAccountFundsChange);
+        assert_eq!(
+            change_alert.context.as_ref().unwrap()["change"],
+            (U256::exp10(18) * 11u64).to_string()
+        );
+    }
+
+    // runs the gas price check (allowing up to 100 gwei) at the given gas price in gwei
+    async fn check_gas_price_at(gas_price_gwei: u64) -> (CheckOutcome, Vec<RaisedAlert>) {
+        let rpc = MockRpc::start(move |method, _| match method {
+            "eth_gasPrice" => Some(Ok(json!(U256::exp10(9) * gas_price_gwei))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.gas_price_alert.alert_level = AlertLevel::Warn;
+        watch_config.gas_price_alert.max_gas_price_gwei = 100.0;
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, _) = WatchtowerEthereumActions::collector(alerts.clone());
+        let outcome = check_gas_price(&ethereum_chain, &watch_config, &actions, &alerts).await;
+        (outcome, raised_alerts.take())
+    }
+
+    #[tokio::test]
+    async fn gas_price_alerts_only_above_the_max() {
+        let (outcome, raised_alerts) = check_gas_price_at(100).await;
+        assert_eq!(outcome, CheckOutcome::Ok);
+        assert!(raised_alerts.is_empty());
+
+        let (outcome, raised_alerts) = check_gas_price_at(101).await;
+        assert_eq!(outcome, CheckOutcome::Alerted(AlertType::EthereumGasSpike));
+        assert_eq!(raised_alerts.len(), 1);
+        assert_eq!(raised_alerts[0].level, AlertLevel::Warn);
+        assert!(raised_alerts[0].text.contains("Current gas price: 101.000000000 gwei."));
+        assert_eq!(
+            raised_alerts[0].context.as_ref().unwrap()["threshold"],
+            (U256::exp10(9) * 100u64).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn commit_check_range_alerts_on_repeated_errors_and_is_capped() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+
+        // the latest block the rpc reports (failing while it is zero)
+        let latest_block = Arc::new(AtomicU64::new(0));
+        let rpc_latest_block = latest_block.clone();
+        let rpc = MockRpc::start(
+            move |method, _| match (method, rpc_latest_block.load(Ordering::SeqCst)) {
+                ("eth_blockNumber", 0) => Some(Err(String::from("upstream unavailable"))),
+                ("eth_blockNumber", block) => Some(Ok(json!(format!("{:#x}", block)))),
+                _ => None,
+            },
+        );
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let mut watch_config = EthereumClientWatcher::default();
+        watch_config.invalid_state_commit_alert.alert_level = AlertLevel::Error;
+        watch_config.invalid_state_commit_alert.alert_action = Some(EthereumAction::PauseState);
+        watch_config
+            .invalid_state_commit_alert
+            .details
+            .consecutive_failures_to_alert = 3;
+        let (alerts, mut raised_alerts) = WatchtowerAlerts::collector();
+        let (actions, mut requested_actions) = WatchtowerEthereumActions::collector(alerts.clone());
+
+        // the first failures are tolerated, then every further one alerts
+        for _ in 0..2 {
+            assert_eq!(
+                get_commit_check_range(&ethereum_chain, 1000, &watch_config, &actions, &alerts).await,
+                None
+            );
+        }
+        assert!(raised_alerts.take().is_empty());
+        assert_eq!(
+            get_commit_check_range(&ethereum_chain, 1000, &watch_config, &actions, &alerts).await,
+            None
+        );
+        let raised = raised_alerts.take();
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].alert_type, AlertType::EthereumInvalidStateCommit);
+        assert_eq!(raised[0].level, AlertLevel::Error);
+        assert!(raised[0]
+            .text
+            .contains("State commit check has not advanced past block 1000"));
+        assert_eq!(requested_actions.take(), vec![EthereumAction::PauseState]);
+
+        // once the rpc recovers the scan resumes from the last checked block
+        latest_block.store(1500, Ordering::SeqCst);
+        assert_eq!(
+            get_commit_check_range(&ethereum_chain, 1000, &watch_config, &actions, &alerts).await,
+            Some((1000, 1500))
+        );
+
+        // but never reaches back more than the max scan window
+        let far_ahead = 1000 + MAX_COMMIT_SCAN_BLOCKS + 500;
+        latest_block.store(far_ahead, Ordering::SeqCst);
+        assert_eq!(
+            get_commit_check_range(&ethereum_chain, 1000, &watch_config, &actions, &alerts).await,
+            Some((far_ahead - MAX_COMMIT_SCAN_BLOCKS, far_ahead))
+        );
+        assert!(raised_alerts.take().is_empty());
+    }
 }
@@ -1,32 +1,85 @@
 use super::ETHEREUM_CONNECTION_RETRIES;
+use crate::error::{Result, WatchtowerError};
+use crate::rpc_limit::{LimitedHttp, RpcLimiter};
 use crate::WatchtowerConfig;
 
-use anyhow::Result;
 use ethers::prelude::k256::ecdsa::SigningKey;
-use ethers::providers::{Http, Middleware, Provider};
+use ethers::providers::{Middleware, Provider, StreamExt, Ws};
 use ethers::signers::{Signer, Wallet};
-use ethers::types::Address;
+use ethers::types::{Address, BlockNumber, Filter, H256};
 use ethers::utils::hex::ToHex;
-use std::ops::Mul;
+use std::cmp::min;
+use std::collections::VecDeque;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub use ethers::types::U256;
 
 #[derive(Clone, Debug)]
 pub struct EthereumChain {
-    provider: Provider<Http>,
+    provider: Provider<LimitedHttp>,
+    latest_block_timestamp: Option<Arc<AtomicU64>>,
 }
 
 impl EthereumChain {
     pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
         // setup provider and check that it is valid
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
+        let provider = config.ethereum_http_provider()?;
         let provider_result = provider.get_chainid().await;
         match provider_result {
-            Err(e) => Err(anyhow::anyhow!("Invalid ethereum RPC: {e}")),
-            Ok(_) => Ok(EthereumChain { provider }),
+            Err(e) => return Err(e.into()),
+            Ok(chain_id) => match config.expected_ethereum_chain_id {
+                // pointing at the wrong network (e.g. a testnet rpc for mainnet) must not go unnoticed
+                Some(expected) if chain_id.as_u64() != expected => {
+                    return Err(WatchtowerError::WrongChain(format!(
+                        "ethereum rpc is on chain {chain_id} but expected_ethereum_chain_id is {expected}"
+                    )));
+                }
+                _ => {}
+            },
+        }
+
+        // optionally track new blocks through a websocket subscription instead of polling
+        let latest_block_timestamp = match &config.ethereum_ws_rpc {
+            Some(ws_rpc) => Some(
+                EthereumChain::subscribe_block_timestamps(ws_rpc, RpcLimiter::shared(config.max_concurrent_rpc))
+                    .await?,
+            ),
+            None => None,
+        };
+
+        Ok(EthereumChain {
+            provider,
+            latest_block_timestamp,
+        })
+    }
+
+    // spawns a thread that records the timestamp of each new block head (reset to zero if the subscription ends)
+    // (connecting and subscribing wait for an rpc permit, the pushed block heads don't need one)
+    async fn subscribe_block_timestamps(ws_rpc: &str, limiter: RpcLimiter) -> Result<Arc<AtomicU64>> {
+        if !ws_rpc.starts_with("ws://") && !ws_rpc.starts_with("wss://") {
+            return Err(WatchtowerError::Config(format!(
+                "Invalid ethereum websocket RPC: {ws_rpc}"
+            )));
         }
+        let ws_provider = limiter.run(Provider::<Ws>::connect(ws_rpc)).await?;
+        let latest_block_timestamp = Arc::new(AtomicU64::new(0));
+        let thread_timestamp = latest_block_timestamp.clone();
+        tokio::spawn(async move {
+            match limiter.run(ws_provider.subscribe_blocks()).await {
+                Ok(mut stream) => {
+                    while let Some(block) = stream.next().await {
+                        thread_timestamp.store(block.timestamp.as_u64(), Ordering::Relaxed);
+                    }
+                    log::warn!("Ethereum new block subscription ended. Falling back to polling.");
+                }
+                Err(e) => log::warn!("Failed to subscribe to ethereum new blocks: {e}. Falling back to polling."),
+            }
+            thread_timestamp.store(0, Ordering::Relaxed);
+        });
+        Ok(latest_block_timestamp)
     }
 
     pub async fn check_connection(&self) -> Result<()> {
@@ -35,7 +88,10 @@ impl EthereumChain {
                 Ok(_) => return Ok(()),
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -44,6 +100,19 @@ impl EthereumChain {
     }
 
     pub async fn get_seconds_since_last_block(&self) -> Result<u32> {
+        // use the block timestamp pushed by the websocket subscription if one is active
+        let pushed_timestamp = match &self.latest_block_timestamp {
+            Some(timestamp) => timestamp.load(Ordering::Relaxed),
+            None => 0,
+        };
+        if pushed_timestamp > 0 {
+            let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            if pushed_timestamp > seconds_now {
+                return Err(WatchtowerError::ClockSkew(pushed_timestamp - seconds_now));
+            }
+            return Ok((seconds_now - pushed_timestamp) as u32);
+        }
+
         let block_num = self.get_latest_block_number().await?;
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_block(block_num).await {
@@ -56,15 +125,18 @@ impl EthereumChain {
                             if millis_now >= last_block_timestamp {
                                 Ok((millis_now - last_block_timestamp) as u32)
                             } else {
-                                Err(anyhow::anyhow!("Block time is ahead of current time"))
+                                Err(WatchtowerError::ClockSkew(last_block_timestamp - millis_now))
                             }
                         }
-                        None => Err(anyhow::anyhow!("Failed to get latest block")),
+                        None => Err(WatchtowerError::ChainData(String::from("Failed to get latest block"))),
                     }
                 }
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -78,7 +150,10 @@ impl EthereumChain {
                 Ok(num) => return Ok(num.as_u64()),
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -86,13 +161,95 @@ impl EthereumChain {
         Ok(0)
     }
 
+    // checks the provider returns logs for a block whose bloom filter says it has some (an empty response then means
+    // the provider's log index is lagging rather than there being no activity)
+    pub async fn are_block_logs_indexed(&self, block_num: u64) -> Result<bool> {
+        let filter = Filter::new().from_block(block_num).to_block(block_num);
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            let logs_result = match self.provider.get_block(block_num).await {
+                Ok(Some(block)) => match block.logs_bloom {
+                    // nothing to compare against when the block has no logs
+                    Some(bloom) if bloom.is_zero() => return Ok(true),
+                    _ => self.provider.get_logs(&filter).await,
+                },
+                Ok(None) => {
+                    return Err(WatchtowerError::ChainData(format!(
+                        "Ethereum provider has no block {block_num}"
+                    )))
+                }
+                Err(e) => Err(e),
+            };
+            match logs_result {
+                Ok(logs) => return Ok(!logs.is_empty()),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    // gets the number of blocks the latest block is ahead of the latest finalized block
+    pub async fn get_finality_lag(&self) -> Result<u64> {
+        let latest_block = self.get_latest_block_number().await?;
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_block(BlockNumber::Finalized).await {
+                Ok(block_result) => {
+                    return match block_result.and_then(|block| block.number) {
+                        Some(finalized_block) => Ok(latest_block.saturating_sub(finalized_block.as_u64())),
+                        None => Err(WatchtowerError::ChainData(String::from(
+                            "Failed to get latest finalized block",
+                        ))),
+                    }
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    // gets the current gas price in wei
+    pub async fn get_gas_price(&self) -> Result<U256> {
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_gas_price().await {
+                Ok(gas_price) => return Ok(gas_price),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(U256::zero())
+    }
+
     pub async fn get_account_balance(&self, addr: &str) -> Result<U256> {
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
-            match self.provider.get_balance(Address::from_str(addr)?, None).await {
+            let address =
+                Address::from_str(addr).map_err(|e| WatchtowerError::InvalidAddress(format!("{addr}: {e}")))?;
+            match self.provider.get_balance(address, None).await {
                 Ok(balance) => return Ok(balance),
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -100,18 +257,215 @@ impl EthereumChain {
         Ok(U256::zero())
     }
 
+    // gets the number of transactions sent by the account (its nonce)
+    pub async fn get_transaction_count(&self, addr: &str) -> Result<u64> {
+        let address = Address::from_str(addr).map_err(|e| WatchtowerError::InvalidAddress(format!("{addr}: {e}")))?;
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_transaction_count(address, None).await {
+                Ok(count) => return Ok(count.as_u64()),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    // gets the hashes of the transactions sent by the account within the given blocks (inclusive)
+    pub async fn get_transactions_from(&self, addr: &str, start_block: u64, end_block: u64) -> Result<Vec<H256>> {
+        let address = Address::from_str(addr).map_err(|e| WatchtowerError::InvalidAddress(format!("{addr}: {e}")))?;
+        let mut tx_hashes = vec![];
+        for block_num in start_block..=end_block {
+            for i in 0..ETHEREUM_CONNECTION_RETRIES {
+                match self.provider.get_block_with_txs(block_num).await {
+                    Ok(block) => {
+                        match block {
+                            Some(block) => tx_hashes.extend(
+                                block
+                                    .transactions
+                                    .iter()
+                                    .filter(|tx| tx.from == address)
+                                    .map(|tx| tx.hash),
+                            ),
+                            None => {}
+                        }
+                        break;
+                    }
+                    Err(e) => {
+                        if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                            return Err(WatchtowerError::retries_exhausted(
+                                e.into(),
+                                ETHEREUM_CONNECTION_RETRIES,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tx_hashes)
+    }
+
     pub async fn get_public_address(key_str: &str) -> Result<String> {
-        let wallet: Wallet<SigningKey> = key_str.parse::<Wallet<SigningKey>>()?;
+        let wallet: Wallet<SigningKey> = key_str
+            .parse::<Wallet<SigningKey>>()
+            .map_err(|e| WatchtowerError::Wallet(e.to_string()))?;
         Ok(wallet.address().encode_hex())
     }
 
-    pub fn get_value(value_fp: f64, decimals: u8) -> U256 {
-        let decimals_p1 = if decimals < 9 { decimals } else { decimals - 9 };
-        let decimals_p2 = decimals - decimals_p1;
+    // gets the absolute net flow between deposits and withdrawals along with its direction
+    pub fn get_net_flow(deposited: U256, withdrawn: U256) -> (U256, &'static str) {
+        if deposited >= withdrawn {
+            (deposited - withdrawn, "deposits exceed withdrawals")
+        } else {
+            (withdrawn - deposited, "withdrawals exceed deposits")
+        }
+    }
+
+    // projects the seconds until the balance runs out using the burn rate across (timestamp, balance) samples
+    pub fn get_seconds_until_depleted(samples: &VecDeque<(u64, U256)>) -> Option<u64> {
+        let (oldest_time, oldest_balance) = samples.front()?;
+        let (newest_time, newest_balance) = samples.back()?;
+        if newest_time <= oldest_time || newest_balance >= oldest_balance {
+            return None;
+        }
+
+        let elapsed = U256::from(newest_time - oldest_time);
+        let burned = *oldest_balance - *newest_balance;
+        let seconds_left = newest_balance.saturating_mul(elapsed) / burned;
+        Some(min(seconds_left, U256::from(u64::MAX)).as_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_config_builder, MockRpc, MockWs};
+    use ethers::types::{Bloom, H160};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn new_heads_update_the_block_timestamp() {
+        let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let ws = MockWs::start(vec![seconds_now - 30, seconds_now - 5]);
+        let rpc = MockRpc::start(|_, _| None);
+        let config = test_config_builder(&rpc.url).ethereum_ws_rpc(&ws.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+
+        // wait for the last pushed head to be recorded
+        let latest_block_timestamp = ethereum_chain.latest_block_timestamp.clone().unwrap();
+        for _ in 0..100 {
+            if latest_block_timestamp.load(Ordering::Relaxed) == seconds_now - 5 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(latest_block_timestamp.load(Ordering::Relaxed), seconds_now - 5);
+
+        // the pushed head is used instead of polling for the latest block
+        let seconds_since_last_block = ethereum_chain.get_seconds_since_last_block().await.unwrap();
+        assert!((5..=10).contains(&seconds_since_last_block));
+        assert!(rpc.requests("eth_blockNumber").is_empty());
+        assert!(rpc.requests("eth_getBlockByNumber").is_empty());
+    }
+
+    #[test]
+    fn falling_balance_is_projected_to_zero() {
+        let eth = U256::exp10(18);
+        let samples = VecDeque::from(vec![(1_000, eth * 100), (1_600, eth * 90), (2_200, eth * 80)]);
+
+        // 20 eth burned over 1200 seconds leaves 80 eth for another 4800 seconds
+        assert_eq!(EthereumChain::get_seconds_until_depleted(&samples), Some(4_800));
+
+        // only the overall trend counts, not a dip in between
+        let samples = VecDeque::from(vec![(1_000, eth * 100), (1_600, eth * 70), (2_200, eth * 80)]);
+        assert_eq!(EthereumChain::get_seconds_until_depleted(&samples), Some(4_800));
+    }
+
+    #[test]
+    fn steady_or_rising_balance_is_never_depleted() {
+        let eth = U256::exp10(18);
+        let depleted = |samples: Vec<(u64, U256)>| EthereumChain::get_seconds_until_depleted(&VecDeque::from(samples));
+        assert_eq!(depleted(vec![]), None);
+        assert_eq!(depleted(vec![(1_000, eth)]), None);
+        assert_eq!(depleted(vec![(1_000, eth), (2_000, eth)]), None);
+        assert_eq!(depleted(vec![(1_000, eth), (2_000, eth * 2)]), None);
+    }
+
+    #[test]
+    fn net_flow_reports_the_imbalance_either_way() {
+        assert_eq!(
+            EthereumChain::get_net_flow(U256::from(500), U256::from(200)),
+            (U256::from(300), "deposits exceed withdrawals")
+        );
+        assert_eq!(
+            EthereumChain::get_net_flow(U256::from(200), U256::from(500)),
+            (U256::from(300), "withdrawals exceed deposits")
+        );
+        assert_eq!(
+            EthereumChain::get_net_flow(U256::from(200), U256::from(200)),
+            (U256::zero(), "deposits exceed withdrawals")
+        );
+    }
+
+    #[tokio::test]
+    async fn ethereum_chain_id_must_match_the_expected_one() {
+        // the mock rpc is on chain 1
+        let rpc = MockRpc::start(|_, _| None);
+        let mut config = test_config_builder(&rpc.url).build().unwrap();
+        config.expected_ethereum_chain_id = Some(1);
+        assert!(EthereumChain::new(&config).await.is_ok());
+        config.expected_ethereum_chain_id = None;
+        assert!(EthereumChain::new(&config).await.is_ok());
+
+        config.expected_ethereum_chain_id = Some(5);
+        match EthereumChain::new(&config).await {
+            Err(WatchtowerError::WrongChain(message)) => assert_eq!(
+                message,
+                "ethereum rpc is on chain 1 but expected_ethereum_chain_id is 5"
+            ),
+            Err(e) => panic!("expected a wrong chain error, got {e:?}"),
+            Ok(_) => panic!("expected a wrong chain error"),
+        }
+    }
+
+    // checks whether block 100 has its logs indexed when its logs bloom is the given byte repeated and the
+    // provider returns the given logs (also giving how many logs queries were made)
+    async fn are_logs_indexed(bloom_byte: u8, logs: Vec<Value>) -> (bool, usize) {
+        let rpc = MockRpc::start(move |method, _| match method {
+            "eth_getBlockByNumber" => Some(Ok(json!({
+                "number": "0x64",
+                "hash": H256::zero(),
+                "timestamp": "0x0",
+                "transactions": [],
+                "logsBloom": Bloom::from([bloom_byte; 256]),
+            }))),
+            "eth_getLogs" => Some(Ok(Value::from(logs.clone()))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let ethereum_chain = EthereumChain::new(&config).await.unwrap();
+        let indexed = ethereum_chain.are_block_logs_indexed(100).await.unwrap();
+        (indexed, rpc.requests("eth_getLogs").len())
+    }
+
+    #[tokio::test]
+    async fn empty_logs_are_only_suspicious_when_the_block_has_logs() {
+        // a block without logs is expected to come back empty (and isn't even queried)
+        assert_eq!(are_logs_indexed(0, vec![]).await, (true, 0));
 
-        let value = value_fp * (10.0 as f64).powf(decimals_p1 as f64);
-        let value = U256::from(value as u64);
-        let value = value.mul((10 as u64).pow(decimals_p2 as u32));
-        value
+        // a block whose bloom says it has logs should not
+        assert_eq!(are_logs_indexed(0xff, vec![]).await, (false, 1));
+        let log = json!({
+            "address": H160::zero(),
+            "topics": [H256::zero()],
+            "data": "0x",
+        });
+        assert_eq!(are_logs_indexed(0xff, vec![log]).await, (true, 1));
     }
 }
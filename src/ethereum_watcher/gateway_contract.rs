@@ -1,32 +1,44 @@
-use super::{ETHEREUM_BLOCK_TIME, ETHEREUM_CONNECTION_RETRIES};
+use super::{
+    get_start_block, parse_role_logs, RoleChange, SentTransactions, CONTRACT_CACHE_TTL, ETHEREUM_CONNECTION_RETRIES,
+    ROLE_EVENTS,
+};
+use crate::error::{Result, WatchtowerError};
+use crate::rpc_limit::LimitedHttp;
 use crate::WatchtowerConfig;
 
-use anyhow::Result;
 use ethers::abi::Address;
 use ethers::prelude::k256::ecdsa::SigningKey;
 use ethers::prelude::{abigen, SignerMiddleware};
-use ethers::providers::{Http, Middleware, Provider};
+use ethers::providers::{Middleware, Provider};
 use ethers::signers::{Signer, Wallet};
-use ethers::types::{Filter, H160, H256, U256};
-use std::cmp::max;
-use std::convert::TryFrom;
+use ethers::types::{Filter, H160, H256, U256, U64};
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 abigen!(FuelERC20Gateway, "./abi/FuelERC20Gateway.json");
+abigen!(ERC20, r#"[function symbol() external view returns (string)]"#);
+// per token pausing isn't in the deployed gateway abi, so support for it is checked before use
+abigen!(TokenPausableGateway, r#"[function pauseToken(address token) external]"#);
 
 #[derive(Clone, Debug)]
 pub struct GatewayContract {
-    provider: Provider<Http>,
-    contract: FuelERC20Gateway<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+    provider: Provider<LimitedHttp>,
+    contract: FuelERC20Gateway<SignerMiddleware<Provider<LimitedHttp>, Wallet<SigningKey>>>,
+    token_pause_contract: TokenPausableGateway<SignerMiddleware<Provider<LimitedHttp>, Wallet<SigningKey>>>,
+    secondary_contract: Option<FuelERC20Gateway<Provider<LimitedHttp>>>,
     address: H160,
     read_only: bool,
+    paused_cache: Arc<Mutex<Option<(SystemTime, bool)>>>,
+    token_symbols: Arc<Mutex<HashMap<H160, String>>>,
+    pause_confirmations: usize,
 }
 
 impl GatewayContract {
     pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
         // setup provider
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
+        let provider = config.ethereum_http_provider()?;
         let chain_id = provider.get_chainid().await?.as_u64();
 
         // setup wallet
@@ -34,26 +46,44 @@ impl GatewayContract {
         let key_str = match &config.ethereum_wallet_key {
             Some(key) => key.clone(),
             None => {
+                if config.require_wallet {
+                    return Err(WatchtowerError::WalletNotConfigured);
+                }
                 read_only = true;
                 String::from("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
             }
         };
-        let wallet: Wallet<SigningKey> = key_str.parse::<Wallet<SigningKey>>()?.with_chain_id(chain_id);
+        let wallet: Wallet<SigningKey> = key_str
+            .parse::<Wallet<SigningKey>>()
+            .map_err(|e| WatchtowerError::Wallet(e.to_string()))?
+            .with_chain_id(chain_id);
 
         // setup contract
-        let address = Address::from_str(&config.gateway_contract_address)?;
-        let client = SignerMiddleware::new(provider.clone(), wallet);
-        let contract = FuelERC20Gateway::new(address, Arc::new(client));
+        let address = Address::from_str(&config.gateway_contract_address)
+            .map_err(|e| WatchtowerError::InvalidAddress(format!("{}: {e}", config.gateway_contract_address)))?;
+        let client = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
+        let contract = FuelERC20Gateway::new(address, client.clone());
+        let token_pause_contract = TokenPausableGateway::new(address, client);
+        let secondary_contract = config
+            .ethereum_secondary_http_provider()?
+            .map(|secondary_provider| FuelERC20Gateway::new(address, Arc::new(secondary_provider)));
 
         // verify contract setup is valid
         let contract_result = contract.paused().call().await;
         match contract_result {
-            Err(_) => Err(anyhow::anyhow!("Invalid gateway contract.")),
+            Err(e) => Err(WatchtowerError::ContractNotInitialized(format!(
+                "Invalid gateway contract: {e}"
+            ))),
             Ok(_) => Ok(GatewayContract {
                 provider,
                 contract,
+                token_pause_contract,
+                secondary_contract,
                 address,
                 read_only,
+                paused_cache: Arc::new(Mutex::new(None)),
+                token_symbols: Arc::new(Mutex::new(HashMap::new())),
+                pause_confirmations: config.pause_confirmations,
             }),
         }
     }
@@ -64,11 +94,10 @@ impl GatewayContract {
         token_address: &str,
         latest_block_num: u64,
     ) -> Result<U256> {
-        let block_offset = timeframe as u64 / ETHEREUM_BLOCK_TIME;
-        let start_block = max(latest_block_num, block_offset) - block_offset;
+        let start_block = get_start_block(timeframe, latest_block_num);
         let token_address = match token_address.parse::<H160>() {
             Ok(addr) => addr,
-            Err(e) => return Err(anyhow::anyhow!("{e}")),
+            Err(e) => return Err(WatchtowerError::InvalidAddress(format!("{token_address}: {e}"))),
         };
 
         //Deposit(bytes32 indexed sender, address indexed tokenId, bytes32 fuelTokenId, uint256 amount)
@@ -77,7 +106,8 @@ impl GatewayContract {
             .address(self.address)
             .event("Deposit(bytes32,address,bytes32,uint256)")
             .topic2(token_topics)
-            .from_block(start_block);
+            .from_block(start_block)
+            .to_block(latest_block_num);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
                 Ok(logs) => {
@@ -90,7 +120,10 @@ impl GatewayContract {
                 }
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -105,11 +138,10 @@ impl GatewayContract {
         token_address: &str,
         latest_block_num: u64,
     ) -> Result<U256> {
-        let block_offset = timeframe as u64 / ETHEREUM_BLOCK_TIME;
-        let start_block = max(latest_block_num, block_offset) - block_offset;
+        let start_block = get_start_block(timeframe, latest_block_num);
         let token_address = match token_address.parse::<H160>() {
             Ok(addr) => addr,
-            Err(e) => return Err(anyhow::anyhow!("{e}")),
+            Err(e) => return Err(WatchtowerError::InvalidAddress(format!("{token_address}: {e}"))),
         };
 
         //Withdrawal(bytes32 indexed recipient, address indexed tokenId, bytes32 fuelTokenId, uint256 amount)
@@ -118,7 +150,8 @@ impl GatewayContract {
             .address(self.address)
             .event("Withdrawal(bytes32,address,bytes32,uint256)")
             .topic2(token_topics)
-            .from_block(start_block);
+            .from_block(start_block)
+            .to_block(latest_block_num);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
                 Ok(logs) => {
@@ -131,7 +164,10 @@ impl GatewayContract {
                 }
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -140,16 +176,317 @@ impl GatewayContract {
         Ok(U256::zero())
     }
 
-    pub async fn pause(&self) -> Result<()> {
+    // gets the total amount deposited over the timeframe for every token with deposits
+    pub async fn get_amounts_deposited_by_token(
+        &self,
+        timeframe: u32,
+        latest_block_num: u64,
+    ) -> Result<HashMap<H160, U256>> {
+        let start_block = get_start_block(timeframe, latest_block_num);
+
+        //Deposit(bytes32 indexed sender, address indexed tokenId, bytes32 fuelTokenId, uint256 amount)
+        let filter = Filter::new()
+            .address(self.address)
+            .event("Deposit(bytes32,address,bytes32,uint256)")
+            .from_block(start_block)
+            .to_block(latest_block_num);
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_logs(&filter).await {
+                Ok(logs) => {
+                    let mut totals: HashMap<H160, U256> = HashMap::new();
+                    for log in logs {
+                        let token = H160::from(log.topics[2]);
+                        let amount = U256::from_big_endian(&log.data[32..64]);
+                        *totals.entry(token).or_insert(U256::zero()) += amount;
+                    }
+                    return Ok(totals);
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(HashMap::new())
+    }
+
+    // gets the distinct tokens deposited or withdrawn over the timeframe
+    pub async fn get_tokens_bridged(&self, timeframe: u32, latest_block_num: u64) -> Result<Vec<H160>> {
+        let start_block = get_start_block(timeframe, latest_block_num);
+
+        //Deposit(bytes32 indexed sender, address indexed tokenId, bytes32 fuelTokenId, uint256 amount)
+        //Withdrawal(bytes32 indexed recipient, address indexed tokenId, bytes32 fuelTokenId, uint256 amount)
+        let mut tokens: Vec<H160> = vec![];
+        for event in [
+            "Deposit(bytes32,address,bytes32,uint256)",
+            "Withdrawal(bytes32,address,bytes32,uint256)",
+        ] {
+            let filter = Filter::new()
+                .address(self.address)
+                .event(event)
+                .from_block(start_block)
+                .to_block(latest_block_num);
+            for i in 0..ETHEREUM_CONNECTION_RETRIES {
+                match self.provider.get_logs(&filter).await {
+                    Ok(logs) => {
+                        for log in logs {
+                            let token = H160::from(log.topics[2]);
+                            if !tokens.contains(&token) {
+                                tokens.push(token);
+                            }
+                        }
+                        break;
+                    }
+                    Err(e) => {
+                        if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                            return Err(WatchtowerError::retries_exhausted(
+                                e.into(),
+                                ETHEREUM_CONNECTION_RETRIES,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    // gets the on-chain ERC20 symbol of a token (symbols don't change so each is only queried once)
+    pub async fn get_token_symbol(&self, token_address: &str) -> Result<String> {
+        let token_address = match token_address.parse::<H160>() {
+            Ok(addr) => addr,
+            Err(e) => return Err(WatchtowerError::InvalidAddress(format!("{token_address}: {e}"))),
+        };
+        match self.token_symbols.lock().unwrap().get(&token_address) {
+            Some(symbol) => return Ok(symbol.clone()),
+            None => {}
+        }
+
+        let token = ERC20::new(token_address, Arc::new(self.provider.clone()));
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match token.symbol().call().await {
+                Ok(symbol) => {
+                    self.token_symbols.lock().unwrap().insert(token_address, symbol.clone());
+                    return Ok(symbol);
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Err(WatchtowerError::ChainData(format!(
+            "Failed to get symbol for token: {token_address:?}"
+        )))
+    }
+
+    pub async fn is_paused(&self) -> Result<bool> {
+        // reuse a recent read so checks in the same cycle don't repeat the call
+        match *self.paused_cache.lock().unwrap() {
+            Some((read_time, paused)) => match SystemTime::now().duration_since(read_time) {
+                Ok(elapsed) if elapsed < CONTRACT_CACHE_TTL => return Ok(paused),
+                _ => {}
+            },
+            None => {}
+        }
+
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.contract.paused().call().await {
+                Ok(paused) => {
+                    *self.paused_cache.lock().unwrap() = Some((SystemTime::now(), paused));
+                    return Ok(paused);
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // reads the paused state through the secondary rpc (none if no secondary rpc is configured)
+    pub async fn is_paused_on_secondary(&self) -> Result<Option<bool>> {
+        let secondary_contract = match &self.secondary_contract {
+            Some(secondary_contract) => secondary_contract,
+            None => return Ok(None),
+        };
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match secondary_contract.paused().call().await {
+                Ok(paused) => return Ok(Some(paused)),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // gets the grants and revocations of the contract's roles in the given blocks (in block order)
+    pub async fn get_role_changes(&self, from_block: u64, to_block: u64) -> Result<Vec<RoleChange>> {
+        let filter = Filter::new()
+            .address(self.address)
+            .events(ROLE_EVENTS)
+            .from_block(from_block)
+            .to_block(to_block);
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_logs(&filter).await {
+                Ok(logs) => return Ok(parse_role_logs(logs, &["DEFAULT_ADMIN_ROLE", "PAUSER_ROLE"])),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(vec![])
+    }
+
+    // simulates a pause call to check the configured wallet is allowed to pause the contract
+    pub async fn can_pause(&self) -> Result<bool> {
+        if self.read_only {
+            return Ok(false);
+        }
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.contract.pause().call().await {
+                Ok(_) => return Ok(true),
+                Err(e) => {
+                    if e.to_string().contains("revert") {
+                        return Ok(false);
+                    }
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // simulates a token pause call to check the contract supports pausing a single token (and the wallet may call it)
+    pub async fn can_pause_token(&self, token_address: &str) -> Result<bool> {
         if self.read_only {
-            return Err(anyhow::anyhow!("Ethereum account not configured."));
+            return Ok(false);
+        }
+        let token_address = match token_address.parse::<H160>() {
+            Ok(addr) => addr,
+            Err(e) => return Err(WatchtowerError::InvalidAddress(format!("{token_address}: {e}"))),
+        };
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.token_pause_contract.pause_token(token_address).call().await {
+                Ok(_) => return Ok(true),
+                Err(e) => {
+                    if e.to_string().contains("revert") {
+                        return Ok(false);
+                    }
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    pub async fn pause_token(&self, token_address: &str, sent_transactions: &SentTransactions) -> Result<()> {
+        if self.read_only {
+            return Err(WatchtowerError::WalletNotConfigured);
+        }
+        let token = match token_address.parse::<H160>() {
+            Ok(addr) => addr,
+            Err(e) => return Err(WatchtowerError::InvalidAddress(format!("{token_address}: {e}"))),
+        };
+
+        let pause_call = self.token_pause_contract.pause_token(token);
+        let pending_tx = pause_call.send().await.map_err(|e| {
+            WatchtowerError::Transaction(format!(
+                "Failed to pause token {token_address} on gateway contract: {e}"
+            ))
+        })?;
+
+        // remember our own transaction so it isn't mistaken for unexpected wallet activity
+        let tx_hash = pending_tx.tx_hash();
+        sent_transactions.lock().unwrap().insert(tx_hash);
+
+        // wait for enough confirmations that the pause can't easily be reorged out
+        let receipt = pending_tx.confirmations(self.pause_confirmations).await.map_err(|e| {
+            WatchtowerError::Transaction(format!(
+                "Failed to pause token {token_address} on gateway contract: {e}"
+            ))
+        })?;
+        match receipt {
+            None => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for token {token_address} on the gateway contract was dropped from the mempool",
+                tx_hash
+            ))),
+            Some(receipt) if receipt.status != Some(U64::from(1)) => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for token {token_address} on the gateway contract reverted",
+                tx_hash
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+
+    pub async fn pause(&self, sent_transactions: &SentTransactions) -> Result<()> {
+        if self.read_only {
+            return Err(WatchtowerError::WalletNotConfigured);
         }
 
         // TODO: implement alert on timeout and a gas escalator (https://github.com/gakonst/ethers-rs/blob/master/examples/middleware/examples/gas_escalator.rs)
-        let result = self.contract.pause().call().await;
-        match result {
-            Err(e) => Err(anyhow::anyhow!("Failed to pause gateway contract: {}", e)),
-            Ok(_) => Ok(()),
+        let pause_call = self.contract.pause();
+        let pending_tx = pause_call
+            .send()
+            .await
+            .map_err(|e| WatchtowerError::Transaction(format!("Failed to pause gateway contract: {e}")))?;
+
+        // remember our own transaction so it isn't mistaken for unexpected wallet activity
+        let tx_hash = pending_tx.tx_hash();
+        sent_transactions.lock().unwrap().insert(tx_hash);
+
+        // wait for enough confirmations that the pause can't easily be reorged out
+        let receipt = pending_tx.confirmations(self.pause_confirmations).await;
+
+        // the paused state may have changed however the wait ended, so the next read goes back to the chain
+        *self.paused_cache.lock().unwrap() = None;
+        let receipt =
+            receipt.map_err(|e| WatchtowerError::Transaction(format!("Failed to pause gateway contract: {e}")))?;
+        match receipt {
+            None => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for the gateway contract was dropped from the mempool",
+                tx_hash
+            ))),
+            Some(receipt) if receipt.status != Some(U64::from(1)) => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for the gateway contract reverted",
+                tx_hash
+            ))),
+            Some(_) => Ok(()),
         }
     }
 }
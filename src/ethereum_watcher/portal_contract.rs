@@ -1,33 +1,39 @@
-use super::{ETHEREUM_BLOCK_TIME, ETHEREUM_CONNECTION_RETRIES};
+use super::{
+    get_start_block, parse_role_logs, RoleChange, SentTransactions, CONTRACT_CACHE_TTL, ETHEREUM_CONNECTION_RETRIES,
+    ROLE_EVENTS,
+};
+use crate::error::{Result, WatchtowerError};
+use crate::rpc_limit::LimitedHttp;
+use crate::token_amount::TokenAmount;
 use crate::WatchtowerConfig;
 
-use anyhow::Result;
 use ethers::abi::Address;
 use ethers::prelude::k256::ecdsa::SigningKey;
 use ethers::prelude::{abigen, SignerMiddleware};
-use ethers::providers::{Http, Middleware, Provider};
+use ethers::providers::{Middleware, Provider};
 use ethers::signers::{Signer, Wallet};
-use ethers::types::{Filter, H160, U256};
-use std::cmp::max;
-use std::convert::TryFrom;
-use std::ops::Mul;
+use ethers::types::{Filter, H160, U256, U64};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 abigen!(FuelMessagePortal, "./abi/FuelMessagePortal.json");
 
 #[derive(Clone, Debug)]
 pub struct PortalContract {
-    provider: Provider<Http>,
-    contract: FuelMessagePortal<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+    provider: Provider<LimitedHttp>,
+    contract: FuelMessagePortal<SignerMiddleware<Provider<LimitedHttp>, Wallet<SigningKey>>>,
+    secondary_contract: Option<FuelMessagePortal<Provider<LimitedHttp>>>,
     address: H160,
     read_only: bool,
+    paused_cache: Arc<Mutex<Option<(SystemTime, bool)>>>,
+    pause_confirmations: usize,
 }
 
 impl PortalContract {
     pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
         // setup provider
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
+        let provider = config.ethereum_http_provider()?;
         let chain_id = provider.get_chainid().await?.as_u64();
 
         // setup wallet
@@ -35,52 +41,71 @@ impl PortalContract {
         let key_str = match &config.ethereum_wallet_key {
             Some(key) => key.clone(),
             None => {
+                if config.require_wallet {
+                    return Err(WatchtowerError::WalletNotConfigured);
+                }
                 read_only = true;
                 String::from("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
             }
         };
-        let wallet: Wallet<SigningKey> = key_str.parse::<Wallet<SigningKey>>()?.with_chain_id(chain_id);
+        let wallet: Wallet<SigningKey> = key_str
+            .parse::<Wallet<SigningKey>>()
+            .map_err(|e| WatchtowerError::Wallet(e.to_string()))?
+            .with_chain_id(chain_id);
 
         // setup contract
-        let address = Address::from_str(&config.portal_contract_address)?;
+        let address = Address::from_str(&config.portal_contract_address)
+            .map_err(|e| WatchtowerError::InvalidAddress(format!("{}: {e}", config.portal_contract_address)))?;
         let client = SignerMiddleware::new(provider.clone(), wallet);
         let contract = FuelMessagePortal::new(address, Arc::new(client));
+        let secondary_contract = config
+            .ethereum_secondary_http_provider()?
+            .map(|secondary_provider| FuelMessagePortal::new(address, Arc::new(secondary_provider)));
 
         // verify contract setup is valid
         let contract_result = contract.paused().call().await;
         match contract_result {
-            Err(_) => Err(anyhow::anyhow!("Invalid portal contract.")),
+            Err(e) => Err(WatchtowerError::ContractNotInitialized(format!(
+                "Invalid portal contract: {e}"
+            ))),
             Ok(_) => Ok(PortalContract {
                 provider,
                 contract,
+                secondary_contract,
                 address,
                 read_only,
+                paused_cache: Arc::new(Mutex::new(None)),
+                pause_confirmations: config.pause_confirmations,
             }),
         }
     }
 
     pub async fn get_amount_deposited(&self, timeframe: u32, latest_block_num: u64) -> Result<U256> {
-        let block_offset = timeframe as u64 / ETHEREUM_BLOCK_TIME;
-        let start_block = max(latest_block_num, block_offset) - block_offset;
+        let start_block = get_start_block(timeframe, latest_block_num);
 
         //MessageSent(bytes32 indexed sender, bytes32 indexed recipient, uint256 indexed nonce, uint64 amount, bytes data)
         let filter = Filter::new()
             .address(self.address)
             .event("MessageSent(bytes32,bytes32,uint256,uint64,bytes)")
-            .from_block(start_block);
+            .from_block(start_block)
+            .to_block(latest_block_num);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
                 Ok(logs) => {
                     let mut total = U256::zero();
                     for log in logs {
-                        let amount = U256::from_big_endian(&log.data[0..32]).mul(U256::from(1_000_000_000));
-                        total += amount;
+                        // fuel amounts have 9 decimals where ethereum amounts have 18
+                        let amount = TokenAmount::new(U256::from_big_endian(&log.data[0..32]), 9).rescale(18);
+                        total += amount.raw();
                     }
                     return Ok(total);
                 }
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -89,27 +114,31 @@ impl PortalContract {
     }
 
     pub async fn get_amount_withdrawn(&self, timeframe: u32, latest_block_num: u64) -> Result<U256> {
-        let block_offset = timeframe as u64 / ETHEREUM_BLOCK_TIME;
-        let start_block = max(latest_block_num, block_offset) - block_offset;
+        let start_block = get_start_block(timeframe, latest_block_num);
 
         //MessageRelayed(bytes32 indexed messageId, bytes32 indexed sender, bytes32 indexed recipient, uint64 amount)
         let filter = Filter::new()
             .address(self.address)
             .event("MessageRelayed(bytes32,bytes32,bytes32,uint64)")
-            .from_block(start_block);
+            .from_block(start_block)
+            .to_block(latest_block_num);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
                 Ok(logs) => {
                     let mut total = U256::zero();
                     for log in logs {
-                        let amount = U256::from_big_endian(&log.data[0..32]).mul(U256::from(1_000_000_000));
-                        total += amount;
+                        // fuel amounts have 9 decimals where ethereum amounts have 18
+                        let amount = TokenAmount::new(U256::from_big_endian(&log.data[0..32]), 9).rescale(18);
+                        total += amount.raw();
                     }
                     return Ok(total);
                 }
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -117,16 +146,217 @@ impl PortalContract {
         Ok(U256::zero())
     }
 
-    pub async fn pause(&self) -> Result<()> {
+    pub async fn is_paused(&self) -> Result<bool> {
+        // reuse a recent read so checks in the same cycle don't repeat the call
+        match *self.paused_cache.lock().unwrap() {
+            Some((read_time, paused)) => match SystemTime::now().duration_since(read_time) {
+                Ok(elapsed) if elapsed < CONTRACT_CACHE_TTL => return Ok(paused),
+                _ => {}
+            },
+            None => {}
+        }
+
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.contract.paused().call().await {
+                Ok(paused) => {
+                    *self.paused_cache.lock().unwrap() = Some((SystemTime::now(), paused));
+                    return Ok(paused);
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // reads the paused state through the secondary rpc (none if no secondary rpc is configured)
+    pub async fn is_paused_on_secondary(&self) -> Result<Option<bool>> {
+        let secondary_contract = match &self.secondary_contract {
+            Some(secondary_contract) => secondary_contract,
+            None => return Ok(None),
+        };
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match secondary_contract.paused().call().await {
+                Ok(paused) => return Ok(Some(paused)),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // gets the grants and revocations of the contract's roles in the given blocks (in block order)
+    pub async fn get_role_changes(&self, from_block: u64, to_block: u64) -> Result<Vec<RoleChange>> {
+        let filter = Filter::new()
+            .address(self.address)
+            .events(ROLE_EVENTS)
+            .from_block(from_block)
+            .to_block(to_block);
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_logs(&filter).await {
+                Ok(logs) => return Ok(parse_role_logs(logs, &["DEFAULT_ADMIN_ROLE", "PAUSER_ROLE"])),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(vec![])
+    }
+
+    // simulates a pause call to check the configured wallet is allowed to pause the contract
+    pub async fn can_pause(&self) -> Result<bool> {
         if self.read_only {
-            return Err(anyhow::anyhow!("Ethereum account not configured."));
+            return Ok(false);
+        }
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.contract.pause().call().await {
+                Ok(_) => return Ok(true),
+                Err(e) => {
+                    if e.to_string().contains("revert") {
+                        return Ok(false);
+                    }
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    pub async fn pause(&self, sent_transactions: &SentTransactions) -> Result<()> {
+        if self.read_only {
+            return Err(WatchtowerError::WalletNotConfigured);
         }
 
         // TODO: implement alert on timeout and a gas escalator (https://github.com/gakonst/ethers-rs/blob/master/examples/middleware/examples/gas_escalator.rs)
-        let result = self.contract.pause().call().await;
+        let pause_call = self.contract.pause();
+        let pending_tx = pause_call
+            .send()
+            .await
+            .map_err(|e| WatchtowerError::Transaction(format!("Failed to pause portal contract: {e}")))?;
+
+        // remember our own transaction so it isn't mistaken for unexpected wallet activity
+        let tx_hash = pending_tx.tx_hash();
+        sent_transactions.lock().unwrap().insert(tx_hash);
+
+        // wait for enough confirmations that the pause can't easily be reorged out
+        let receipt = pending_tx.confirmations(self.pause_confirmations).await;
+
+        // the paused state may have changed however the wait ended, so the next read goes back to the chain
+        *self.paused_cache.lock().unwrap() = None;
+        let receipt =
+            receipt.map_err(|e| WatchtowerError::Transaction(format!("Failed to pause portal contract: {e}")))?;
+        match receipt {
+            None => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for the portal contract was dropped from the mempool",
+                tx_hash
+            ))),
+            Some(receipt) if receipt.status != Some(U64::from(1)) => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for the portal contract reverted",
+                tx_hash
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        abi_bool, mined_tx, send_tx_response, test_config_builder, tx_receipt, MockRpc, WALLET_KEY,
+    };
+    use serde_json::{json, Value};
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // pauses the portal contract through a mock rpc answering with the given mined transaction and receipt
+    // (each block number request finds one more block on top of block 100)
+    async fn pause_through(mined_tx: Value, receipt: Value) -> (Result<()>, MockRpc) {
+        let block_number = AtomicU64::new(100);
+        let rpc = MockRpc::start(move |method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            "eth_getTransactionByHash" => Some(Ok(mined_tx.clone())),
+            "eth_getTransactionReceipt" => Some(Ok(receipt.clone())),
+            "eth_blockNumber" => Some(Ok(json!(format!(
+                "{:#x}",
+                block_number.fetch_add(1, Ordering::SeqCst) + 1
+            )))),
+            method => send_tx_response(method),
+        });
+        let mut config = test_config_builder(&rpc.url)
+            .ethereum_wallet_key(WALLET_KEY)
+            .build()
+            .unwrap();
+        config.pause_confirmations = 3;
+        let portal_contract = PortalContract::new(&config).await.unwrap();
+        let result = portal_contract.pause(&Arc::new(Mutex::new(HashSet::new()))).await;
+        (result, rpc)
+    }
+
+    #[tokio::test]
+    async fn pause_waits_for_its_confirmations() {
+        let (result, rpc) = pause_through(mined_tx(100), tx_receipt(100, true)).await;
+        assert!(result.is_ok());
+
+        // blocks 101 and 102 give the pause 2 of its 3 confirmations, block 103 the last one
+        assert_eq!(rpc.requests("eth_blockNumber").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn dropped_pause_is_an_error() {
+        let (result, rpc) = pause_through(Value::Null, Value::Null).await;
+        match result {
+            Err(WatchtowerError::Transaction(message)) => assert!(message.contains("dropped from the mempool")),
+            other => panic!("expected a transaction error, got {other:?}"),
+        }
+        assert!(rpc.requests("eth_getTransactionReceipt").is_empty());
+    }
+
+    #[tokio::test]
+    async fn reverted_pause_is_an_error() {
+        let (result, _) = pause_through(mined_tx(100), tx_receipt(100, false)).await;
         match result {
-            Err(e) => Err(anyhow::anyhow!("Failed to pause portal contract: {}", e)),
-            Ok(_) => Ok(()),
+            Err(WatchtowerError::Transaction(message)) => assert!(message.contains("reverted")),
+            other => panic!("expected a transaction error, got {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn missing_wallet_is_read_only_unless_required() {
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let mut config = test_config_builder(&rpc.url).build().unwrap();
+        let portal_contract = PortalContract::new(&config).await.unwrap();
+        let result = portal_contract.pause(&Arc::new(Mutex::new(HashSet::new()))).await;
+        assert!(matches!(result, Err(WatchtowerError::WalletNotConfigured)));
+        assert!(rpc.requests("eth_sendRawTransaction").is_empty());
+
+        config.require_wallet = true;
+        let result = PortalContract::new(&config).await;
+        assert!(matches!(result, Err(WatchtowerError::WalletNotConfigured)));
+    }
 }
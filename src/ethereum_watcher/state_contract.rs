@@ -1,31 +1,47 @@
-use super::ETHEREUM_CONNECTION_RETRIES;
+use super::{
+    parse_role_logs, RoleChange, SentTransactions, CONTRACT_CACHE_TTL, ETHEREUM_BLOCK_TIME,
+    ETHEREUM_CONNECTION_RETRIES, ROLE_EVENTS,
+};
+use crate::error::{Result, WatchtowerError};
+use crate::rpc_limit::LimitedHttp;
 use crate::WatchtowerConfig;
 
-use anyhow::Result;
 use ethers::abi::Address;
 use ethers::prelude::k256::ecdsa::SigningKey;
 use ethers::prelude::{abigen, SignerMiddleware};
-use ethers::providers::{Http, Middleware, Provider};
+use ethers::providers::{Middleware, Provider};
 use ethers::signers::{Signer, Wallet};
-use ethers::types::{Filter, H160};
-use std::convert::TryFrom;
+use ethers::types::{Filter, Log, H160, H256, U256, U64};
+use std::collections::VecDeque;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 abigen!(FuelChainState, "./abi/FuelChainState.json");
 
+#[derive(Clone, Debug, Default)]
+pub struct CommitLogs {
+    pub commits: Vec<(u64, String)>,
+    pub commit_blocks: Vec<u64>,
+    pub skipped: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct StateContract {
-    provider: Provider<Http>,
-    contract: FuelChainState<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+    provider: Provider<LimitedHttp>,
+    secondary_provider: Option<Provider<LimitedHttp>>,
+    contract: FuelChainState<SignerMiddleware<Provider<LimitedHttp>, Wallet<SigningKey>>>,
+    secondary_contract: Option<FuelChainState<Provider<LimitedHttp>>>,
     address: H160,
     read_only: bool,
+    paused_cache: Arc<Mutex<Option<(SystemTime, bool)>>>,
+    pause_confirmations: usize,
 }
 
 impl StateContract {
     pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
         // setup provider
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
+        let provider = config.ethereum_http_provider()?;
         let chain_id = provider.get_chainid().await?.as_u64();
 
         // setup wallet
@@ -33,50 +49,231 @@ impl StateContract {
         let key_str = match &config.ethereum_wallet_key {
             Some(key) => key.clone(),
             None => {
+                if config.require_wallet {
+                    return Err(WatchtowerError::WalletNotConfigured);
+                }
                 read_only = true;
                 String::from("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
             }
         };
-        let wallet: Wallet<SigningKey> = key_str.parse::<Wallet<SigningKey>>()?.with_chain_id(chain_id);
+        let wallet: Wallet<SigningKey> = key_str
+            .parse::<Wallet<SigningKey>>()
+            .map_err(|e| WatchtowerError::Wallet(e.to_string()))?
+            .with_chain_id(chain_id);
 
         // setup contract
-        let address = Address::from_str(&config.state_contract_address)?;
+        let address = Address::from_str(&config.state_contract_address)
+            .map_err(|e| WatchtowerError::InvalidAddress(format!("{}: {e}", config.state_contract_address)))?;
         let client = SignerMiddleware::new(provider.clone(), wallet);
         let contract = FuelChainState::new(address, Arc::new(client));
+        let secondary_provider = config.ethereum_secondary_http_provider()?;
+        let secondary_contract = secondary_provider
+            .clone()
+            .map(|secondary_provider| FuelChainState::new(address, Arc::new(secondary_provider)));
 
         // verify contract setup is valid
         let contract_result = contract.paused().call().await;
         match contract_result {
-            Err(_) => Err(anyhow::anyhow!("Invalid state contract.")),
+            Err(e) => Err(WatchtowerError::ContractNotInitialized(format!(
+                "Invalid state contract: {e}"
+            ))),
             Ok(_) => Ok(StateContract {
                 provider,
+                secondary_provider,
                 contract,
+                secondary_contract,
                 address,
                 read_only,
+                paused_cache: Arc::new(Mutex::new(None)),
+                pause_confirmations: config.pause_confirmations,
             }),
         }
     }
 
-    pub async fn get_latest_commits(&self, from_block: u64) -> Result<Vec<String>> {
+    pub async fn get_latest_commits(&self, from_block: u64, to_block: u64) -> Result<CommitLogs> {
         //CommitSubmitted(uint256 indexed commitHeight, bytes32 blockHash)
         let filter = Filter::new()
             .address(self.address)
             .event("CommitSubmitted(uint256,bytes32)")
-            .from_block(from_block);
+            .from_block(from_block)
+            .to_block(to_block);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
+                Ok(logs) => return Ok(StateContract::parse_commit_logs(logs)),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(CommitLogs::default())
+    }
+
+    // checks the secondary rpc also saw a commit (none if no secondary rpc is configured or it hasn't reached the
+    // block range yet, so a lagging secondary can't hold back a real alert)
+    pub async fn confirm_commit_on_secondary(
+        &self,
+        commit_height: u64,
+        block_hash: &str,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Option<bool>> {
+        let secondary_provider = match &self.secondary_provider {
+            Some(secondary_provider) => secondary_provider,
+            None => return Ok(None),
+        };
+        let filter = Filter::new()
+            .address(self.address)
+            .event("CommitSubmitted(uint256,bytes32)")
+            .from_block(from_block)
+            .to_block(to_block);
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            let logs_result = match secondary_provider.get_block_number().await {
+                Ok(latest_block) if latest_block.as_u64() < to_block => return Ok(None),
+                Ok(_) => secondary_provider.get_logs(&filter).await,
+                Err(e) => Err(e),
+            };
+            match logs_result {
                 Ok(logs) => {
-                    // TODO
+                    let commit_logs = StateContract::parse_commit_logs(logs);
+                    return Ok(Some(
+                        commit_logs
+                            .commits
+                            .iter()
+                            .any(|(height, hash)| *height == commit_height && hash == block_hash),
+                    ));
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse_commit_logs(logs: Vec<Log>) -> CommitLogs {
+        let mut commit_logs = CommitLogs::default();
+        for log in logs {
+            // skip malformed logs rather than dropping the whole batch
+            if log.topics.len() < 2 || log.data.len() < 32 {
+                log::warn!(
+                    "Skipping malformed state contract commit log (tx: {:?}, topics: {}, data length: {}).",
+                    log.transaction_hash,
+                    log.topics.len(),
+                    log.data.len()
+                );
+                commit_logs.skipped += 1;
+                continue;
+            }
+            let commit_height = U256::from_big_endian(log.topics[1].as_bytes()).as_u64();
+            let block_hash = H256::from_slice(&log.data[0..32]);
+            commit_logs.commits.push((commit_height, format!("{:#x}", block_hash)));
+            match log.block_number {
+                Some(block_number) => commit_logs.commit_blocks.push(block_number.as_u64()),
+                None => {}
+            }
+        }
+        commit_logs
+    }
 
-                    return Ok(vec![
-                        String::from("0xe605385d3c61bdb625d58b2c55999730d9916fd90b62ce4af0d143539c3a5cb9"),
-                        String::from("0x24246dd5331b1eb52588ae8c0082987b1eb679edc09a4b5da305e4ebe5425c5a"),
-                        String::from("0x3de6af85929f81780a443b8db918a37036b7fe1b2e46e32fe876250806ab739d"),
-                    ]);
+    // gets a percentile (0-100) of the seconds between consecutive commits from the blocks they were made in
+    pub fn get_commit_interval_percentile(commit_blocks: &VecDeque<u64>, percentile: f64) -> Option<u64> {
+        let mut intervals: Vec<u64> = commit_blocks
+            .iter()
+            .zip(commit_blocks.iter().skip(1))
+            .map(|(prev, next)| next.saturating_sub(*prev) * ETHEREUM_BLOCK_TIME)
+            .collect();
+        if intervals.is_empty() {
+            return None;
+        }
+
+        // nearest rank
+        intervals.sort_unstable();
+        let rank = (percentile / 100.0 * intervals.len() as f64).ceil() as usize;
+        Some(intervals[rank.clamp(1, intervals.len()) - 1])
+    }
+
+    pub async fn is_paused(&self) -> Result<bool> {
+        // reuse a recent read so checks in the same cycle don't repeat the call
+        match *self.paused_cache.lock().unwrap() {
+            Some((read_time, paused)) => match SystemTime::now().duration_since(read_time) {
+                Ok(elapsed) if elapsed < CONTRACT_CACHE_TTL => return Ok(paused),
+                _ => {}
+            },
+            None => {}
+        }
+
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.contract.paused().call().await {
+                Ok(paused) => {
+                    *self.paused_cache.lock().unwrap() = Some((SystemTime::now(), paused));
+                    return Ok(paused);
                 }
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // reads the paused state through the secondary rpc (none if no secondary rpc is configured)
+    pub async fn is_paused_on_secondary(&self) -> Result<Option<bool>> {
+        let secondary_contract = match &self.secondary_contract {
+            Some(secondary_contract) => secondary_contract,
+            None => return Ok(None),
+        };
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match secondary_contract.paused().call().await {
+                Ok(paused) => return Ok(Some(paused)),
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // gets the grants and revocations of the contract's roles in the given blocks (in block order)
+    pub async fn get_role_changes(&self, from_block: u64, to_block: u64) -> Result<Vec<RoleChange>> {
+        let filter = Filter::new()
+            .address(self.address)
+            .events(ROLE_EVENTS)
+            .from_block(from_block)
+            .to_block(to_block);
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_logs(&filter).await {
+                Ok(logs) => {
+                    return Ok(parse_role_logs(
+                        logs,
+                        &["DEFAULT_ADMIN_ROLE", "PAUSER_ROLE", "COMMITTER_ROLE"],
+                    ))
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            e.into(),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -84,16 +281,293 @@ impl StateContract {
         Ok(vec![])
     }
 
-    pub async fn pause(&self) -> Result<()> {
+    // simulates a pause call to check the configured wallet is allowed to pause the contract
+    pub async fn can_pause(&self) -> Result<bool> {
         if self.read_only {
-            return Err(anyhow::anyhow!("Ethereum account not configured."));
+            return Ok(false);
+        }
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.contract.pause().call().await {
+                Ok(_) => return Ok(true),
+                Err(e) => {
+                    if e.to_string().contains("revert") {
+                        return Ok(false);
+                    }
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            ETHEREUM_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    pub async fn pause(&self, sent_transactions: &SentTransactions) -> Result<()> {
+        if self.read_only {
+            return Err(WatchtowerError::WalletNotConfigured);
         }
 
         // TODO: implement alert on timeout and a gas escalator (https://github.com/gakonst/ethers-rs/blob/master/examples/middleware/examples/gas_escalator.rs)
-        let result = self.contract.pause().call().await;
+        let pause_call = self.contract.pause();
+        let pending_tx = pause_call
+            .send()
+            .await
+            .map_err(|e| WatchtowerError::Transaction(format!("Failed to pause state contract: {e}")))?;
+
+        // remember our own transaction so it isn't mistaken for unexpected wallet activity
+        let tx_hash = pending_tx.tx_hash();
+        sent_transactions.lock().unwrap().insert(tx_hash);
+
+        // wait for enough confirmations that the pause can't easily be reorged out
+        let receipt = pending_tx.confirmations(self.pause_confirmations).await;
+
+        // the paused state may have changed however the wait ended, so the next read goes back to the chain
+        *self.paused_cache.lock().unwrap() = None;
+        let receipt =
+            receipt.map_err(|e| WatchtowerError::Transaction(format!("Failed to pause state contract: {e}")))?;
+        match receipt {
+            None => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for the state contract was dropped from the mempool",
+                tx_hash
+            ))),
+            Some(receipt) if receipt.status != Some(U64::from(1)) => Err(WatchtowerError::Transaction(format!(
+                "Pause transaction {:#x} for the state contract reverted",
+                tx_hash
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        abi_bool, mined_tx, send_tx_response, test_config_builder, tx_receipt, MockRpc, STATE_CONTRACT_ADDRESS,
+        TX_HASH, WALLET_KEY,
+    };
+    use ethers::utils::keccak256;
+    use serde_json::{json, Value};
+    use std::collections::HashSet;
+
+    #[test]
+    fn commit_interval_percentile_uses_nearest_rank() {
+        // intervals of 1, 2, 3 and 4 blocks
+        let commit_blocks = VecDeque::from(vec![100, 101, 103, 106, 110]);
+        let percentile = |p| StateContract::get_commit_interval_percentile(&commit_blocks, p);
+        assert_eq!(percentile(0.0), Some(ETHEREUM_BLOCK_TIME));
+        assert_eq!(percentile(50.0), Some(2 * ETHEREUM_BLOCK_TIME));
+        assert_eq!(percentile(75.0), Some(3 * ETHEREUM_BLOCK_TIME));
+        assert_eq!(percentile(90.0), Some(4 * ETHEREUM_BLOCK_TIME));
+        assert_eq!(percentile(100.0), Some(4 * ETHEREUM_BLOCK_TIME));
+    }
+
+    #[test]
+    fn commit_interval_percentile_needs_two_commits() {
+        assert_eq!(
+            StateContract::get_commit_interval_percentile(&VecDeque::new(), 50.0),
+            None
+        );
+        assert_eq!(
+            StateContract::get_commit_interval_percentile(&VecDeque::from(vec![100]), 50.0),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn paused_state_is_read_once_within_the_cache_ttl() {
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let state_contract = StateContract::new(&config).await.unwrap();
+        let setup_calls = rpc.requests("eth_call").len();
+
+        assert!(!state_contract.is_paused().await.unwrap());
+        assert!(!state_contract.is_paused().await.unwrap());
+        assert_eq!(rpc.requests("eth_call").len(), setup_calls + 1);
+    }
+
+    #[tokio::test]
+    async fn failed_pause_clears_the_cached_paused_state() {
+        // the pause is mined but the confirmations can't be counted
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            "eth_getTransactionByHash" => Some(Ok(mined_tx(100))),
+            "eth_getTransactionReceipt" => Some(Ok(tx_receipt(100, true))),
+            "eth_blockNumber" => Some(Err(String::from("unavailable"))),
+            method => send_tx_response(method),
+        });
+        let config = test_config_builder(&rpc.url)
+            .ethereum_wallet_key(WALLET_KEY)
+            .build()
+            .unwrap();
+        let state_contract = StateContract::new(&config).await.unwrap();
+        assert!(!state_contract.is_paused().await.unwrap());
+        let cached_calls = rpc.requests("eth_call").len();
+
+        let sent_transactions = Arc::new(Mutex::new(HashSet::new()));
+        assert!(state_contract.pause(&sent_transactions).await.is_err());
+        assert!(sent_transactions.lock().unwrap().contains(&TX_HASH.parse().unwrap()));
+
+        // the next read goes back to the chain rather than trusting the cached unpaused state
+        assert!(!state_contract.is_paused().await.unwrap());
+        assert_eq!(rpc.requests("eth_call").len(), cached_calls + 1);
+    }
+
+    fn commit_log(height: u64, block_hash: H256) -> Value {
+        json!({
+            "address": STATE_CONTRACT_ADDRESS,
+            "topics": [H256::from(keccak256("CommitSubmitted(uint256,bytes32)")), H256::from_low_u64_be(height)],
+            "data": block_hash,
+            "blockNumber": "0x60",
+        })
+    }
+
+    // a state contract reading from the given primary rpc and a secondary rpc answering with the given logs
+    async fn with_secondary(secondary_logs: Vec<Value>, secondary_block: u64) -> (StateContract, MockRpc) {
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let secondary_rpc = MockRpc::start(move |method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(true))),
+            "eth_getLogs" => Some(Ok(json!(secondary_logs))),
+            "eth_blockNumber" => Some(Ok(json!(format!("{secondary_block:#x}")))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url)
+            .ethereum_rpc_secondary(&secondary_rpc.url)
+            .build()
+            .unwrap();
+        (StateContract::new(&config).await.unwrap(), secondary_rpc)
+    }
+
+    #[tokio::test]
+    async fn commit_missing_on_the_secondary_is_a_disagreement() {
+        let block_hash = H256::from_low_u64_be(0xabc);
+        let hash = format!("{block_hash:#x}");
+
+        let (state_contract, _secondary_rpc) = with_secondary(vec![], 100).await;
+        let confirmed = state_contract.confirm_commit_on_secondary(7, &hash, 90, 100).await;
+        assert_eq!(confirmed.unwrap(), Some(false));
+
+        // a different commit at the same height doesn't count either
+        let other_commit = commit_log(7, H256::from_low_u64_be(0xdef));
+        let (state_contract, _secondary_rpc) = with_secondary(vec![other_commit], 100).await;
+        let confirmed = state_contract.confirm_commit_on_secondary(7, &hash, 90, 100).await;
+        assert_eq!(confirmed.unwrap(), Some(false));
+
+        let (state_contract, _secondary_rpc) = with_secondary(vec![commit_log(7, block_hash)], 100).await;
+        let confirmed = state_contract.confirm_commit_on_secondary(7, &hash, 90, 100).await;
+        assert_eq!(confirmed.unwrap(), Some(true));
+    }
+
+    #[tokio::test]
+    async fn lagging_secondary_neither_confirms_nor_disputes_a_commit() {
+        let (state_contract, secondary_rpc) = with_secondary(vec![], 99).await;
+        let hash = format!("{:#x}", H256::from_low_u64_be(0xabc));
+        let confirmed = state_contract.confirm_commit_on_secondary(7, &hash, 90, 100).await;
+        assert_eq!(confirmed.unwrap(), None);
+        assert!(secondary_rpc.requests("eth_getLogs").is_empty());
+    }
+
+    #[tokio::test]
+    async fn paused_state_is_also_read_through_the_secondary() {
+        let (state_contract, _secondary_rpc) = with_secondary(vec![], 100).await;
+        assert!(!state_contract.is_paused().await.unwrap());
+        assert_eq!(state_contract.is_paused_on_secondary().await.unwrap(), Some(true));
+
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let state_contract = StateContract::new(&config).await.unwrap();
+        assert_eq!(state_contract.is_paused_on_secondary().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn bad_contract_address_is_an_invalid_address() {
+        let rpc = MockRpc::start(|_, _| None);
+        let mut config = test_config_builder(&rpc.url).build().unwrap();
+        config.state_contract_address = String::from("0xnot-an-address");
+        match StateContract::new(&config).await {
+            Err(WatchtowerError::InvalidAddress(address)) => assert!(address.starts_with("0xnot-an-address")),
+            other => panic!("expected an invalid address error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_log_queries_exhaust_their_retries() {
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            "eth_getLogs" => Some(Err(String::from("query timeout exceeded"))),
+            _ => None,
+        });
+        let config = test_config_builder(&rpc.url).build().unwrap();
+        let state_contract = StateContract::new(&config).await.unwrap();
+        let result = state_contract.get_latest_commits(90, 100).await;
+        assert_eq!(rpc.requests("eth_getLogs").len() as u64, ETHEREUM_CONNECTION_RETRIES);
+        let message = result.as_ref().unwrap_err().to_string();
+        assert!(
+            message.ends_with(&format!("(failed after {ETHEREUM_CONNECTION_RETRIES} attempts)")),
+            "{message}"
+        );
         match result {
-            Err(e) => Err(anyhow::anyhow!("Failed to pause state contract: {}", e)),
-            Ok(_) => Ok(()),
+            Err(WatchtowerError::RetriesExhausted { source, attempts }) => {
+                assert_eq!(attempts, ETHEREUM_CONNECTION_RETRIES);
+                assert!(matches!(*source, WatchtowerError::Connection(_)));
+            }
+            other => panic!("expected the retries to be exhausted, got {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn missing_wallet_is_read_only_unless_required() {
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let mut config = test_config_builder(&rpc.url).build().unwrap();
+        let state_contract = StateContract::new(&config).await.unwrap();
+        let result = state_contract.pause(&Arc::new(Mutex::new(HashSet::new()))).await;
+        assert!(matches!(result, Err(WatchtowerError::WalletNotConfigured)));
+        assert!(rpc.requests("eth_sendRawTransaction").is_empty());
+
+        config.require_wallet = true;
+        let result = StateContract::new(&config).await;
+        assert!(matches!(result, Err(WatchtowerError::WalletNotConfigured)));
+    }
+
+    #[test]
+    fn malformed_commit_logs_are_skipped_and_counted() {
+        let first_hash = H256::from_low_u64_be(0xaa);
+        let second_hash = H256::from_low_u64_be(0xbb);
+        let mut short_data = commit_log(11, H256::zero());
+        short_data["data"] = json!("0x1234");
+        let mut missing_height = commit_log(12, H256::zero());
+        missing_height["topics"] = json!([H256::from(keccak256("CommitSubmitted(uint256,bytes32)"))]);
+        let logs: Vec<Log> = [
+            commit_log(10, first_hash),
+            short_data,
+            missing_height,
+            commit_log(13, second_hash),
+        ]
+        .into_iter()
+        .map(|log| serde_json::from_value(log).unwrap())
+        .collect();
+
+        let commit_logs = StateContract::parse_commit_logs(logs);
+        assert_eq!(
+            commit_logs.commits,
+            vec![(10, format!("{:#x}", first_hash)), (13, format!("{:#x}", second_hash))]
+        );
+        assert_eq!(commit_logs.commit_blocks, vec![0x60, 0x60]);
+        assert_eq!(commit_logs.skipped, 2);
+    }
 }
@@ -1,168 +1,712 @@
-use crate::alerts::{AlertLevel, WatchtowerAlerts};
+use crate::alerts::{AlertLevel, AlertType, Chain, CheckOutcome, WatchtowerAlerts};
+use crate::config::{is_check_enabled, BlockProduction, FuelClientWatcher, WithdrawAlert, WithdrawalScanMode};
+use crate::deadmanswitch::DeadManSwitchClient;
+use crate::error::WatchtowerError;
 use crate::ethereum_actions::WatchtowerEthereumActions;
+use crate::token_amount::{get_threshold_band, ThresholdBand, TokenAmount};
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
-use fuel_chain::FuelChain;
+use fuel_chain::{FuelChain, WithdrawnAmounts};
 use fungible_token_contract::FungibleTokenContract;
-use std::thread;
+use serde_json::{json, Value};
+use std::cmp::max;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::task::JoinHandle;
 
+pub mod extended_provider;
 pub mod fuel_chain;
 pub mod fungible_token_contract;
 
 pub static POLL_DURATION: Duration = Duration::from_millis(4000);
-pub static POLL_LOGGING_SKIP: u64 = 75;
 pub static FUEL_CONNECTION_RETRIES: u64 = 2;
 pub static FUEL_BLOCK_TIME: u64 = 1;
 
 pub async fn start_fuel_watcher(
     config: &WatchtowerConfig,
+    live_watch_config: Arc<RwLock<FuelClientWatcher>>,
     actions: WatchtowerEthereumActions,
     alerts: WatchtowerAlerts,
+    deadmanswitch: Option<DeadManSwitchClient>,
 ) -> Result<JoinHandle<()>> {
     let fuel_chain = FuelChain::new(config).await?;
     let fungible_token_contract = FungibleTokenContract::new(config).await?;
 
-    let watch_config = config.fuel_client_watcher.clone();
+    let max_cycles = config.max_cycles;
+    // pause briefly between checks so rpc calls are spread out instead of bursting at the start of each cycle
+    let check_stagger = Duration::from_millis(config.check_stagger_ms);
+    let mut cycles: u64 = 0;
+    let mut last_block_height: u64 = 0;
+    let mut stalled_cycles: u32 = 0;
 
     // start thread
     let handle = tokio::spawn(async move {
         loop {
+            // stamp everything alerted this cycle with the cycle number
+            let alerts = alerts.for_cycle(cycles);
+
+            // pick up any changes from a config reload
+            let watch_config = live_watch_config.read().unwrap().clone();
+            let poll_logging_skip = max(watch_config.poll_logging_skip, 1);
+
             // update the log every so often to notify that everything is working
-            alerts.alert(String::from("Watching fuel chain."), AlertLevel::Info);
-            for _i in 0..POLL_LOGGING_SKIP {
-                // check chain connection
-                if watch_config.connection_alert.alert_level != AlertLevel::None {
-                    match fuel_chain.check_connection().await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check fuel connection: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
-                    }
-                }
+            if cycles.is_multiple_of(poll_logging_skip) {
+                alerts.alert(
+                    String::from("Watching fuel chain."),
+                    AlertLevel::Info,
+                    AlertType::FuelWatcherStatus,
+                );
+            }
 
-                // check block production
-                if watch_config.block_production_alert.alert_level != AlertLevel::None {
-                    match fuel_chain.get_seconds_since_last_block().await {
-                        Ok(seconds_since_last_block) => {
-                            if seconds_since_last_block > watch_config.block_production_alert.max_block_time {
-                                alerts.alert(
-                                    format!(
-                                        "Next fuel block is taking longer than {} seconds. Last block was {} seconds ago.",
-                                        watch_config.block_production_alert.max_block_time, seconds_since_last_block
-                                    ),
-                                    watch_config.block_production_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    watch_config.block_production_alert.alert_action.clone(),
-                                    Some(watch_config.block_production_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check fuel block production: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
-                    }
-                }
+            // check chain connection
+            let connection_outcome = check_chain_connection(&fuel_chain, &watch_config, &actions, &alerts).await;
 
-                // check base asset withdrawals
-                for portal_withdraw_alert in &watch_config.portal_withdraw_alerts {
-                    if portal_withdraw_alert.alert_level != AlertLevel::None {
-                        let time_frame = portal_withdraw_alert.time_frame;
-                        match fuel_chain.get_amount_withdrawn(time_frame).await {
-                            Ok(amount) => {
-                                let amount_threshold = FuelChain::get_value(portal_withdraw_alert.amount, 9);
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "Base asset withdraw threshold of {} over {} seconds has been reached. Amount withdrawn: {}",
-                                            amount_threshold, time_frame, amount
-                                        ),
-                                        portal_withdraw_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        portal_withdraw_alert.alert_action.clone(),
-                                        Some(portal_withdraw_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check base asset withdrawals: {e}"),
-                                    portal_withdraw_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    portal_withdraw_alert.alert_action.clone(),
-                                    Some(portal_withdraw_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                    }
+            tokio::time::sleep(check_stagger).await;
+            // check block production
+            check_block_production(&fuel_chain, &watch_config, &actions, &alerts).await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check block height is still advancing
+            check_block_height(
+                &fuel_chain,
+                &mut last_block_height,
+                &mut stalled_cycles,
+                &watch_config,
+                &actions,
+                &alerts,
+            )
+            .await;
+
+            tokio::time::sleep(check_stagger).await;
+            // check base asset withdrawals
+            for portal_withdraw_alert in &watch_config.portal_withdraw_alerts {
+                check_base_asset_withdrawals(&fuel_chain, portal_withdraw_alert, &watch_config, &actions, &alerts)
+                    .await;
+            }
+
+            tokio::time::sleep(check_stagger).await;
+            // check ERC20 token withdrawals
+            for gateway_withdraw_alert in &watch_config.gateway_withdraw_alerts {
+                if !watch_config.is_token_ignored(&gateway_withdraw_alert.token_address) {
+                    check_token_withdrawals(&fungible_token_contract, gateway_withdraw_alert, &actions, &alerts).await;
                 }
+            }
 
-                // check ERC20 token withdrawals
-                for gateway_withdraw_alert in &watch_config.gateway_withdraw_alerts {
-                    if gateway_withdraw_alert.alert_level != AlertLevel::None {
-                        match fungible_token_contract
-                            .get_amount_withdrawn(
-                                gateway_withdraw_alert.time_frame,
-                                &gateway_withdraw_alert.token_address,
-                            )
-                            .await
-                        {
-                            Ok(amount) => {
-                                let amount_threshold = FuelChain::get_value(
-                                    gateway_withdraw_alert.amount,
-                                    gateway_withdraw_alert.token_decimals,
-                                );
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "ERC20 withdraw threshold of {}{} over {} seconds has been reached. Amount withdrawn: {}{}",
-                                            amount_threshold, gateway_withdraw_alert.token_name, gateway_withdraw_alert.time_frame, amount, gateway_withdraw_alert.token_name
-                                        ),
-                                        gateway_withdraw_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        gateway_withdraw_alert.alert_action.clone(),
-                                        Some(gateway_withdraw_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check ERC20 withdrawals: {e}"),
-                                    gateway_withdraw_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    gateway_withdraw_alert.alert_action.clone(),
-                                    Some(gateway_withdraw_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                    }
+            // push anything held back from this cycle (when aggregating alerts per cycle)
+            alerts.end_cycle(Chain::Fuel);
+
+            // let the dead man's switch know the cycle completed (a failed connection counts as unhealthy)
+            match &deadmanswitch {
+                Some(deadmanswitch) => {
+                    let healthy = !matches!(connection_outcome, CheckOutcome::Failed(_));
+                    deadmanswitch.report_cycle("fuel", healthy).await;
                 }
+                None => {}
+            }
 
-                thread::sleep(POLL_DURATION);
+            // stop after a fixed number of cycles when configured to (used for testing)
+            cycles += 1;
+            match max_cycles {
+                Some(max_cycles) if cycles >= max_cycles => return,
+                _ => {}
             }
+
+            tokio::time::sleep(POLL_DURATION).await;
         }
     });
 
     Ok(handle)
 }
+
+async fn check_chain_connection(
+    fuel_chain: &FuelChain,
+    watch_config: &FuelClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(
+        watch_config.connection_alert.enabled,
+        &watch_config.connection_alert.alert_level,
+    ) {
+        return CheckOutcome::Ok;
+    }
+
+    match fuel_chain.check_connection().await {
+        Ok(_) => {
+            alerts.record_success(AlertType::FuelConnection, "");
+            CheckOutcome::Ok
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::FuelConnection,
+                "",
+                watch_config.connection_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    watch_config
+                        .connection_alert
+                        .details
+                        .annotate(format!("Failed to check fuel connection: {e}")),
+                    watch_config.connection_alert.alert_level.clone(),
+                    AlertType::FuelConnection,
+                );
+                actions.action(
+                    watch_config.connection_alert.alert_action.clone(),
+                    Some(watch_config.connection_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+async fn check_block_production(
+    fuel_chain: &FuelChain,
+    watch_config: &FuelClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(
+        watch_config.block_production_alert.enabled,
+        &watch_config.block_production_alert.alert_level,
+    ) {
+        return CheckOutcome::Ok;
+    }
+
+    match fuel_chain.get_seconds_since_last_block().await {
+        Ok(seconds_since_last_block) => {
+            alerts.record_success(AlertType::FuelBlockProduction, "");
+
+            // a block time far beyond the normal threshold means production has stopped rather than slowed
+            let block_production_alert = &watch_config.block_production_alert;
+            match block_production_alert.get_block_production(seconds_since_last_block) {
+                BlockProduction::Stopped { critical_block_time } => {
+                    alerts.alert(
+                        block_production_alert.details.annotate(format!(
+                            "Fuel block production has stopped. No block for over {} seconds. Last block was {} seconds ago.",
+                            critical_block_time, seconds_since_last_block
+                        )),
+                        AlertLevel::Error,
+                        AlertType::FuelBlockProductionStopped,
+                    );
+                    actions.action(
+                        block_production_alert.critical_alert_action.clone(),
+                        Some(AlertLevel::Error),
+                    );
+                    CheckOutcome::Alerted(AlertType::FuelBlockProductionStopped)
+                }
+                BlockProduction::Slow => {
+                    alerts.alert(
+                        block_production_alert.details.annotate(format!(
+                            "Next fuel block is taking longer than {} seconds. Last block was {} seconds ago.",
+                            block_production_alert.max_block_time, seconds_since_last_block
+                        )),
+                        block_production_alert.alert_level.clone(),
+                        AlertType::FuelBlockProduction,
+                    );
+                    actions.action(
+                        block_production_alert.alert_action.clone(),
+                        Some(block_production_alert.alert_level.clone()),
+                    );
+                    CheckOutcome::Alerted(AlertType::FuelBlockProduction)
+                }
+                BlockProduction::OnTime => CheckOutcome::Ok,
+            }
+        }
+        Err(WatchtowerError::ClockSkew(seconds_ahead)) => {
+            // the local clock is behind the node, so don't mistake this for a block production issue
+            alerts.alert_for_chain(
+                watch_config.block_production_alert.details.annotate(format!(
+                    "Latest fuel block time is {} seconds ahead of the local clock. Check NTP on the watchtower and node hosts.",
+                    seconds_ahead
+                )),
+                watch_config.block_production_alert.alert_level.clone(),
+                AlertType::ClockSkew,
+                Chain::Fuel,
+            );
+            CheckOutcome::Alerted(AlertType::ClockSkew)
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::FuelBlockProduction,
+                "",
+                watch_config.connection_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    watch_config
+                        .connection_alert
+                        .details
+                        .annotate(format!("Failed to check fuel block production: {e}")),
+                    watch_config.connection_alert.alert_level.clone(),
+                    AlertType::FuelBlockProduction,
+                );
+                actions.action(
+                    watch_config.connection_alert.alert_action.clone(),
+                    Some(watch_config.connection_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+async fn check_block_height(
+    fuel_chain: &FuelChain,
+    last_block_height: &mut u64,
+    stalled_cycles: &mut u32,
+    watch_config: &FuelClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(
+        watch_config.block_height_stall_alert.enabled,
+        &watch_config.block_height_stall_alert.alert_level,
+    ) {
+        return CheckOutcome::Ok;
+    }
+
+    match fuel_chain.get_latest_block_height().await {
+        Ok(block_height) => {
+            alerts.record_success(AlertType::FuelBlockHeightStalled, "");
+            if is_height_stalled(
+                last_block_height,
+                stalled_cycles,
+                block_height,
+                watch_config.block_height_stall_alert.max_stalled_cycles,
+            ) {
+                alerts.alert(
+                    watch_config.block_height_stall_alert.details.annotate(format!(
+                        "Fuel block height has not advanced past {} for {} cycles.",
+                        last_block_height, stalled_cycles
+                    )),
+                    watch_config.block_height_stall_alert.alert_level.clone(),
+                    AlertType::FuelBlockHeightStalled,
+                );
+                actions.action(
+                    watch_config.block_height_stall_alert.alert_action.clone(),
+                    Some(watch_config.block_height_stall_alert.alert_level.clone()),
+                );
+                return CheckOutcome::Alerted(AlertType::FuelBlockHeightStalled);
+            }
+            CheckOutcome::Ok
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::FuelBlockHeightStalled,
+                "",
+                watch_config
+                    .block_height_stall_alert
+                    .details
+                    .consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    watch_config
+                        .block_height_stall_alert
+                        .details
+                        .annotate(format!("Failed to check fuel block height: {e}")),
+                    watch_config.block_height_stall_alert.alert_level.clone(),
+                    AlertType::FuelBlockHeightStalled,
+                );
+                actions.action(
+                    watch_config.block_height_stall_alert.alert_action.clone(),
+                    Some(watch_config.block_height_stall_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+// counts the cycles the height has not advanced for, true once that reaches max_stalled_cycles
+// (a new height resets the count)
+fn is_height_stalled(
+    last_block_height: &mut u64,
+    stalled_cycles: &mut u32,
+    block_height: u64,
+    max_stalled_cycles: u32,
+) -> bool {
+    if block_height > *last_block_height {
+        *last_block_height = block_height;
+        *stalled_cycles = 0;
+    } else {
+        *stalled_cycles += 1;
+    }
+    *stalled_cycles >= max_stalled_cycles
+}
+
+async fn check_base_asset_withdrawals(
+    fuel_chain: &FuelChain,
+    portal_withdraw_alert: &WithdrawAlert,
+    watch_config: &FuelClientWatcher,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(portal_withdraw_alert.enabled, &portal_withdraw_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let time_frame = portal_withdraw_alert.time_frame;
+    let check_id = time_frame.to_string();
+    let sample_rate = match watch_config.withdrawal_scan_mode {
+        WithdrawalScanMode::Full => 1.0,
+        WithdrawalScanMode::Sampled => watch_config.withdrawal_sample_rate,
+    };
+    match fuel_chain
+        .get_amount_withdrawn(time_frame, sample_rate, watch_config.withdrawal_receipt_filter)
+        .await
+    {
+        Ok(withdrawn) => {
+            alerts.record_success(AlertType::FuelPortalWithdraw, &check_id);
+            let amount = TokenAmount::new(withdrawn.total, 9);
+            let largest_amount = TokenAmount::new(withdrawn.largest, 9);
+            let mut outcome = CheckOutcome::Ok;
+            let amount_threshold = TokenAmount::from_float(portal_withdraw_alert.amount, 9);
+            let band = get_threshold_band(
+                amount,
+                portal_withdraw_alert.amount,
+                portal_withdraw_alert.warning_fraction,
+                9,
+            );
+            if band == ThresholdBand::Over {
+                alerts.alert_with_context(
+                    portal_withdraw_alert.details.annotate(format!(
+                        "Base asset withdraw threshold of {} over {} seconds has been reached. Amount withdrawn: {}{} (blocks [{}, {}])",
+                        amount_threshold,
+                        time_frame,
+                        amount,
+                        get_estimate_label(&withdrawn),
+                        withdrawn.start_height,
+                        withdrawn.end_height
+                    )),
+                    portal_withdraw_alert.alert_level.clone(),
+                    AlertType::FuelPortalWithdraw,
+                    None,
+                    get_amount_context(amount, amount_threshold, None, time_frame, &withdrawn),
+                );
+                actions.action(
+                    portal_withdraw_alert.alert_action.clone(),
+                    Some(portal_withdraw_alert.alert_level.clone()),
+                );
+                outcome = CheckOutcome::Alerted(AlertType::FuelPortalWithdraw);
+            } else {
+                // warn early once withdrawals get close to the threshold (no action is taken)
+                if band == ThresholdBand::Warning {
+                    alerts.alert_with_context(
+                        portal_withdraw_alert.details.annotate(format!(
+                            "Base asset withdrawals over {} seconds are approaching the threshold of {}. Amount withdrawn: {}{} (blocks [{}, {}])",
+                            time_frame,
+                            amount_threshold,
+                            amount,
+                            get_estimate_label(&withdrawn),
+                            withdrawn.start_height,
+                            withdrawn.end_height
+                        )),
+                        AlertLevel::Warn,
+                        AlertType::FuelPortalWithdraw,
+                        Some("warning"),
+                        get_amount_context(amount, amount_threshold, None, time_frame, &withdrawn),
+                    );
+                    outcome = CheckOutcome::Alerted(AlertType::FuelPortalWithdraw);
+                }
+            }
+
+            // a single large withdrawal can be worth alerting on even when the total is under the threshold
+            match get_single_tx_threshold_reached(largest_amount, portal_withdraw_alert.single_tx_threshold, 9) {
+                Some(single_tx_threshold) => {
+                    alerts.alert_with_context(
+                        portal_withdraw_alert.details.annotate(format!(
+                            "Base asset single withdraw threshold of {} has been reached. Largest withdrawal over {} seconds: {}{} (blocks [{}, {}])",
+                            single_tx_threshold,
+                            time_frame,
+                            largest_amount,
+                            get_sampled_label(&withdrawn),
+                            withdrawn.start_height,
+                            withdrawn.end_height
+                        )),
+                        portal_withdraw_alert.alert_level.clone(),
+                        AlertType::LargeSingleWithdrawal,
+                        None,
+                        get_amount_context(largest_amount, single_tx_threshold, None, time_frame, &withdrawn),
+                    );
+                    actions.action(
+                        portal_withdraw_alert.alert_action.clone(),
+                        Some(portal_withdraw_alert.alert_level.clone()),
+                    );
+                    outcome = CheckOutcome::Alerted(AlertType::LargeSingleWithdrawal);
+                }
+                None => {}
+            }
+            outcome
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::FuelPortalWithdraw,
+                &check_id,
+                portal_withdraw_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert(
+                    portal_withdraw_alert
+                        .details
+                        .annotate(format!("Failed to check base asset withdrawals: {e}")),
+                    portal_withdraw_alert.alert_level.clone(),
+                    AlertType::FuelPortalWithdraw,
+                );
+                actions.action(
+                    portal_withdraw_alert.alert_action.clone(),
+                    Some(portal_withdraw_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+// gets the single withdrawal threshold (scaled up by the given decimals) once the largest withdrawal has reached it
+fn get_single_tx_threshold_reached(
+    largest_amount: TokenAmount,
+    single_tx_threshold: Option<f64>,
+    decimals: u8,
+) -> Option<TokenAmount> {
+    let single_tx_threshold = TokenAmount::from_float(single_tx_threshold?, decimals);
+    match largest_amount >= single_tx_threshold {
+        true => Some(single_tx_threshold),
+        false => None,
+    }
+}
+
+// builds the structured context for a withdraw threshold alert (amounts are strings to match the ethereum alerts)
+fn get_amount_context(
+    amount: TokenAmount,
+    threshold: TokenAmount,
+    token: Option<&str>,
+    time_frame: u32,
+    withdrawn: &WithdrawnAmounts,
+) -> Value {
+    json!({
+        "amount": amount.to_string(),
+        "threshold": threshold.to_string(),
+        "token": token,
+        "time_frame": time_frame,
+        "start_block": withdrawn.start_height,
+        "end_block": withdrawn.end_height,
+        "estimated": withdrawn.estimated,
+    })
+}
+
+// labels amounts extrapolated from a sample of blocks so they aren't mistaken for exact figures
+fn get_estimate_label(withdrawn: &WithdrawnAmounts) -> &'static str {
+    match withdrawn.estimated {
+        true => " (estimated from sampled blocks)",
+        false => "",
+    }
+}
+
+// labels amounts only taken from a sample of blocks (a larger one may have been missed)
+fn get_sampled_label(withdrawn: &WithdrawnAmounts) -> &'static str {
+    match withdrawn.estimated {
+        true => " (from sampled blocks only)",
+        false => "",
+    }
+}
+
+async fn check_token_withdrawals(
+    fungible_token_contract: &FungibleTokenContract,
+    gateway_withdraw_alert: &WithdrawAlert,
+    actions: &WatchtowerEthereumActions,
+    alerts: &WatchtowerAlerts,
+) -> CheckOutcome {
+    if !is_check_enabled(gateway_withdraw_alert.enabled, &gateway_withdraw_alert.alert_level) {
+        return CheckOutcome::Ok;
+    }
+
+    let check_id = format!(
+        "{}/{}",
+        gateway_withdraw_alert.token_address, gateway_withdraw_alert.time_frame
+    );
+    match fungible_token_contract
+        .get_amount_withdrawn(gateway_withdraw_alert.time_frame, &gateway_withdraw_alert.token_address)
+        .await
+    {
+        Ok(withdrawn) => {
+            alerts.record_success(AlertType::FuelGatewayWithdraw, &check_id);
+            let amount = TokenAmount::new(withdrawn.total, gateway_withdraw_alert.token_decimals);
+            let largest_amount = TokenAmount::new(withdrawn.largest, gateway_withdraw_alert.token_decimals);
+            let mut outcome = CheckOutcome::Ok;
+            let amount_threshold =
+                TokenAmount::from_float(gateway_withdraw_alert.amount, gateway_withdraw_alert.token_decimals);
+            let band = get_threshold_band(
+                amount,
+                gateway_withdraw_alert.amount,
+                gateway_withdraw_alert.warning_fraction,
+                gateway_withdraw_alert.token_decimals,
+            );
+            if band == ThresholdBand::Over {
+                alerts.alert_with_context(
+                    gateway_withdraw_alert.details.annotate(format!(
+                        "ERC20 withdraw threshold of {}{} over {} seconds has been reached. Amount withdrawn: {}{} (blocks [{}, {}])",
+                        amount_threshold,
+                        gateway_withdraw_alert.token_name,
+                        gateway_withdraw_alert.time_frame,
+                        amount,
+                        gateway_withdraw_alert.token_name,
+                        withdrawn.start_height,
+                        withdrawn.end_height
+                    )),
+                    gateway_withdraw_alert.alert_level.clone(),
+                    AlertType::FuelGatewayWithdraw,
+                    Some(&gateway_withdraw_alert.token_address),
+                    get_amount_context(
+                        amount,
+                        amount_threshold,
+                        Some(&gateway_withdraw_alert.token_address),
+                        gateway_withdraw_alert.time_frame,
+                        &withdrawn,
+                    ),
+                );
+                actions.action(
+                    gateway_withdraw_alert.alert_action.clone(),
+                    Some(gateway_withdraw_alert.alert_level.clone()),
+                );
+                outcome = CheckOutcome::Alerted(AlertType::FuelGatewayWithdraw);
+            } else {
+                // warn early once withdrawals get close to the threshold (no action is taken)
+                if band == ThresholdBand::Warning {
+                    alerts.alert_with_context(
+                        gateway_withdraw_alert.details.annotate(format!(
+                            "ERC20 withdrawals over {} seconds are approaching the threshold of {}{}. Amount withdrawn: {}{} (blocks [{}, {}])",
+                            gateway_withdraw_alert.time_frame,
+                            amount_threshold,
+                            gateway_withdraw_alert.token_name,
+                            amount,
+                            gateway_withdraw_alert.token_name,
+                            withdrawn.start_height,
+                            withdrawn.end_height
+                        )),
+                        AlertLevel::Warn,
+                        AlertType::FuelGatewayWithdraw,
+                        Some(&format!("{}/warning", gateway_withdraw_alert.token_address)),
+                        get_amount_context(
+                            amount,
+                            amount_threshold,
+                            Some(&gateway_withdraw_alert.token_address),
+                            gateway_withdraw_alert.time_frame,
+                            &withdrawn,
+                        ),
+                    );
+                    outcome = CheckOutcome::Alerted(AlertType::FuelGatewayWithdraw);
+                }
+            }
+
+            // a single large withdrawal can be worth alerting on even when the total is under the threshold
+            match get_single_tx_threshold_reached(
+                largest_amount,
+                gateway_withdraw_alert.single_tx_threshold,
+                gateway_withdraw_alert.token_decimals,
+            ) {
+                Some(single_tx_threshold) => {
+                    alerts.alert_with_context(
+                        gateway_withdraw_alert.details.annotate(format!(
+                            "ERC20 single withdraw threshold of {}{} has been reached. Largest withdrawal over {} seconds: {}{} (blocks [{}, {}])",
+                            single_tx_threshold,
+                            gateway_withdraw_alert.token_name,
+                            gateway_withdraw_alert.time_frame,
+                            largest_amount,
+                            gateway_withdraw_alert.token_name,
+                            withdrawn.start_height,
+                            withdrawn.end_height
+                        )),
+                        gateway_withdraw_alert.alert_level.clone(),
+                        AlertType::LargeSingleWithdrawal,
+                        Some(&gateway_withdraw_alert.token_address),
+                        get_amount_context(
+                            largest_amount,
+                            single_tx_threshold,
+                            Some(&gateway_withdraw_alert.token_address),
+                            gateway_withdraw_alert.time_frame,
+                            &withdrawn,
+                        ),
+                    );
+                    actions.action(
+                        gateway_withdraw_alert.alert_action.clone(),
+                        Some(gateway_withdraw_alert.alert_level.clone()),
+                    );
+                    outcome = CheckOutcome::Alerted(AlertType::LargeSingleWithdrawal);
+                }
+                None => {}
+            }
+            outcome
+        }
+        Err(e) => {
+            if alerts.record_failure(
+                AlertType::FuelGatewayWithdraw,
+                &check_id,
+                gateway_withdraw_alert.details.consecutive_failures_to_alert,
+            ) {
+                alerts.alert_with_dedup_suffix(
+                    gateway_withdraw_alert
+                        .details
+                        .annotate(format!("Failed to check ERC20 withdrawals: {e}")),
+                    gateway_withdraw_alert.alert_level.clone(),
+                    AlertType::FuelGatewayWithdraw,
+                    &gateway_withdraw_alert.token_address,
+                );
+                actions.action(
+                    gateway_withdraw_alert.alert_action.clone(),
+                    Some(gateway_withdraw_alert.alert_level.clone()),
+                );
+            }
+            CheckOutcome::Failed(e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_height_stalls_after_the_max_cycles() {
+        let mut last_block_height = 0;
+        let mut stalled_cycles = 0;
+        let mut stalled =
+            |block_height| is_height_stalled(&mut last_block_height, &mut stalled_cycles, block_height, 2);
+        assert!(!stalled(100));
+        assert!(!stalled(100));
+        assert!(stalled(100));
+        assert!(stalled(100));
+
+        // a new height resets the count
+        assert!(!stalled(101));
+        assert!(!stalled(101));
+        assert!(stalled(101));
+    }
+
+    #[test]
+    fn lower_height_does_not_count_as_progress() {
+        let mut last_block_height = 0;
+        let mut stalled_cycles = 0;
+        let mut stalled =
+            |block_height| is_height_stalled(&mut last_block_height, &mut stalled_cycles, block_height, 1);
+        assert!(!stalled(100));
+        assert!(stalled(99));
+        assert_eq!(last_block_height, 100);
+    }
+
+    #[test]
+    fn one_large_withdrawal_alerts_under_the_windowed_threshold() {
+        // withdrawals of 3, 2 and 8 over the window, against a window threshold of 20 and a single one of 5
+        let withdrawals: [u64; 3] = [3_000_000_000, 2_000_000_000, 8_000_000_000];
+        let total = TokenAmount::new(withdrawals.iter().sum::<u64>(), 9);
+        let largest = TokenAmount::new(*withdrawals.iter().max().unwrap(), 9);
+        assert_eq!(get_threshold_band(total, 20.0, None, 9), ThresholdBand::Under);
+        assert_eq!(
+            get_single_tx_threshold_reached(largest, Some(5.0), 9),
+            Some(TokenAmount::from_float(5.0, 9))
+        );
+
+        // nothing is reached under the single threshold or without one
+        assert_eq!(get_single_tx_threshold_reached(largest, Some(9.0), 9), None);
+        assert_eq!(get_single_tx_threshold_reached(largest, None, 9), None);
+    }
+}
@@ -0,0 +1,187 @@
+use super::FUEL_CONNECTION_RETRIES;
+use crate::error::{Result, WatchtowerError};
+use crate::rpc_limit::RpcLimiter;
+
+use fuels::{
+    client::{PageDirection, PaginationRequest},
+    prelude::Provider,
+    tx::{Bytes32, Receipt},
+    types::block::Block,
+};
+use std::future::Future;
+
+pub static TRANSACTION_BATCH_SIZE: usize = 16;
+
+// wraps the fuel provider with queries that would otherwise take one round trip per transaction
+#[derive(Clone, Debug)]
+pub struct ExtendedProvider {
+    provider: Provider,
+    limiter: RpcLimiter,
+}
+
+impl ExtendedProvider {
+    pub fn new(provider: Provider, limiter: RpcLimiter) -> Self {
+        ExtendedProvider { provider, limiter }
+    }
+
+    pub async fn get_latest_blocks(&self, num_blocks: usize) -> Result<Vec<Block>> {
+        for i in 0..FUEL_CONNECTION_RETRIES {
+            let req = PaginationRequest {
+                cursor: None,
+                results: num_blocks,
+                direction: PageDirection::Backward,
+            };
+            match self.limiter.run(self.provider.get_blocks(req)).await {
+                Ok(blocks_result) => return Ok(blocks_result.results),
+                Err(e) => {
+                    if i == FUEL_CONNECTION_RETRIES - 1 {
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            FUEL_CONNECTION_RETRIES,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(vec![])
+    }
+
+    // gets the receipts of the given transactions (a single query per transaction, run concurrently in batches)
+    pub async fn get_receipts(&self, tx_ids: &[Bytes32]) -> Result<Vec<Vec<Receipt>>> {
+        let provider = self.provider.clone();
+        let limiter = self.limiter.clone();
+        query_in_batches(tx_ids, |tx_id| get_receipts(provider.clone(), limiter.clone(), tx_id)).await
+    }
+}
+
+// runs a query for each id, with up to a batch worth of queries in flight at once (results keep the order of the ids)
+// (queries still wait for an rpc permit, so max_concurrent_rpc can hold them to fewer)
+async fn query_in_batches<I, T, F, Fut>(ids: &[I], query: F) -> Result<Vec<T>>
+where
+    I: Clone,
+    T: Send + 'static,
+    F: Fn(I) -> Fut,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+{
+    let mut results = Vec::with_capacity(ids.len());
+    for batch in ids.chunks(TRANSACTION_BATCH_SIZE) {
+        let handles: Vec<_> = batch.iter().map(|id| tokio::spawn(query(id.clone()))).collect();
+        for handle in handles {
+            match handle.await {
+                Ok(result) => results.push(result?),
+                Err(e) => return Err(WatchtowerError::ChainData(format!("Failed to query transaction: {e}"))),
+            }
+        }
+    }
+    Ok(results)
+}
+
+async fn get_receipts(provider: Provider, limiter: RpcLimiter, tx_id: Bytes32) -> Result<Vec<Receipt>> {
+    for i in 0..FUEL_CONNECTION_RETRIES {
+        match limiter.run(provider.get_receipts(&tx_id)).await {
+            Ok(receipts) => return Ok(receipts),
+            Err(e) => {
+                if i == FUEL_CONNECTION_RETRIES - 1 {
+                    return Err(WatchtowerError::retries_exhausted(
+                        WatchtowerError::Connection(e.to_string()),
+                        FUEL_CONNECTION_RETRIES,
+                    ));
+                }
+            }
+        }
+    }
+    Ok(vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn query_in_batches_overlaps_round_trips() {
+        let ids: Vec<usize> = (0..40).collect();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let rounds = Arc::new(AtomicUsize::new(0));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let results = query_in_batches(&ids, |id| {
+            let (in_flight, max_in_flight, rounds, calls) =
+                (in_flight.clone(), max_in_flight.clone(), rounds.clone(), calls.clone());
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                // a query starting with nothing else in flight waits on a new round trip
+                if in_flight.fetch_add(1, Ordering::SeqCst) == 0 {
+                    rounds.fetch_add(1, Ordering::SeqCst);
+                }
+                max_in_flight.fetch_max(in_flight.load(Ordering::SeqCst), Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(id * 2)
+            }
+        })
+        .await
+        .unwrap();
+
+        // one query per id with results in order, but only one round trip per batch rather than per transaction
+        assert_eq!(results, ids.iter().map(|id| id * 2).collect::<Vec<_>>());
+        assert_eq!(calls.load(Ordering::SeqCst), ids.len());
+        assert_eq!(rounds.load(Ordering::SeqCst), 3);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), TRANSACTION_BATCH_SIZE);
+    }
+
+    #[tokio::test]
+    async fn query_in_batches_fails_on_any_error() {
+        let ids: Vec<usize> = (0..5).collect();
+        let result = query_in_batches(&ids, |id| async move {
+            match id {
+                3 => Err(WatchtowerError::ChainData(String::from("missing transaction"))),
+                _ => Ok(id),
+            }
+        })
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn query_in_batches_waits_for_rpc_permits() {
+        let ids: Vec<usize> = (0..40).collect();
+        let limiter = RpcLimiter::new(Some(4));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let results = query_in_batches(&ids, |id| {
+            let (limiter, in_flight, max_in_flight) = (limiter.clone(), in_flight.clone(), max_in_flight.clone());
+            async move {
+                limiter
+                    .run(async {
+                        max_in_flight.fetch_max(in_flight.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    })
+                    .await;
+                Ok(id)
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(results, ids);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn receipts_take_one_query_per_transaction() {
+        // nothing answers on this endpoint, so every query fails and is retried
+        let provider = Provider::connect("http://127.0.0.1:9/graphql").await.unwrap();
+        let limiter = RpcLimiter::new(None);
+        let extended_provider = ExtendedProvider::new(provider, limiter.clone());
+
+        assert!(extended_provider.get_receipts(&[Bytes32::default()]).await.is_err());
+        // a single receipts query per attempt (rather than a transaction query and a status query)
+        assert_eq!(limiter.requests() as u64, FUEL_CONNECTION_RETRIES);
+    }
+}
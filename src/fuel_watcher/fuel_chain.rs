@@ -1,36 +1,67 @@
+use super::extended_provider::ExtendedProvider;
 use super::{FUEL_BLOCK_TIME, FUEL_CONNECTION_RETRIES};
+use crate::config::WithdrawalReceiptFilter;
+use crate::error::{Result, WatchtowerError};
+use crate::rpc_limit::RpcLimiter;
 use crate::WatchtowerConfig;
 
-use anyhow::Result;
 use fuels::{
-    client::{PageDirection, PaginationRequest},
     prelude::Provider,
+    tx::{Bytes32, Receipt, ScriptExecutionResult},
 };
+use std::cmp::max;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+// the amounts withdrawn over a range of fuel blocks
+#[derive(Clone, Debug, Default)]
+pub struct WithdrawnAmounts {
+    pub total: u64,
+    pub largest: u64,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub estimated: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct FuelChain {
     provider: Provider,
+    extended_provider: ExtendedProvider,
+    limiter: RpcLimiter,
 }
 
 impl FuelChain {
     pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
         // setup provider and check that it is valid
-        let provider = Provider::connect(&config.fuel_graphql).await?;
-        let provider_result = provider.chain_info().await;
+        let provider = Provider::connect(&config.fuel_graphql)
+            .await
+            .map_err(|e| WatchtowerError::Connection(format!("Invalid fuel graphql endpoint: {e}")))?;
+        let limiter = RpcLimiter::shared(config.max_concurrent_rpc);
+        let provider_result = limiter.run(provider.chain_info()).await;
         match provider_result {
-            Err(e) => Err(anyhow::anyhow!("Invalid fuel graphql endpoint: {e}")),
-            Ok(_) => Ok(FuelChain { provider }),
+            Err(e) => Err(WatchtowerError::Connection(format!(
+                "Invalid fuel graphql endpoint: {e}"
+            ))),
+            Ok(chain_info) => {
+                check_chain_name(&chain_info.name, &config.expected_fuel_chain_name)?;
+                Ok(FuelChain {
+                    extended_provider: ExtendedProvider::new(provider.clone(), limiter.clone()),
+                    provider,
+                    limiter,
+                })
+            }
         }
     }
 
     pub async fn check_connection(&self) -> Result<()> {
         for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.chain_info().await {
+            match self.limiter.run(self.provider.chain_info()).await {
                 Ok(_) => return Ok(()),
                 Err(e) => {
                     if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            FUEL_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -40,25 +71,25 @@ impl FuelChain {
 
     pub async fn get_seconds_since_last_block(&self) -> Result<u32> {
         for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.chain_info().await {
+            match self.limiter.run(self.provider.chain_info()).await {
                 Ok(info) => {
-                    return match info.latest_block.header.time {
-                        Some(time) => {
-                            let last_block_timestamp = (time.timestamp_millis() as u64) / 1000;
-                            let millis_now =
-                                (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64) / 1000;
-                            if millis_now >= last_block_timestamp {
-                                Ok((millis_now - last_block_timestamp) as u32)
-                            } else {
-                                Err(anyhow::anyhow!("Block time is ahead of current time"))
-                            }
-                        }
-                        None => Err(anyhow::anyhow!("Failed to get latest block")),
-                    }
+                    let header = &info.latest_block.header;
+                    let seconds_now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+                        Ok(d) => d.as_secs(),
+                        Err(e) => return Err(WatchtowerError::ChainData(format!("Invalid local time: {e}"))),
+                    };
+                    return get_seconds_since_block(
+                        header.height,
+                        header.time.map(|time| time.timestamp()),
+                        seconds_now,
+                    );
                 }
                 Err(e) => {
                     if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            FUEL_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -66,35 +97,16 @@ impl FuelChain {
         Ok(0)
     }
 
-    pub async fn get_amount_withdrawn(&self, timeframe: u32) -> Result<u64> {
-        let num_blocks = match usize::try_from(timeframe as u64 / FUEL_BLOCK_TIME) {
-            Ok(val) => val,
-            Err(e) => return Err(anyhow::anyhow!("{e}")),
-        };
+    pub async fn get_latest_block_height(&self) -> Result<u64> {
         for i in 0..FUEL_CONNECTION_RETRIES {
-            let req = PaginationRequest {
-                cursor: None,
-                results: num_blocks,
-                direction: PageDirection::Backward,
-            };
-            match self.provider.get_blocks(req).await {
-                Ok(blocks_result) => {
-                    let mut total: u64 = 0;
-                    for block in blocks_result.results {
-                        for tx_id in block.transactions {
-                            match self.get_amount_withdrawn_from_tx(&tx_id.to_string()).await {
-                                Ok(amount) => {
-                                    total += amount;
-                                }
-                                Err(e) => return Err(anyhow::anyhow!("{e}")),
-                            }
-                        }
-                    }
-                    return Ok(total);
-                }
+            match self.limiter.run(self.provider.chain_info()).await {
+                Ok(info) => return Ok(info.latest_block.header.height),
                 Err(e) => {
                     if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            FUEL_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
@@ -102,54 +114,270 @@ impl FuelChain {
         Ok(0)
     }
 
-    pub async fn get_amount_withdrawn_from_tx(&self, tx_id: &str) -> Result<u64> {
+    // gets the total amount withdrawn over the time frame along with the largest single withdrawal
+    // (with a sample rate below 1 only that fraction of blocks is scanned and the total is extrapolated)
+    pub async fn get_amount_withdrawn(
+        &self,
+        timeframe: u32,
+        sample_rate: f64,
+        receipt_filter: WithdrawalReceiptFilter,
+    ) -> Result<WithdrawnAmounts> {
+        // always scan at least the latest block
+        let num_blocks = match usize::try_from(max(timeframe as u64 / FUEL_BLOCK_TIME, 1)) {
+            Ok(val) => val,
+            Err(e) => return Err(WatchtowerError::Config(format!("Invalid time frame: {e}"))),
+        };
+        let blocks = self.extended_provider.get_latest_blocks(num_blocks).await?;
+        let sampled_blocks: Vec<_> = get_sampled_indexes(blocks.len(), sample_rate)
+            .into_iter()
+            .map(|i| &blocks[i])
+            .collect();
+        let tx_ids: Vec<Bytes32> = sampled_blocks
+            .iter()
+            .flat_map(|block| block.transactions.iter().copied())
+            .collect();
+        let receipts = self.extended_provider.get_receipts(&tx_ids).await?;
+
+        let mut withdrawn = WithdrawnAmounts {
+            start_height: blocks.iter().map(|block| block.header.height).min().unwrap_or(0),
+            end_height: blocks.iter().map(|block| block.header.height).max().unwrap_or(0),
+            estimated: sampled_blocks.len() < blocks.len(),
+            ..WithdrawnAmounts::default()
+        };
+        for (tx_id, tx_receipts) in tx_ids.iter().zip(&receipts) {
+            let amount = FuelChain::get_amount_withdrawn_from_receipts(tx_id, tx_receipts, receipt_filter);
+            withdrawn.total += amount;
+            withdrawn.largest = max(withdrawn.largest, amount);
+        }
+        if withdrawn.estimated && !sampled_blocks.is_empty() {
+            let total = withdrawn.total as u128 * blocks.len() as u128 / sampled_blocks.len() as u128;
+            withdrawn.total = u64::try_from(total).unwrap_or(u64::MAX);
+        }
+        Ok(withdrawn)
+    }
+
+    // sums the base asset sent out to ethereum in a transaction's message receipts
+    // (transactions skipped by the filter are logged so any under counting doesn't go unnoticed)
+    pub fn get_amount_withdrawn_from_receipts(
+        tx_id: &Bytes32,
+        receipts: &[Receipt],
+        receipt_filter: WithdrawalReceiptFilter,
+    ) -> u64 {
+        let amount: u64 = receipts
+            .iter()
+            .map(|receipt| match receipt {
+                Receipt::MessageOut { amount, .. } => *amount,
+                _ => 0,
+            })
+            .sum();
+        let has_messages = receipts
+            .iter()
+            .any(|receipt| matches!(receipt, Receipt::MessageOut { .. }));
+        if !has_messages {
+            return amount;
+        }
+
+        // only script transactions end with a script result
+        let script_result = receipts.iter().find_map(|receipt| match receipt {
+            Receipt::ScriptResult { result, .. } => Some(*result),
+            _ => None,
+        });
+        get_filtered_amount(tx_id, amount, script_result, receipt_filter)
+    }
+
+    pub async fn verify_block_commit(&self, block_height: u64, block_hash: &str) -> Result<bool> {
         for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.get_transaction_by_id(&tx_id).await {
-                Ok(tx_result) => {
-                    match tx_result {
-                        Some(tx) => {
-                            // TODO
-
-                            return Ok(0); ///////////////////////////////
-                        }
-                        None => {
-                            if i == FUEL_CONNECTION_RETRIES - 1 {
-                                return Err(anyhow::anyhow!("Failed to find details for transaction: {tx_id}"));
-                            }
-                        }
-                    }
+            match self.limiter.run(self.provider.block(block_hash)).await {
+                Ok(block_result) => {
+                    return Ok(is_committed_block(
+                        block_result.map(|block| block.header.height),
+                        block_height,
+                    ))
                 }
                 Err(e) => {
                     if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+                        return Err(WatchtowerError::retries_exhausted(
+                            WatchtowerError::Connection(e.to_string()),
+                            FUEL_CONNECTION_RETRIES,
+                        ));
                     }
                 }
             }
         }
+        Ok(true)
+    }
+}
 
-        Ok(0)
+// gets the amount to count from a transaction with message receipts under the receipt filter, given its script
+// result (none for a transaction that isn't a script)
+fn get_filtered_amount(
+    tx_id: &Bytes32,
+    amount: u64,
+    script_result: Option<ScriptExecutionResult>,
+    receipt_filter: WithdrawalReceiptFilter,
+) -> u64 {
+    match (script_result, receipt_filter) {
+        (Some(ScriptExecutionResult::Success), _) => amount,
+        (_, WithdrawalReceiptFilter::All) => {
+            log::warn!(
+                "Counting {amount} withdrawn in transaction {tx_id} which is not a successful script (result: {:?}).",
+                script_result
+            );
+            amount
+        }
+        (_, WithdrawalReceiptFilter::SuccessfulScripts) => {
+            log::warn!(
+                "Skipping {amount} withdrawn in transaction {tx_id} which is not a successful script (result: {:?}).",
+                script_result
+            );
+            0
+        }
     }
+}
 
-    pub async fn verify_block_commit(&self, block_hash: &str) -> Result<bool> {
-        for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.block(block_hash).await {
-                Ok(_) => return Ok(true),
-                Err(e) => {
-                    if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
-                    }
-                }
+// checks the endpoint is on the expected chain (when one is configured)
+fn check_chain_name(chain_name: &str, expected_chain_name: &Option<String>) -> Result<()> {
+    match expected_chain_name {
+        Some(expected) if chain_name != expected => Err(WatchtowerError::WrongChain(format!(
+            "fuel graphql endpoint is on chain {chain_name} but expected_fuel_chain_name is {expected}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// checks a commit names a real fuel block at the committed height (given the height of the block with the committed
+// hash, if fuel has one)
+fn is_committed_block(found_height: Option<u64>, commit_height: u64) -> bool {
+    match found_height {
+        Some(found_height) => found_height == commit_height,
+        None => false,
+    }
+}
+
+// picks which of the blocks to scan so the given fraction of them is scanned, spread evenly over the range
+// (block i is taken whenever floor(i * sample_rate) advances, and the first block is always taken)
+fn get_sampled_indexes(num_blocks: usize, sample_rate: f64) -> Vec<usize> {
+    (0..num_blocks)
+        .filter(|i| *i == 0 || (*i as f64 * sample_rate).floor() > ((*i - 1) as f64 * sample_rate).floor())
+        .collect()
+}
+
+// gets how long ago a block was produced from its header timestamp
+// (a malformed block header is reported as bad chain data rather than taking down the watcher)
+fn get_seconds_since_block(height: u64, timestamp: Option<i64>, seconds_now: u64) -> Result<u32> {
+    let last_block_timestamp = match timestamp {
+        Some(timestamp) if timestamp >= 0 => timestamp as u64,
+        Some(timestamp) => {
+            return Err(WatchtowerError::ChainData(format!(
+                "Latest fuel block (height {height}) has an invalid timestamp: {timestamp}"
+            )))
+        }
+        None => {
+            return Err(WatchtowerError::ChainData(format!(
+                "Latest fuel block (height {height}) has no timestamp"
+            )))
+        }
+    };
+    match seconds_now >= last_block_timestamp {
+        true => Ok((seconds_now - last_block_timestamp) as u32),
+        false => Err(WatchtowerError::ClockSkew(last_block_timestamp - seconds_now)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling_takes_every_block_at_full_rate() {
+        assert_eq!(get_sampled_indexes(5, 1.0), vec![0, 1, 2, 3, 4]);
+        assert!(get_sampled_indexes(0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn sampling_spreads_blocks_evenly() {
+        assert_eq!(get_sampled_indexes(12, 0.25), vec![0, 4, 8]);
+        assert_eq!(get_sampled_indexes(10, 0.5), vec![0, 2, 4, 6, 8]);
+        assert_eq!(get_sampled_indexes(1, 0.1), vec![0]);
+    }
+
+    #[test]
+    fn sampling_follows_rates_above_one_half() {
+        // rounding 1 / rate to a whole interval would scan every block or every other block here
+        assert_eq!(get_sampled_indexes(10, 0.6), vec![0, 2, 4, 5, 7, 9]);
+        assert_eq!(get_sampled_indexes(100, 0.6).len(), 60);
+        assert_eq!(get_sampled_indexes(100, 0.75).len(), 75);
+        assert_eq!(get_sampled_indexes(100, 0.9).len(), 90);
+    }
+
+    #[test]
+    fn block_age_comes_from_the_header_timestamp() {
+        assert_eq!(get_seconds_since_block(7, Some(1_000), 1_030).unwrap(), 30);
+        assert_eq!(get_seconds_since_block(7, Some(1_000), 1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn missing_or_negative_header_timestamp_is_bad_chain_data() {
+        match get_seconds_since_block(7, None, 1_000) {
+            Err(WatchtowerError::ChainData(message)) => {
+                assert!(message.contains("height 7") && message.contains("no timestamp"))
             }
+            other => panic!("expected a chain data error, got {other:?}"),
+        }
+        match get_seconds_since_block(7, Some(-1), 1_000) {
+            Err(WatchtowerError::ChainData(message)) => assert!(message.contains("invalid timestamp: -1")),
+            other => panic!("expected a chain data error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn header_timestamp_ahead_of_the_local_clock_is_clock_skew() {
+        match get_seconds_since_block(7, Some(1_045), 1_000) {
+            Err(WatchtowerError::ClockSkew(seconds)) => assert_eq!(seconds, 45),
+            other => panic!("expected clock skew, got {other:?}"),
         }
-        Ok(true)
     }
 
-    pub fn get_value(value_fp: f64, decimals: u8) -> u64 {
-        let decimals_p1 = if decimals < 9 { decimals } else { decimals - 9 };
-        let decimals_p2 = decimals - decimals_p1;
+    #[test]
+    fn commit_must_match_the_height_of_the_block_it_names() {
+        assert!(is_committed_block(Some(1_200), 1_200));
+
+        // the hash is a real block, but not the one at the committed height
+        assert!(!is_committed_block(Some(1_199), 1_200));
+        assert!(!is_committed_block(Some(1_201), 1_200));
+
+        // fuel has no block with the hash at all
+        assert!(!is_committed_block(None, 1_200));
+    }
+
+    #[test]
+    fn fuel_chain_name_must_match_the_expected_one() {
+        assert!(check_chain_name("beta-3", &Some(String::from("beta-3"))).is_ok());
+        assert!(check_chain_name("beta-3", &None).is_ok());
+        match check_chain_name("beta-3", &Some(String::from("mainnet"))) {
+            Err(WatchtowerError::WrongChain(message)) => assert_eq!(
+                message,
+                "fuel graphql endpoint is on chain beta-3 but expected_fuel_chain_name is mainnet"
+            ),
+            other => panic!("expected a wrong chain error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn non_script_withdrawals_are_only_counted_without_the_script_filter() {
+        let tx_id = Bytes32::default();
+        let count = |script_result, receipt_filter| get_filtered_amount(&tx_id, 5, script_result, receipt_filter);
+
+        // a transaction that isn't a script never has a script result
+        assert_eq!(count(None, WithdrawalReceiptFilter::All), 5);
+        assert_eq!(count(None, WithdrawalReceiptFilter::SuccessfulScripts), 0);
 
-        let value = value_fp * (10.0 as f64).powf(decimals_p1 as f64);
-        let value = (value as u64) * (10 as u64).pow(decimals_p2 as u32);
-        value
+        // a successful script counts under either filter, a failed one only when counting everything
+        let success = Some(ScriptExecutionResult::Success);
+        assert_eq!(count(success, WithdrawalReceiptFilter::All), 5);
+        assert_eq!(count(success, WithdrawalReceiptFilter::SuccessfulScripts), 5);
+        let revert = Some(ScriptExecutionResult::Revert);
+        assert_eq!(count(revert, WithdrawalReceiptFilter::All), 5);
+        assert_eq!(count(revert, WithdrawalReceiptFilter::SuccessfulScripts), 0);
     }
 }
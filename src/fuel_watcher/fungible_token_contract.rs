@@ -1,7 +1,8 @@
+use super::fuel_chain::WithdrawnAmounts;
 use super::{FUEL_BLOCK_TIME, FUEL_CONNECTION_RETRIES};
+use crate::error::Result;
 use crate::WatchtowerConfig;
 
-use anyhow::Result;
 use std::cmp::max;
 
 #[derive(Clone, Debug)]
@@ -12,10 +13,11 @@ impl FungibleTokenContract {
         Ok(FungibleTokenContract {})
     }
 
-    pub async fn get_amount_withdrawn(&self, timeframe: u32, token_address: &str) -> Result<u64> {
+    // gets the total amount withdrawn over the time frame along with the largest single withdrawal
+    pub async fn get_amount_withdrawn(&self, timeframe: u32, token_address: &str) -> Result<WithdrawnAmounts> {
         let block_offset = timeframe as u64 / FUEL_BLOCK_TIME;
         // TODO
 
-        Ok(0)
+        Ok(WithdrawnAmounts::default())
     }
 }
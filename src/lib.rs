@@ -1,18 +1,130 @@
 mod alerts;
 mod config;
+mod deadmanswitch;
+mod email;
+mod error;
 mod ethereum_actions;
 mod ethereum_watcher;
 mod fuel_watcher;
+mod message_bus;
+mod preflight;
+mod reload;
+mod rpc_limit;
+mod teams;
+#[cfg(test)]
+mod test_utils;
+mod token_amount;
 
-pub use config::{load_config, WatchtowerConfig};
+pub use alerts::AlertLevel;
+pub use config::{
+    config_json_schema, load_config, load_configs, redact_secrets, EthereumClientWatcher, FuelClientWatcher,
+    MessageBusKind, WatchtowerConfig, WatchtowerConfigBuilder,
+};
+pub use error::WatchtowerError;
+pub use preflight::{preflight, validate_config_file};
 
-use alerts::{AlertLevel, WatchtowerAlerts};
+use alerts::{AlertType, WatchtowerAlerts};
 use anyhow::Result;
+use deadmanswitch::DeadManSwitchClient;
 use ethereum_actions::WatchtowerEthereumActions;
 use ethereum_watcher::start_ethereum_watcher;
 use fuel_watcher::start_fuel_watcher;
+use reload::{start_config_reloader, LiveWatchConfig};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::signal::unix::{signal, SignalKind};
+
+static RECENT_ALERTS_ON_FAILURE: usize = 20;
+
+// same as run_all, but on a runtime built from the runtime settings in the config
+// (the runtime is shared by every deployment so only the first config's settings are used)
+pub fn run_all_blocking(configs: &[WatchtowerConfig], config_file: Option<&str>) -> Result<()> {
+    let runtime = build_runtime(configs)?;
+    runtime.block_on(run_all(configs, config_file))
+}
+
+// builds the runtime from the first config's worker_threads and max_blocking_threads (tokio's defaults otherwise)
+// the checks mostly wait on rpc calls so a few workers are usually enough, more help when many deployments or long
+// scans run at once but each costs a thread
+fn build_runtime(configs: &[WatchtowerConfig]) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    match configs.first() {
+        Some(config) => {
+            match config.worker_threads {
+                Some(worker_threads) => {
+                    builder.worker_threads(worker_threads);
+                }
+                None => {}
+            }
+            match config.max_blocking_threads {
+                Some(max_blocking_threads) => {
+                    builder.max_blocking_threads(max_blocking_threads);
+                }
+                None => {}
+            }
+        }
+        None => {}
+    }
+    builder.build()
+}
+
+// same as run_all_blocking for a single deployment
+pub fn run_blocking(config: &WatchtowerConfig) -> Result<()> {
+    run_all_blocking(std::slice::from_ref(config), None)
+}
+
+// runs an independent watchtower for each bridge deployment, returning once they have all stopped
+// (watcher settings are reloaded from the config file on SIGHUP when one is given)
+pub async fn run_all(configs: &[WatchtowerConfig], config_file: Option<&str>) -> Result<()> {
+    let mut handles = vec![];
+    let mut deployments = vec![];
+    for config in configs {
+        let config = config.clone();
+        let live_watch_config = LiveWatchConfig::new(&config);
+        deployments.push((config.clone(), live_watch_config.clone()));
+        handles.push(tokio::spawn(async move {
+            let result = run_deployment(&config, live_watch_config).await;
+            (config, result)
+        }));
+    }
+    match config_file {
+        Some(config_file) => {
+            start_config_reloader(config_file, deployments)?;
+        }
+        None => {}
+    }
+
+    let mut failures = vec![];
+    for handle in handles {
+        match handle.await {
+            Ok((_, Ok(_))) => {}
+            Ok((config, Err(e))) => {
+                let name = config.deployment_name.clone().unwrap_or(String::from("default"));
+                failures.push(format!(
+                    "[{name}] {}",
+                    redact_secrets(&e.to_string(), &config.secrets())
+                ));
+            }
+            Err(e) => failures.push(format!("Watchtower thread failed: {e}")),
+        }
+    }
+    if !failures.is_empty() {
+        return Err(anyhow::anyhow!(failures.join(" ")));
+    }
+    Ok(())
+}
 
 pub async fn run(config: &WatchtowerConfig) -> Result<()> {
+    run_deployment(config, LiveWatchConfig::new(config)).await
+}
+
+// same as run, but watching the given live watcher settings (so a reloader can swap them out)
+pub(crate) async fn run_deployment(config: &WatchtowerConfig, live_watch_config: LiveWatchConfig) -> Result<()> {
+    // optionally check every integration before starting
+    if config.preflight {
+        preflight(config).await?;
+    }
+
     // build alerts service
     let alerts_result = WatchtowerAlerts::new(config);
     if alerts_result.is_err() {
@@ -22,6 +134,12 @@ pub async fn run(config: &WatchtowerConfig) -> Result<()> {
         ));
     }
     let alerts = alerts_result.unwrap();
+    alerts
+        .lifecycle(format!("Watchtower started. {}", get_lifecycle_details(config)))
+        .await;
+
+    // echo back what is being watched
+    alerts.alert(config.get_summary(), AlertLevel::Info, AlertType::StartupSummary);
 
     // build ethereum actions service
     let actions_result = WatchtowerEthereumActions::new(config, alerts.clone()).await;
@@ -33,8 +151,18 @@ pub async fn run(config: &WatchtowerConfig) -> Result<()> {
     }
     let actions = actions_result.unwrap();
 
+    // build dead man's switch client (pinged by the watchers after each healthy cycle)
+    let deadmanswitch = DeadManSwitchClient::from_config(config)?;
+
     // start fuel watcher
-    let fuel_watcher_result = start_fuel_watcher(config, actions.clone(), alerts.clone()).await;
+    let fuel_watcher_result = start_fuel_watcher(
+        config,
+        live_watch_config.fuel_client_watcher.clone(),
+        actions.clone(),
+        alerts.clone(),
+        deadmanswitch.clone(),
+    )
+    .await;
     if fuel_watcher_result.is_err() {
         return Err(anyhow::anyhow!(
             "Failed to start fuel watcher: {}",
@@ -43,8 +171,18 @@ pub async fn run(config: &WatchtowerConfig) -> Result<()> {
     }
     let fuel_thread = fuel_watcher_result.unwrap();
 
+    // stagger the start of the ethereum watcher so the two watchers don't hit shared infra at the same time
+    tokio::time::sleep(get_startup_delay(config)).await;
+
     // start ethereum watcher
-    let ethereum_watcher_result = start_ethereum_watcher(config, actions.clone(), alerts.clone()).await;
+    let ethereum_watcher_result = start_ethereum_watcher(
+        config,
+        live_watch_config.ethereum_client_watcher.clone(),
+        actions.clone(),
+        alerts.clone(),
+        deadmanswitch.clone(),
+    )
+    .await;
     if ethereum_watcher_result.is_err() {
         return Err(anyhow::anyhow!(
             "Failed to start ethereum watcher: {}",
@@ -54,20 +192,226 @@ pub async fn run(config: &WatchtowerConfig) -> Result<()> {
     let ethereum_thread = ethereum_watcher_result.unwrap();
 
     // wait for threads to finish (if ever)
-    match ethereum_thread.await {
-        Err(e) => {
-            alerts.alert(String::from("Ethereum watcher thread failed."), AlertLevel::Error);
-            return Err(anyhow::anyhow!("Ethereum watcher thread failed: {}", e));
+    let watchers = async {
+        match ethereum_thread.await {
+            Err(e) => {
+                log_recent_alerts(&alerts);
+                alerts.alert(
+                    String::from("Ethereum watcher thread failed."),
+                    AlertLevel::Error,
+                    AlertType::EthereumWatcherThreadFailure,
+                );
+                return Err(anyhow::anyhow!("Ethereum watcher thread failed: {}", e));
+            }
+            Ok(_) => {}
+        }
+        match fuel_thread.await {
+            Err(e) => {
+                log_recent_alerts(&alerts);
+                alerts.alert(
+                    String::from("Fuel watcher thread failed."),
+                    AlertLevel::Error,
+                    AlertType::FuelWatcherThreadFailure,
+                );
+                return Err(anyhow::anyhow!("Fuel watcher thread failed: {}", e));
+            }
+            Ok(_) => {}
         }
-        Ok(_) => {}
+        Ok(())
+    };
+
+    // stop early if the process is asked to shut down
+    tokio::select! {
+        result = watchers => result?,
+        _ = shutdown_signal() => log::info!("Shutdown requested."),
     }
-    match fuel_thread.await {
+    alerts
+        .lifecycle(format!("Watchtower stopped. {}", get_lifecycle_details(config)))
+        .await;
+
+    Ok(())
+}
+
+// describes the running watchtower for lifecycle alerts
+fn get_lifecycle_details(config: &WatchtowerConfig) -> String {
+    format!(
+        "Version: {}, config hash: {}, deployment: {}",
+        env!("CARGO_PKG_VERSION"),
+        config.config_hash(),
+        config.deployment_name.as_deref().unwrap_or("default")
+    )
+}
+
+// resolves once the process receives ctrl-c or SIGTERM (never, if the signals can't be listened for)
+async fn shutdown_signal() {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
         Err(e) => {
-            alerts.alert(String::from("Fuel watcher thread failed."), AlertLevel::Error);
-            return Err(anyhow::anyhow!("Fuel watcher thread failed: {}", e));
+            log::warn!("Failed to listen for shutdown signals: {e}");
+            return std::future::pending().await;
         }
-        Ok(_) => {}
+    };
+    tokio::select! {
+        Ok(()) = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+// logs the recent alert history to give context on a failure
+fn log_recent_alerts(alerts: &WatchtowerAlerts) {
+    for record in alerts.recent_alerts(RECENT_ALERTS_ON_FAILURE) {
+        let age = record.time.elapsed().unwrap_or_default().as_secs();
+        log::info!(
+            "Recent {:?} alert ({:?}, {}s ago): {}",
+            record.level,
+            record.alert_type,
+            age,
+            record.text
+        );
     }
+}
 
-    Ok(())
+// gets how long to wait after starting the fuel watcher before starting the ethereum watcher (the configured
+// stagger plus up to as much again in jitter)
+fn get_startup_delay(config: &WatchtowerConfig) -> Duration {
+    let startup_stagger = Duration::from_millis(config.startup_stagger_ms);
+    startup_stagger + get_jitter(startup_stagger)
+}
+
+// gets a pseudo random duration up to the given max (good enough to keep loops from aligning)
+fn get_jitter(max: Duration) -> Duration {
+    let max_millis = max.as_millis() as u64;
+    if max_millis == 0 {
+        return Duration::ZERO;
+    }
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos() as u64;
+    Duration::from_millis(nanos % max_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{abi_bool, test_config_builder, MockHttp, MockRpc};
+
+    fn deployment_config(name: &str) -> WatchtowerConfig {
+        WatchtowerConfigBuilder::new()
+            .deployment_name(name)
+            .fuel_graphql("http://localhost:4000/graphql")
+            .ethereum_rpc("http://localhost:8545")
+            .contract_addresses(
+                "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512",
+                "0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0",
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn deployments_alert_independently() {
+        let testnet = WatchtowerAlerts::new(&deployment_config("testnet")).unwrap();
+        let mainnet = WatchtowerAlerts::new(&deployment_config("mainnet")).unwrap();
+        testnet.alert(
+            String::from("Testnet commit is invalid."),
+            AlertLevel::Warn,
+            AlertType::EthereumInvalidStateCommit,
+        );
+        mainnet.alert(
+            String::from("Mainnet block production stopped."),
+            AlertLevel::Error,
+            AlertType::FuelBlockProductionStopped,
+        );
+
+        // both alert threads run at once on the shared runtime
+        for _ in 0..50 {
+            if !testnet.recent_alerts(10).is_empty() && !mainnet.recent_alerts(10).is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let testnet_alerts: Vec<String> = testnet.recent_alerts(10).into_iter().map(|alert| alert.text).collect();
+        let mainnet_alerts: Vec<String> = mainnet.recent_alerts(10).into_iter().map(|alert| alert.text).collect();
+        assert_eq!(
+            testnet_alerts,
+            vec![String::from("[testnet] Testnet commit is invalid.")]
+        );
+        assert_eq!(
+            mainnet_alerts,
+            vec![String::from("[mainnet] Mainnet block production stopped.")]
+        );
+    }
+
+    #[test]
+    fn runtime_uses_the_first_deployments_worker_count() {
+        let mut testnet = deployment_config("testnet");
+        testnet.worker_threads = Some(3);
+        testnet.max_blocking_threads = Some(4);
+        let mut mainnet = deployment_config("mainnet");
+        mainnet.worker_threads = Some(8);
+
+        let runtime = build_runtime(&[testnet, mainnet]).unwrap();
+        assert_eq!(runtime.metrics().num_workers(), 3);
+
+        // left unset, tokio picks the worker count
+        let default_runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+        let runtime = build_runtime(&[deployment_config("default")]).unwrap();
+        assert_eq!(runtime.metrics().num_workers(), default_runtime.metrics().num_workers());
+    }
+
+    #[test]
+    fn ethereum_watcher_starts_at_least_the_stagger_after_the_fuel_watcher() {
+        let mut config = deployment_config("testnet");
+        config.startup_stagger_ms = 500;
+        let stagger = Duration::from_millis(500);
+        for _ in 0..20 {
+            let delay = get_startup_delay(&config);
+            assert!(delay >= stagger && delay < stagger * 2, "{delay:?}");
+        }
+
+        // no stagger starts both at once
+        config.startup_stagger_ms = 0;
+        assert_eq!(get_startup_delay(&config), Duration::ZERO);
+    }
+
+    #[test]
+    fn jitter_stays_under_the_max() {
+        let max = Duration::from_millis(3);
+        assert!((0..50).all(|_| get_jitter(max) < max));
+        assert_eq!(get_jitter(Duration::ZERO), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn start_is_pushed_exactly_once() {
+        let webhook = MockHttp::start(vec![]);
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        // nothing answers on the fuel endpoint, so the deployment stops once it tries to start the fuel watcher
+        let config = test_config_builder(&rpc.url)
+            .deployment_name("testnet")
+            .fuel_graphql("http://127.0.0.1:9/graphql")
+            .teams_webhook_url(&webhook.url)
+            .min_notify_level(AlertLevel::Error)
+            .build()
+            .unwrap();
+
+        let result = run_deployment(&config, LiveWatchConfig::new(&config)).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Failed to start fuel watcher"));
+        let lifecycle_alerts: Vec<String> = webhook
+            .requests()
+            .into_iter()
+            .map(|request| request.body)
+            .filter(|body| body.contains("Watchtower started. Version"))
+            .collect();
+        assert_eq!(lifecycle_alerts.len(), 1);
+        assert!(lifecycle_alerts[0].contains(&get_lifecycle_details(&config)));
+        assert!(!webhook
+            .requests()
+            .iter()
+            .any(|request| request.body.contains("Watchtower stopped.")));
+    }
 }
@@ -1,16 +1,50 @@
+use log::LevelFilter;
+use log4rs::append::console::{ConsoleAppender, Target};
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
 use std::env;
+use std::process;
 
 pub static WATCHTOWER_CONFIG_FILE: &str = "watchtower_config.json";
 pub static LOGGING_CONFIG_FILE: &str = "logging_config.yaml";
+pub static CONFIG_SCHEMA_ARG: &str = "--config-schema";
+pub static CHECK_ARG: &str = "--check";
+pub static PREFLIGHT_ARG: &str = "--preflight";
+
+// the runtime is built by the library so its worker settings can come from the config
+fn main() {
+    // print the config json schema instead of running (for editor validation)
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 1 && args[1] == CONFIG_SCHEMA_ARG {
+        let schema = fuel_canary_watchtower::config_json_schema();
+        println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+        return;
+    }
+
+    // only validate the config (for ci), optionally checking connectivity too
+    if args.len() > 1 && args[1] == CHECK_ARG {
+        let config_file = match args.get(2) {
+            Some(arg) if arg != PREFLIGHT_ARG => arg.as_str(),
+            _ => WATCHTOWER_CONFIG_FILE,
+        };
+        let connectivity = args.iter().any(|arg| arg == PREFLIGHT_ARG);
+        init_stderr_logging();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        match runtime.block_on(fuel_canary_watchtower::validate_config_file(config_file, connectivity)) {
+            Ok(_) => println!("Config {} is valid.", config_file),
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
-#[tokio::main]
-async fn main() {
     // setup logging
     log4rs::init_file(LOGGING_CONFIG_FILE, Default::default()).unwrap();
 
     // determine the config file to use
     let mut config_file = WATCHTOWER_CONFIG_FILE;
-    let args: Vec<String> = env::args().collect();
     if args.len() > 1 {
         let arg = &args[1];
         if arg.ends_with(".json") {
@@ -24,17 +58,31 @@ async fn main() {
         log::info!("Using default config file: {}", config_file);
     }
 
-    // get the watchtower config
-    let config_result = fuel_canary_watchtower::load_config(config_file);
-    if config_result.is_err() {
-        log::error!("Failed to load config: {}", config_result.err().unwrap());
+    // get the watchtower config (one per bridge deployment)
+    let configs_result = fuel_canary_watchtower::load_configs(config_file);
+    if configs_result.is_err() {
+        log::error!("Failed to load config: {}", configs_result.err().unwrap());
     } else {
-        let config = config_result.unwrap();
+        let configs = configs_result.unwrap();
 
-        // start the watchtower
-        let run_result = fuel_canary_watchtower::run(&config).await;
+        // start the watchtowers (errors are already redacted, SIGHUP reloads the watcher settings)
+        let run_result = fuel_canary_watchtower::run_all_blocking(&configs, Some(config_file));
         if run_result.is_err() {
             log::error!("{}", run_result.err().unwrap());
         }
     }
 }
+
+// logs to stderr so config warnings show up when only checking the config
+// (without writing to the log file or needing the logging config to be present)
+fn init_stderr_logging() {
+    let stderr = ConsoleAppender::builder()
+        .target(Target::Stderr)
+        .encoder(Box::new(PatternEncoder::new("[{l}] - {m}{n}")))
+        .build();
+    let config = Config::builder()
+        .appender(Appender::builder().build("stderr", Box::new(stderr)))
+        .build(Root::builder().appender("stderr").build(LevelFilter::Info))
+        .unwrap();
+    log4rs::init_config(config).unwrap();
+}
@@ -0,0 +1,202 @@
+use crate::alerts::{AlertLevel, AlertType, Chain};
+use crate::config::MessageBusKind;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+static KAFKA_DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// publishes payloads to a subject (or topic) on a message bus
+// (kept separate from the client so messages can be built without a real bus)
+#[async_trait]
+pub trait MessageBusTransport: Send + Sync {
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()>;
+}
+
+// connects on first publish so an unreachable bus doesn't stop the watchtower from starting
+pub struct NatsTransport {
+    servers: String,
+    client: OnceCell<async_nats::Client>,
+}
+
+impl NatsTransport {
+    pub fn new(servers: &str) -> Self {
+        NatsTransport {
+            servers: String::from(servers),
+            client: OnceCell::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageBusTransport for NatsTransport {
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        let client = self
+            .client
+            .get_or_try_init(|| async_nats::connect(self.servers.as_str()))
+            .await?;
+        client.publish(String::from(subject), payload.into()).await?;
+        client.flush().await?;
+        Ok(())
+    }
+}
+
+pub struct KafkaTransport {
+    producer: FutureProducer,
+}
+
+impl KafkaTransport {
+    pub fn new(servers: &str) -> Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", servers)
+            .set("message.timeout.ms", KAFKA_DELIVERY_TIMEOUT.as_millis().to_string())
+            .create()?;
+        Ok(KafkaTransport { producer })
+    }
+}
+
+#[async_trait]
+impl MessageBusTransport for KafkaTransport {
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        let record = FutureRecord::<(), Vec<u8>>::to(subject).payload(&payload);
+        match self.producer.send(record, KAFKA_DELIVERY_TIMEOUT).await {
+            Ok(_) => Ok(()),
+            Err((e, _)) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MessageBusClient {
+    transport: Arc<dyn MessageBusTransport>,
+    subject: String,
+    system_name: String,
+}
+
+impl MessageBusClient {
+    // builds a client from the message bus settings in the config (none if no message bus is configured)
+    pub fn from_config(config: &WatchtowerConfig) -> Result<Option<Self>> {
+        let message_bus = match &config.message_bus {
+            Some(message_bus) => message_bus,
+            None => return Ok(None),
+        };
+        let transport: Arc<dyn MessageBusTransport> = match message_bus.bus {
+            MessageBusKind::Nats => Arc::new(NatsTransport::new(&message_bus.servers)),
+            MessageBusKind::Kafka => Arc::new(KafkaTransport::new(&message_bus.servers)?),
+        };
+        Ok(Some(MessageBusClient::new(
+            transport,
+            &message_bus.subject,
+            &config.system_name,
+        )))
+    }
+
+    pub fn new(transport: Arc<dyn MessageBusTransport>, subject: &str, system_name: &str) -> Self {
+        MessageBusClient {
+            transport,
+            subject: String::from(subject),
+            system_name: String::from(system_name),
+        }
+    }
+
+    pub async fn publish_alert(
+        &self,
+        text: &str,
+        level: &AlertLevel,
+        alert_type: AlertType,
+        chain: Chain,
+        context: Option<&Value>,
+    ) -> Result<()> {
+        let payload = self.build_payload(text, level, alert_type, chain, context);
+        self.transport
+            .publish(&self.subject, serde_json::to_vec(&payload)?)
+            .await
+    }
+
+    // builds the json message for an alert (the same fields downstream consumers would otherwise parse from Teams)
+    pub fn build_payload(
+        &self,
+        text: &str,
+        level: &AlertLevel,
+        alert_type: AlertType,
+        chain: Chain,
+        context: Option<&Value>,
+    ) -> Value {
+        json!({
+            "system": self.system_name,
+            "text": text,
+            "level": format!("{:?}", level),
+            "alert_type": format!("{:?}", alert_type),
+            "chain": format!("{:?}", chain),
+            "context": context,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::FakeMessageBus;
+
+    #[tokio::test]
+    async fn alert_is_published_to_the_subject_as_json() {
+        let bus = Arc::new(FakeMessageBus::default());
+        let client = MessageBusClient::new(bus.clone(), "watchtower.alerts", "Fuel Canary Watchtower");
+        let context = json!({ "amount": "12.5" });
+        client
+            .publish_alert(
+                "Deposit over the limit.",
+                &AlertLevel::Warn,
+                AlertType::EthereumPortalDeposit,
+                Chain::Ethereum,
+                Some(&context),
+            )
+            .await
+            .unwrap();
+        client
+            .publish_alert(
+                "Watchtower started.",
+                &AlertLevel::Info,
+                AlertType::Lifecycle,
+                Chain::None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            bus.published(),
+            vec![
+                (
+                    String::from("watchtower.alerts"),
+                    json!({
+                        "system": "Fuel Canary Watchtower",
+                        "text": "Deposit over the limit.",
+                        "level": "Warn",
+                        "alert_type": "EthereumPortalDeposit",
+                        "chain": "Ethereum",
+                        "context": { "amount": "12.5" },
+                    })
+                ),
+                (
+                    String::from("watchtower.alerts"),
+                    json!({
+                        "system": "Fuel Canary Watchtower",
+                        "text": "Watchtower started.",
+                        "level": "Info",
+                        "alert_type": "Lifecycle",
+                        "chain": "None",
+                        "context": null,
+                    })
+                ),
+            ]
+        );
+    }
+}
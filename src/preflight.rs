@@ -0,0 +1,213 @@
+use crate::config::{load_configs, redact_secrets};
+use crate::email::EmailClient;
+use crate::ethereum_watcher::ethereum_chain::EthereumChain;
+use crate::ethereum_watcher::gateway_contract::GatewayContract;
+use crate::ethereum_watcher::portal_contract::PortalContract;
+use crate::ethereum_watcher::state_contract::StateContract;
+use crate::fuel_watcher::fuel_chain::FuelChain;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use reqwest::Client;
+
+// verifies every configured integration up front so a misconfiguration is reported at startup (all at once)
+// rather than discovered mid-operation
+pub async fn preflight(config: &WatchtowerConfig) -> Result<()> {
+    let mut failures: Vec<String> = vec![];
+
+    // ethereum rpc is reachable and on the expected chain
+    match EthereumChain::new(config).await {
+        Ok(_) => {}
+        Err(e) => failures.push(format!("Ethereum RPC: {e}")),
+    }
+
+    // fuel graphql is reachable and on the expected chain
+    match FuelChain::new(config).await {
+        Ok(_) => {}
+        Err(e) => failures.push(format!("Fuel GraphQL: {e}")),
+    }
+
+    // all contracts initialize
+    match StateContract::new(config).await {
+        Ok(_) => {}
+        Err(e) => failures.push(format!("State contract: {e}")),
+    }
+    match GatewayContract::new(config).await {
+        Ok(_) => {}
+        Err(e) => failures.push(format!("Gateway contract: {e}")),
+    }
+    match PortalContract::new(config).await {
+        Ok(_) => {}
+        Err(e) => failures.push(format!("Portal contract: {e}")),
+    }
+
+    // the wallet key (if any) is valid
+    match &config.ethereum_wallet_key {
+        Some(key) => match EthereumChain::get_public_address(key).await {
+            Ok(_) => {}
+            Err(e) => failures.push(format!("Ethereum wallet: {e}")),
+        },
+        None => {}
+    }
+
+    // alert sinks accept connections (teams is left out since any request to it posts a message)
+    match EmailClient::from_config(config) {
+        Ok(Some(email_client)) => match email_client.test_connection().await {
+            Ok(_) => {}
+            Err(e) => failures.push(format!("SMTP server: {e}")),
+        },
+        Ok(None) => {}
+        Err(e) => failures.push(format!("Email alerts: {e}")),
+    }
+    let client = config.http_client(None)?;
+    match &config.heartbeat_url {
+        Some(url) => match check_url(&client, url).await {
+            Ok(_) => {}
+            Err(e) => failures.push(format!("Heartbeat url: {e}")),
+        },
+        None => {}
+    }
+    match &config.deadmanswitch_url {
+        // signal a start rather than a success so the switch isn't marked healthy before the first cycle
+        Some(url) => match check_url(&client, &format!("{}/start", url.trim_end_matches('/'))).await {
+            Ok(_) => {}
+            Err(e) => failures.push(format!("Dead man's switch url: {e}")),
+        },
+        None => {}
+    }
+
+    if !failures.is_empty() {
+        let report = redact_secrets(&failures.join("\n- "), &config.secrets());
+        return Err(anyhow::anyhow!("Preflight checks failed:\n- {report}"));
+    }
+    log::info!("Preflight checks passed.");
+    Ok(())
+}
+
+// validates a config file without starting any watchers (with connectivity set the preflight checks are also run)
+pub async fn validate_config_file(file_path: &str, connectivity: bool) -> Result<()> {
+    let configs = load_configs(file_path)?;
+    let mut failures: Vec<String> = vec![];
+    for config in &configs {
+        let name = config.deployment_name.clone().unwrap_or(String::from("default"));
+        match connectivity {
+            true => match preflight(config).await {
+                Ok(_) => {}
+                Err(e) => failures.push(format!("[{name}] {e}")),
+            },
+            false => match EmailClient::from_config(config) {
+                Ok(_) => {}
+                Err(e) => failures.push(format!(
+                    "[{name}] Email alerts: {}",
+                    redact_secrets(&e.to_string(), &config.secrets())
+                )),
+            },
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Config {file_path} is invalid:\n{}",
+            failures.join("\n")
+        ));
+    }
+    Ok(())
+}
+
+async fn check_url(client: &Client, url: &str) -> Result<()> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("returned status {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{abi_bool, test_config_builder, MockRpc};
+    use serde_json::{json, Value};
+    use std::fs;
+
+    // the example config pointed at local endpoints
+    fn example_config() -> Value {
+        let mut config: Value = serde_json::from_str(include_str!("../watchtower_config.json.example")).unwrap();
+        config["fuel_graphql"] = Value::from("http://localhost:4000/graphql");
+        config["ethereum_rpc"] = Value::from("http://localhost:8545");
+        config
+    }
+
+    // validates the given config file contents (without connectivity checks)
+    async fn validate(name: &str, contents: &str) -> Result<()> {
+        let path = std::env::temp_dir().join(format!("watchtower-validate-{name}-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, contents).unwrap();
+        let result = validate_config_file(path, false).await;
+        fs::remove_file(path).unwrap();
+        result
+    }
+
+    #[tokio::test]
+    async fn preflight_reports_an_ethereum_rpc_on_the_wrong_chain() {
+        let rpc = MockRpc::start(|method, _| match method {
+            "eth_call" => Some(Ok(abi_bool(false))),
+            _ => None,
+        });
+        let mut config = test_config_builder(&rpc.url).build().unwrap();
+        config.expected_ethereum_chain_id = Some(5);
+        let report = preflight(&config).await.unwrap_err().to_string();
+        assert!(report.starts_with("Preflight checks failed:"));
+        assert!(report.contains(
+            "- Ethereum RPC: Connected to the wrong chain: ethereum rpc is on chain 1 but expected_ethereum_chain_id is 5"
+        ));
+
+        // the contracts answer on any chain, so only the chain check is reported
+        assert!(!report.contains("contract:"));
+
+        // (the fuel graphql endpoint isn't running, so preflight still fails, but not on the ethereum rpc)
+        config.expected_ethereum_chain_id = Some(1);
+        match preflight(&config).await {
+            Ok(_) => {}
+            Err(e) => assert!(!e.to_string().contains("Ethereum RPC")),
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_config_files_pass_validation() {
+        let config = example_config();
+        assert!(validate("single", &config.to_string()).await.is_ok());
+
+        let mut testnet = config.clone();
+        testnet["deployment_name"] = Value::from("testnet");
+        let mut mainnet = config;
+        mainnet["deployment_name"] = Value::from("mainnet");
+        assert!(validate("multiple", &json!([testnet, mainnet]).to_string())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn invalid_config_files_fail_validation() {
+        let error = validate("malformed", "{ \"fuel_graphql\": ").await.unwrap_err();
+        assert!(error.to_string().contains("Failed to parse"));
+
+        // several deployments need telling apart
+        let config = example_config();
+        let error = validate("unnamed", &json!([config.clone(), config.clone()]).to_string())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("A deployment_name is required"));
+
+        // email alerts need a sender
+        let mut no_sender = config;
+        no_sender["deployment_name"] = Value::from("testnet");
+        no_sender["smtp_host"] = Value::from("smtp.example.com");
+        no_sender["email_to"] = json!(["oncall@example.com"]);
+        let error = validate("no-sender", &no_sender.to_string())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("is invalid:"));
+        assert!(error.contains("[testnet] Email alerts: email_from must be set when smtp_host is set."));
+    }
+}
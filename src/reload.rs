@@ -0,0 +1,199 @@
+use crate::config::{load_configs, EthereumClientWatcher, FuelClientWatcher, WatchtowerConfig};
+
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task::JoinHandle;
+
+// the parts of a config that can be swapped out while the watchtower is running
+#[derive(Clone, Debug)]
+pub struct LiveWatchConfig {
+    pub fuel_client_watcher: Arc<RwLock<FuelClientWatcher>>,
+    pub ethereum_client_watcher: Arc<RwLock<EthereumClientWatcher>>,
+}
+
+impl LiveWatchConfig {
+    pub fn new(config: &WatchtowerConfig) -> Self {
+        LiveWatchConfig {
+            fuel_client_watcher: Arc::new(RwLock::new(config.fuel_client_watcher.clone())),
+            ethereum_client_watcher: Arc::new(RwLock::new(config.ethereum_client_watcher.clone())),
+        }
+    }
+
+    // swaps in the watcher settings from a reloaded config
+    pub fn update(&self, config: &WatchtowerConfig) {
+        *self.fuel_client_watcher.write().unwrap() = config.fuel_client_watcher.clone();
+        *self.ethereum_client_watcher.write().unwrap() = config.ethereum_client_watcher.clone();
+    }
+}
+
+// re-reads the config file on SIGHUP and swaps in the watcher settings of each running deployment
+pub fn start_config_reloader(
+    config_file: &str,
+    deployments: Vec<(WatchtowerConfig, LiveWatchConfig)>,
+) -> Result<JoinHandle<()>> {
+    let mut hangup = signal(SignalKind::hangup())?;
+    let config_file = String::from(config_file);
+    let handle = tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            log::info!("Reloading config file: {}", config_file);
+            match load_configs(&config_file) {
+                Ok(configs) => reload_deployments(&configs, &deployments),
+                Err(e) => log::warn!("Failed to reload config (keeping the current config): {e}"),
+            }
+        }
+    });
+    Ok(handle)
+}
+
+// applies reloaded configs to the running deployments they match (by deployment name)
+fn reload_deployments(configs: &[WatchtowerConfig], deployments: &[(WatchtowerConfig, LiveWatchConfig)]) {
+    for (running_config, live_watch_config) in deployments {
+        let name = running_config
+            .deployment_name
+            .clone()
+            .unwrap_or(String::from("default"));
+        let reloaded_config = configs
+            .iter()
+            .find(|config| config.deployment_name == running_config.deployment_name);
+        match reloaded_config {
+            Some(reloaded_config) => match get_changed_static_field(running_config, reloaded_config) {
+                Some(field) => log::warn!(
+                    "Ignoring reloaded config for deployment {name}: {field} cannot be changed without a restart."
+                ),
+                None => {
+                    live_watch_config.update(reloaded_config);
+                    log::info!("Reloaded watcher config for deployment {name}.");
+                    let ignored_fields = get_ignored_changed_fields(running_config, reloaded_config);
+                    if !ignored_fields.is_empty() {
+                        log::warn!(
+                            "Ignoring changes to {} in the reloaded config for deployment {name}: only fuel_client_watcher and ethereum_client_watcher are applied without a restart.",
+                            ignored_fields.join(", ")
+                        );
+                    }
+                }
+            },
+            None => log::warn!("Reloaded config is missing deployment {name}. Keeping the current config."),
+        }
+    }
+}
+
+// gets the first field that changed but can only be applied by restarting (connections and contracts are set up once)
+fn get_changed_static_field(current: &WatchtowerConfig, reloaded: &WatchtowerConfig) -> Option<&'static str> {
+    if current.fuel_graphql != reloaded.fuel_graphql {
+        return Some("fuel_graphql");
+    }
+    if current.ethereum_rpc != reloaded.ethereum_rpc {
+        return Some("ethereum_rpc");
+    }
+    if current.ethereum_ws_rpc != reloaded.ethereum_ws_rpc {
+        return Some("ethereum_ws_rpc");
+    }
+    if current.ethereum_rpc_secondary != reloaded.ethereum_rpc_secondary {
+        return Some("ethereum_rpc_secondary");
+    }
+    if current.max_concurrent_rpc != reloaded.max_concurrent_rpc {
+        return Some("max_concurrent_rpc");
+    }
+    if current.state_contract_address != reloaded.state_contract_address {
+        return Some("state_contract_address");
+    }
+    if current.portal_contract_address != reloaded.portal_contract_address {
+        return Some("portal_contract_address");
+    }
+    if current.gateway_contract_address != reloaded.gateway_contract_address {
+        return Some("gateway_contract_address");
+    }
+    None
+}
+
+// gets the other top-level fields that changed, which are left as they are until a restart
+// (compared in their serialized form so no field can be missed)
+fn get_ignored_changed_fields(current: &WatchtowerConfig, reloaded: &WatchtowerConfig) -> Vec<String> {
+    let (current, reloaded) = match (serde_json::to_value(current), serde_json::to_value(reloaded)) {
+        (Ok(Value::Object(current)), Ok(Value::Object(reloaded))) => (current, reloaded),
+        _ => {
+            log::warn!("Failed to compare the reloaded config to the running config.");
+            return vec![];
+        }
+    };
+    current
+        .iter()
+        .filter(|(field, value)| {
+            field.as_str() != "fuel_client_watcher"
+                && field.as_str() != "ethereum_client_watcher"
+                && reloaded.get(field.as_str()) != Some(value)
+        })
+        .map(|(field, _)| field.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // writes the example config (pointed at local endpoints) with the given fuel max block time
+    fn write_config(path: &str, max_block_time: u32) {
+        let mut config: Value = serde_json::from_str(include_str!("../watchtower_config.json.example")).unwrap();
+        config["fuel_graphql"] = Value::from("http://localhost:4000/graphql");
+        config["ethereum_rpc"] = Value::from("http://localhost:8545");
+        config["fuel_client_watcher"]["block_production_alert"]["max_block_time"] = Value::from(max_block_time);
+        fs::write(path, config.to_string()).unwrap();
+    }
+
+    // loads a config written by write_config
+    fn load_config(name: &str, max_block_time: u32) -> WatchtowerConfig {
+        let path = std::env::temp_dir().join(format!("watchtower-reload-{name}-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_config(path, max_block_time);
+        let config = load_configs(path).unwrap().remove(0);
+        fs::remove_file(path).unwrap();
+        config
+    }
+
+    fn max_block_time(live_watch_config: &LiveWatchConfig) -> u32 {
+        live_watch_config
+            .fuel_client_watcher
+            .read()
+            .unwrap()
+            .block_production_alert
+            .max_block_time
+    }
+
+    #[test]
+    fn reload_applies_a_changed_threshold() {
+        let config = load_config("running", 10);
+        let live_watch_config = LiveWatchConfig::new(&config);
+        let reloaded_config = load_config("reloaded", 25);
+
+        reload_deployments(&[reloaded_config], &[(config, live_watch_config.clone())]);
+        assert_eq!(max_block_time(&live_watch_config), 25);
+    }
+
+    #[test]
+    fn reload_keeps_the_config_when_a_static_field_changed() {
+        let config = load_config("static-running", 10);
+        let live_watch_config = LiveWatchConfig::new(&config);
+        let mut reloaded_config = load_config("static-reloaded", 25);
+        reloaded_config.ethereum_rpc = String::from("http://localhost:9545");
+
+        reload_deployments(&[reloaded_config], &[(config, live_watch_config.clone())]);
+        assert_eq!(max_block_time(&live_watch_config), 10);
+    }
+
+    #[test]
+    fn changes_outside_the_watchers_are_named() {
+        let current = WatchtowerConfig::default();
+        let mut reloaded = current.clone();
+        reloaded.duplicate_alert_delay += 60;
+        reloaded.system_name = String::from("Other Watchtower");
+        reloaded.fuel_client_watcher.block_production_alert.max_block_time += 5;
+
+        let mut ignored_fields = get_ignored_changed_fields(&current, &reloaded);
+        ignored_fields.sort();
+        assert_eq!(ignored_fields, vec!["duplicate_alert_delay", "system_name"]);
+        assert!(get_ignored_changed_fields(&current, &current.clone()).is_empty());
+    }
+}
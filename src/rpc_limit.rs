@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// the limiter shared by every rpc provider in the process (set up by the first provider, and load_configs
+// rejects deployments that disagree on max_concurrent_rpc so it is the same for all of them)
+static RPC_LIMITER: OnceLock<RpcLimiter> = OnceLock::new();
+
+// caps the number of rpc requests in flight at once (a burst of checks would otherwise get throttled by a
+// rate limited rpc), requests over the cap wait for a permit instead of failing
+#[derive(Clone, Debug, Default)]
+pub struct RpcLimiter {
+    permits: Option<Arc<Semaphore>>,
+    // counts the requests run through the limiter so tests can see how many a query takes
+    #[cfg(test)]
+    requests: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl RpcLimiter {
+    pub fn new(max_concurrent_rpc: Option<usize>) -> Self {
+        RpcLimiter {
+            permits: max_concurrent_rpc.map(|max_concurrent_rpc| Arc::new(Semaphore::new(max_concurrent_rpc))),
+            #[cfg(test)]
+            requests: Arc::default(),
+        }
+    }
+
+    // gets the limiter shared by the process
+    pub fn shared(max_concurrent_rpc: Option<usize>) -> Self {
+        RPC_LIMITER.get_or_init(|| RpcLimiter::new(max_concurrent_rpc)).clone()
+    }
+
+    // waits for a permit (none when requests aren't capped), held until it is dropped
+    // (the semaphore is never closed)
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.permits {
+            Some(permits) => permits.clone().acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+
+    // runs a request while holding a permit
+    pub async fn run<F: Future>(&self, request: F) -> F::Output {
+        let _permit = self.acquire().await;
+        #[cfg(test)]
+        self.requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        request.await
+    }
+
+    // gets the number of requests run through the limiter (or its clones) so far
+    #[cfg(test)]
+    pub fn requests(&self) -> usize {
+        self.requests.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+// an http transport that waits for a permit before each request so no more than max_concurrent_rpc
+// requests are ever in flight
+#[derive(Clone, Debug)]
+pub struct LimitedHttp {
+    http: Http,
+    limiter: RpcLimiter,
+}
+
+impl LimitedHttp {
+    pub fn new(http: Http, max_concurrent_rpc: Option<usize>) -> Self {
+        LimitedHttp {
+            http,
+            limiter: RpcLimiter::shared(max_concurrent_rpc),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for LimitedHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        // the permit is held until the response arrives
+        self.limiter.run(self.http.request(method, params)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn limiter_caps_requests_in_flight() {
+        let limiter = RpcLimiter::new(Some(3));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..20)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                tokio::spawn(async move {
+                    limiter
+                        .run(async {
+                            let now_in_flight = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                            max_in_flight.fetch_max(now_in_flight, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(5)).await;
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn uncapped_limiter_never_waits() {
+        let limiter = RpcLimiter::new(None);
+        let mut permits = vec![];
+        for _ in 0..100 {
+            permits.push(limiter.acquire().await);
+        }
+        assert!(permits.iter().all(|permit| permit.is_none()));
+        assert_eq!(limiter.run(async { 1 }).await, 1);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::alerts::{AlertLevel, AlertType, Chain};
+
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+static RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug)]
+pub struct TeamsClient {
+    client: Client,
+    webhook_url: String,
+    system_name: String,
+    source: String,
+    component: Option<String>,
+    retries: u32,
+}
+
+impl TeamsClient {
+    pub fn new(
+        client: Client,
+        webhook_url: &str,
+        system_name: &str,
+        source: &str,
+        component: Option<&str>,
+        retries: u32,
+    ) -> Self {
+        TeamsClient {
+            client,
+            webhook_url: String::from(webhook_url),
+            system_name: String::from(system_name),
+            source: String::from(source),
+            component: component.map(String::from),
+            retries,
+        }
+    }
+
+    pub async fn send_alert(
+        &self,
+        text: &str,
+        level: &AlertLevel,
+        alert_type: AlertType,
+        chain: Chain,
+        context: Option<&Value>,
+    ) -> Result<()> {
+        let card = self.build_message_card(text, level, alert_type, chain, context);
+        self.post_card(&card).await
+    }
+
+    // posts a card, retrying with backoff so a brief network blip doesn't drop an alert
+    async fn post_card(&self, card: &Value) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match self.try_post_card(card).await {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    if attempt >= self.retries {
+                        return Err(e);
+                    }
+                    log::warn!("Failed to post to Teams (attempt {}). Retrying.", attempt + 1);
+                    tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn try_post_card(&self, card: &Value) -> Result<()> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .header("Content-Type", "application/json")
+            .body(card.to_string())
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Teams webhook returned status: {}", response.status()));
+        }
+        Ok(())
+    }
+
+    // builds the legacy connector MessageCard payload accepted by Teams incoming webhooks
+    // (any structured alert context is added as facts so it can be read without parsing the text)
+    // (component, group and class are set at the top level for routing, with the chain as group and alert type as class)
+    pub fn build_message_card(
+        &self,
+        text: &str,
+        level: &AlertLevel,
+        alert_type: AlertType,
+        chain: Chain,
+        context: Option<&Value>,
+    ) -> Value {
+        let theme_color = match level {
+            AlertLevel::Error => "D70000",
+            AlertLevel::Warn => "FFA500",
+            _ => "0078D7",
+        };
+        let mut facts = vec![
+            json!({ "name": "Alert Type", "value": format!("{:?}", alert_type) }),
+            json!({ "name": "Chain", "value": format!("{:?}", chain) }),
+            json!({ "name": "System", "value": self.system_name }),
+            json!({ "name": "Source", "value": self.source }),
+        ];
+        match &self.component {
+            Some(component) => facts.push(json!({ "name": "Component", "value": component })),
+            None => {}
+        }
+        match context {
+            Some(Value::Object(fields)) => {
+                for (name, value) in fields {
+                    let value = match value {
+                        Value::String(value) => value.clone(),
+                        _ => value.to_string(),
+                    };
+                    facts.push(json!({ "name": name, "value": value }));
+                }
+            }
+            _ => {}
+        }
+        json!({
+            "@type": "MessageCard",
+            "@context": "http://schema.org/extensions",
+            "themeColor": theme_color,
+            "summary": text,
+            "sections": [{
+                "activityTitle": format!("{} {:?}", self.system_name, level),
+                "text": text,
+                "facts": facts,
+            }],
+            "alertContext": context,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    // serves a webhook answering with the given statuses in turn (then 200), counting the requests it gets
+    fn serve_webhook(statuses: Vec<u16>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    match line.strip_prefix("content-length:") {
+                        Some(length) => content_length = length.trim().parse().unwrap(),
+                        None => {}
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let status = match statuses.get(counter.fetch_add(1, Ordering::SeqCst)) {
+                    Some(status) => *status,
+                    None => 200,
+                };
+                let response = format!("HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn teams_client(url: &str, retries: u32) -> TeamsClient {
+        TeamsClient::new(Client::new(), url, "Watchtower", "test", None, retries)
+    }
+
+    #[tokio::test]
+    async fn transient_failure_succeeds_on_retry() {
+        let (url, requests) = serve_webhook(vec![503]);
+        let client = teams_client(&url, 3);
+        let result = client
+            .send_alert(
+                "down",
+                &AlertLevel::Error,
+                AlertType::EthereumConnection,
+                Chain::Ethereum,
+                None,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn persistent_failure_exhausts_retries() {
+        let (url, requests) = serve_webhook(vec![500, 500, 500]);
+        let client = teams_client(&url, 1);
+        let result = client
+            .send_alert(
+                "down",
+                &AlertLevel::Error,
+                AlertType::EthereumConnection,
+                Chain::Ethereum,
+                None,
+            )
+            .await;
+        assert!(result.unwrap_err().to_string().contains("500"));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn card_includes_routing_fields() {
+        let client = TeamsClient::new(
+            Client::new(),
+            "http://localhost",
+            "Watchtower",
+            "test",
+            Some("bridge"),
+            0,
+        );
+        let context = json!({ "token": "0xaa" });
+        let card = client.build_message_card(
+            "deposit",
+            &AlertLevel::Warn,
+            AlertType::EthereumGatewayDeposit,
+            Chain::Ethereum,
+            Some(&context),
+        );
+        let facts = card["sections"][0]["facts"].as_array().unwrap();
+        assert!(facts.contains(&json!({ "name": "Component", "value": "bridge" })));
+        assert!(facts.contains(&json!({ "name": "Chain", "value": "Ethereum" })));
+        assert!(facts.contains(&json!({ "name": "Alert Type", "value": "EthereumGatewayDeposit" })));
+        assert!(facts.contains(&json!({ "name": "token", "value": "0xaa" })));
+        assert_eq!(card["alertContext"]["token"], "0xaa");
+        assert_eq!(card["component"], Value::Null);
+
+        let client = teams_client("http://localhost", 0);
+        let card = client.build_message_card("down", &AlertLevel::Error, AlertType::FuelConnection, Chain::Fuel, None);
+        let facts = card["sections"][0]["facts"].as_array().unwrap();
+        assert!(!facts.iter().any(|fact| fact["name"] == "Component"));
+        assert!(facts.contains(&json!({ "name": "Chain", "value": "Fuel" })));
+    }
+}
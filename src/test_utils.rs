@@ -0,0 +1,389 @@
+use crate::config::WatchtowerConfigBuilder;
+use crate::email::EmailTransport;
+use crate::message_bus::MessageBusTransport;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::types::{Block, Transaction, TransactionReceipt, H256, U256};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tungstenite::Message;
+
+pub static STATE_CONTRACT_ADDRESS: &str = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+pub static PORTAL_CONTRACT_ADDRESS: &str = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512";
+pub static GATEWAY_CONTRACT_ADDRESS: &str = "0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0";
+
+// the first anvil account's key
+pub static WALLET_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+// the hash given to every transaction sent through a mock rpc
+pub static TX_HASH: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+// a json-rpc answer from a mock rpc handler (an error is returned to the client as a json-rpc error)
+pub type RpcResponse = Option<std::result::Result<Value, String>>;
+
+// a local json-rpc server standing in for an ethereum rpc
+// each request is answered by the handler, falling back to chain id 1 and block 100 when it has no answer
+pub struct MockRpc {
+    pub url: String,
+    requests: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl MockRpc {
+    pub fn start(handler: impl Fn(&str, &Value) -> RpcResponse + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let thread_requests = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        break;
+                    }
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    match line.strip_prefix("content-length:") {
+                        Some(length) => content_length = length.trim().parse().unwrap(),
+                        None => {}
+                    }
+                }
+                let mut body = vec![0; content_length];
+                if reader.read_exact(&mut body).is_err() {
+                    continue;
+                }
+                let request: Value = match serde_json::from_slice(&body) {
+                    Ok(request) => request,
+                    Err(_) => continue,
+                };
+                let method = request["method"].as_str().unwrap_or_default().to_string();
+                let params = request["params"].clone();
+                thread_requests.lock().unwrap().push((method.clone(), params.clone()));
+
+                let result = match handler(&method, &params) {
+                    Some(result) => result,
+                    None => match method.as_str() {
+                        "eth_chainId" => Ok(json!("0x1")),
+                        "eth_blockNumber" => Ok(json!("0x64")),
+                        _ => Err(format!("{method} is not mocked")),
+                    },
+                };
+                let response = match result {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                    Err(message) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": { "code": -32000, "message": message },
+                    }),
+                };
+                let response = response.to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                    response.len()
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        MockRpc { url, requests }
+    }
+
+    // gets the params of each request made for the given method (oldest first)
+    pub fn requests(&self, method: &str) -> Vec<Value> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(request_method, _)| request_method == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+}
+
+// a local websocket json-rpc server that accepts a new heads subscription and pushes a block with each of the given
+// timestamps (the connection is then held open so the subscription doesn't end)
+pub struct MockWs {
+    pub url: String,
+}
+
+impl MockWs {
+    pub fn start(block_timestamps: Vec<u64>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut socket = match stream.map(tungstenite::accept) {
+                    Ok(Ok(socket)) => socket,
+                    _ => continue,
+                };
+                while let Ok(message) = socket.read_message() {
+                    let request: Value = match message {
+                        Message::Text(text) => serde_json::from_str(&text).unwrap_or_default(),
+                        _ => continue,
+                    };
+                    let response = match request["method"].as_str() {
+                        Some("eth_subscribe") => json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x1" }),
+                        _ => json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "error": { "code": -32000, "message": "not mocked" },
+                        }),
+                    };
+                    let _ = socket.write_message(Message::Text(response.to_string()));
+                    if request["method"] != "eth_subscribe" {
+                        continue;
+                    }
+                    for (i, timestamp) in block_timestamps.iter().enumerate() {
+                        let head = json!({
+                            "jsonrpc": "2.0",
+                            "method": "eth_subscription",
+                            "params": {
+                                "subscription": "0x1",
+                                "result": { "number": format!("{:#x}", 100 + i), "timestamp": format!("{timestamp:#x}") },
+                            },
+                        });
+                        let _ = socket.write_message(Message::Text(head.to_string()));
+                    }
+                }
+            }
+        });
+        MockWs { url }
+    }
+}
+
+// an http request received by a mock http server
+#[derive(Clone, Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+// a local http server (like a webhook or a ping url) that records each request and answers it with the next of
+// the given statuses (200 once they run out)
+pub struct MockHttp {
+    pub url: String,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl MockHttp {
+    pub fn start(statuses: Vec<u16>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let thread_requests = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        break;
+                    }
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    match line.strip_prefix("content-length:") {
+                        Some(length) => content_length = length.trim().parse().unwrap(),
+                        None => {}
+                    }
+                }
+                let mut body = vec![0; content_length];
+                if reader.read_exact(&mut body).is_err() {
+                    continue;
+                }
+                let mut request_parts = request_line.split_whitespace();
+                let request = HttpRequest {
+                    method: request_parts.next().unwrap_or_default().to_string(),
+                    path: request_parts.next().unwrap_or_default().to_string(),
+                    body: String::from_utf8_lossy(&body).to_string(),
+                };
+                let status = {
+                    let mut requests = thread_requests.lock().unwrap();
+                    requests.push(request);
+                    match statuses.get(requests.len() - 1) {
+                        Some(status) => *status,
+                        None => 200,
+                    }
+                };
+                let response = format!("HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        MockHttp { url, requests }
+    }
+
+    // gets the requests received so far (oldest first)
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+// an email transport that keeps the messages sent through it (or fails every send)
+#[derive(Debug, Default)]
+pub struct FakeEmailTransport {
+    fail: bool,
+    sent: Mutex<Vec<lettre::Message>>,
+}
+
+impl FakeEmailTransport {
+    pub fn failing() -> Self {
+        FakeEmailTransport {
+            fail: true,
+            sent: Mutex::new(vec![]),
+        }
+    }
+
+    // gets the messages sent so far (oldest first)
+    pub fn sent(&self) -> Vec<lettre::Message> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl EmailTransport for FakeEmailTransport {
+    fn send(&self, message: &lettre::Message) -> Result<()> {
+        match self.fail {
+            true => Err(anyhow::anyhow!("smtp server unreachable")),
+            false => {
+                self.sent.lock().unwrap().push(message.clone());
+                Ok(())
+            }
+        }
+    }
+
+    fn test_connection(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// an in-memory message bus that keeps what is published to it (or never finishes publishing)
+#[derive(Default)]
+pub struct FakeMessageBus {
+    hang: bool,
+    published: Mutex<Vec<(String, Value)>>,
+}
+
+impl FakeMessageBus {
+    pub fn hanging() -> Self {
+        FakeMessageBus {
+            hang: true,
+            published: Mutex::new(vec![]),
+        }
+    }
+
+    // gets the subject and json payload of each message published so far (oldest first)
+    pub fn published(&self) -> Vec<(String, Value)> {
+        self.published.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl MessageBusTransport for FakeMessageBus {
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        if self.hang {
+            std::future::pending::<()>().await;
+        }
+        let payload = serde_json::from_slice(&payload)?;
+        self.published.lock().unwrap().push((String::from(subject), payload));
+        Ok(())
+    }
+}
+
+// a config builder pointed at the given ethereum rpc (and a fuel graphql endpoint that isn't used)
+pub fn test_config_builder(ethereum_rpc: &str) -> WatchtowerConfigBuilder {
+    WatchtowerConfigBuilder::new()
+        .fuel_graphql("http://localhost:4000/graphql")
+        .ethereum_rpc(ethereum_rpc)
+        .contract_addresses(
+            STATE_CONTRACT_ADDRESS,
+            PORTAL_CONTRACT_ADDRESS,
+            GATEWAY_CONTRACT_ADDRESS,
+        )
+}
+
+// encodes a word returned from a contract call
+pub fn abi_word(value: u64) -> Value {
+    json!(format!("0x{value:064x}"))
+}
+
+pub fn abi_bool(value: bool) -> Value {
+    abi_word(value as u64)
+}
+
+// gets the function selector of an eth_call or eth_estimateGas request
+pub fn call_selector(params: &Value) -> String {
+    let data = match params[0]["data"].as_str() {
+        Some(data) => data,
+        None => params[0]["input"].as_str().unwrap_or_default(),
+    };
+    data.chars().take(10).collect()
+}
+
+pub fn selector(signature: &str) -> String {
+    format!("0x{}", ethers::utils::hex::encode(ethers::utils::id(signature)))
+}
+
+// answers the requests a signer makes to fill in and send a transaction (nonce, fees and gas)
+pub fn send_tx_response(method: &str) -> RpcResponse {
+    let gwei = U256::exp10(9);
+    match method {
+        "eth_getTransactionCount" => Some(Ok(json!("0x0"))),
+        "eth_getBlockByNumber" => Some(Ok(serde_json::to_value(Block::<H256> {
+            number: Some(100.into()),
+            base_fee_per_gas: Some(gwei),
+            ..Default::default()
+        })
+        .unwrap())),
+        "eth_feeHistory" => Some(Ok(json!({
+            "oldestBlock": "0x64",
+            "baseFeePerGas": [gwei],
+            "gasUsedRatio": [0.5],
+            "reward": [[gwei]],
+        }))),
+        "eth_estimateGas" => Some(Ok(json!("0x186a0"))),
+        "eth_sendRawTransaction" => Some(Ok(json!(TX_HASH))),
+        _ => None,
+    }
+}
+
+// the sent transaction once it has been mined in the given block
+pub fn mined_tx(block: u64) -> Value {
+    serde_json::to_value(Transaction {
+        hash: TX_HASH.parse().unwrap(),
+        block_number: Some(block.into()),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+// the receipt of the sent transaction (mined in the given block)
+pub fn tx_receipt(block: u64, success: bool) -> Value {
+    serde_json::to_value(TransactionReceipt {
+        transaction_hash: TX_HASH.parse().unwrap(),
+        block_number: Some(block.into()),
+        status: Some((success as u64).into()),
+        ..Default::default()
+    })
+    .unwrap()
+}
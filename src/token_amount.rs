@@ -0,0 +1,156 @@
+use ethers::types::U256;
+use std::cmp::{max, Ordering};
+use std::fmt;
+
+// an on-chain amount along with the decimals it is scaled by, so amounts are only ever compared at the same scale
+// (displays as the raw amount to match how amounts have always been reported)
+#[derive(Clone, Copy, Debug)]
+pub struct TokenAmount {
+    raw: U256,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: impl Into<U256>, decimals: u8) -> Self {
+        TokenAmount {
+            raw: raw.into(),
+            decimals,
+        }
+    }
+
+    // scales a human readable value (like a config threshold) up by the given decimals
+    pub fn from_float(value: f64, decimals: u8) -> Self {
+        // scale in two steps so the float only has to hold up to 9 decimals of precision
+        let decimals_p1 = if decimals < 9 { decimals } else { decimals - 9 };
+        let decimals_p2 = decimals - decimals_p1;
+
+        let value = value * 10f64.powf(decimals_p1 as f64);
+        let raw = U256::from(value as u64).saturating_mul(U256::exp10(decimals_p2 as usize));
+        TokenAmount { raw, decimals }
+    }
+
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    // gets the same amount at a different scale (scaling down truncates and scaling up saturates)
+    pub fn rescale(&self, decimals: u8) -> Self {
+        let raw = match decimals.cmp(&self.decimals) {
+            Ordering::Equal => self.raw,
+            Ordering::Greater => self
+                .raw
+                .saturating_mul(U256::exp10((decimals - self.decimals) as usize)),
+            Ordering::Less => self.raw / U256::exp10((self.decimals - decimals) as usize),
+        };
+        TokenAmount { raw, decimals }
+    }
+}
+
+// where an amount falls against a threshold (with an optional warning band just below it)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThresholdBand {
+    Under,
+    Warning,
+    Over,
+}
+
+// gets the band an amount falls in, given the threshold and the fraction of it the warning band starts at
+// (both human readable values scaled up by the given decimals)
+pub fn get_threshold_band(
+    amount: TokenAmount,
+    threshold: f64,
+    warning_fraction: Option<f64>,
+    decimals: u8,
+) -> ThresholdBand {
+    if amount >= TokenAmount::from_float(threshold, decimals) {
+        return ThresholdBand::Over;
+    }
+    match warning_fraction {
+        Some(fraction) if amount >= TokenAmount::from_float(threshold * fraction, decimals) => ThresholdBand::Warning,
+        _ => ThresholdBand::Under,
+    }
+}
+
+impl PartialEq for TokenAmount {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TokenAmount {}
+
+impl PartialOrd for TokenAmount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TokenAmount {
+    // compares at the finer of the two scales so no precision is lost
+    fn cmp(&self, other: &Self) -> Ordering {
+        let decimals = max(self.decimals, other.decimals);
+        self.rescale(decimals).raw.cmp(&other.rescale(decimals).raw)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_float_scales_by_decimals() {
+        assert_eq!(TokenAmount::from_float(1.5, 6).raw(), U256::from(1_500_000u64));
+        assert_eq!(
+            TokenAmount::from_float(1.5, 18).raw(),
+            U256::from(1_500_000_000_000_000_000u64)
+        );
+        assert_eq!(TokenAmount::from_float(0.25, 2).raw(), U256::from(25u64));
+        assert_eq!(TokenAmount::from_float(2.0, 0).raw(), U256::from(2u64));
+    }
+
+    #[test]
+    fn amounts_compare_across_decimals() {
+        let six = TokenAmount::new(1_000_000u64, 6);
+        let eighteen = TokenAmount::new(1_000_000_000_000_000_000u64, 18);
+        assert_eq!(six, eighteen);
+
+        let more = TokenAmount::new(1_000_000_000_000_000_001u64, 18);
+        assert!(more > six);
+        assert!(six < more);
+        assert_eq!(max(six, more), more);
+    }
+
+    #[test]
+    fn rescale_truncates_down_and_scales_up() {
+        let amount = TokenAmount::new(1_234_567u64, 6);
+        assert_eq!(amount.rescale(3).raw(), U256::from(1_234u64));
+        assert_eq!(amount.rescale(9).raw(), U256::from(1_234_567_000u64));
+        assert_eq!(amount.rescale(6).raw(), U256::from(1_234_567u64));
+    }
+
+    #[test]
+    fn warning_band_starts_at_the_warning_fraction() {
+        let band = |amount: u64| get_threshold_band(TokenAmount::new(amount, 9), 100.0, Some(0.8), 9);
+        let gwei = 1_000_000_000;
+        assert_eq!(band(79 * gwei), ThresholdBand::Under);
+        assert_eq!(band(80 * gwei - 1), ThresholdBand::Under);
+        assert_eq!(band(80 * gwei), ThresholdBand::Warning);
+        assert_eq!(band(100 * gwei - 1), ThresholdBand::Warning);
+        assert_eq!(band(100 * gwei), ThresholdBand::Over);
+        assert_eq!(band(250 * gwei), ThresholdBand::Over);
+    }
+
+    #[test]
+    fn no_warning_band_without_a_warning_fraction() {
+        let eth = U256::exp10(18);
+        let band = |amount: U256| get_threshold_band(TokenAmount::new(amount, 18), 10.0, None, 18);
+        assert_eq!(band(eth * 9), ThresholdBand::Under);
+        assert_eq!(band(eth * 10), ThresholdBand::Over);
+    }
+}